@@ -1,60 +1,1331 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::header::HeaderValue;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::time::Duration;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::Read;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 
-const OPENROUTER_API_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+use crate::clock::{Clock, SystemClock};
+use crate::logger::Logger;
+use crate::sanitize;
+
+/// OpenRouter API base URL, used unless [`OpenRouterClient::with_base_url`]
+/// sets another.
+pub const DEFAULT_BASE_URL: &str = "https://openrouter.ai/api/v1";
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
 
+/// Error message for requests cut off by the run deadline.
+pub const RUN_DEADLINE_EXCEEDED: &str = "Run deadline exceeded";
+
+/// Error message for requests refused because the cost cap was reached.
+pub const COST_CAP_REACHED: &str = "Cost cap reached";
+
+/// Token counts reported by the API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+
+    fn from_response(response: &Value) -> Self {
+        let field = |name: &str| response["usage"][name].as_u64().unwrap_or(0);
+        Self {
+            prompt_tokens: field("prompt_tokens"),
+            completion_tokens: field("completion_tokens"),
+            total_tokens: field("total_tokens"),
+        }
+    }
+}
+
+/// Optional generation parameters. `None` fields are omitted from the request
+/// so OpenRouter applies its own defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatParams {
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Nucleus sampling: only tokens within this cumulative probability are
+    /// considered.
+    pub top_p: Option<f32>,
+    /// Sampling seed. Only providers that support seeding honour it, so equal
+    /// seeds make output repeatable but not guaranteed identical.
+    pub seed: Option<u64>,
+    /// OpenRouter prompt transforms, e.g. `["middle-out"]`.
+    pub transforms: Option<Vec<String>>,
+    /// Function definitions the model may call, in OpenAI `tools` format.
+    pub tools: Option<Vec<Value>>,
+    /// Stream the reply and print it to stdout as it is generated. The
+    /// returned response is the same as without streaming.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+impl ChatParams {
+    fn apply(&self, body: &mut Value) {
+        if let Some(max_tokens) = self.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if let Some(seed) = self.seed {
+            body["seed"] = json!(seed);
+        }
+        if let Some(transforms) = &self.transforms {
+            body["transforms"] = json!(transforms);
+        }
+        if let Some(tools) = &self.tools {
+            body["tools"] = json!(tools);
+        }
+        if self.stream {
+            body["stream"] = json!(true);
+        }
+    }
+}
+
+/// A chat completion result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatResponse {
+    pub content: String,
+    pub finish_reason: Option<String>,
+    /// Separate reasoning text returned by reasoning models, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    /// Functions the model called, when the request offered `tools`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Tokens used by this request, when the API reported them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// A function call made by the model: the function name and its parsed
+/// JSON arguments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    pub arguments: Value,
+}
+
+impl ToolCall {
+    /// Reads one entry of a message's `tool_calls`, whose arguments arrive as
+    /// a JSON-encoded string.
+    fn from_json(call: &Value) -> Result<Self> {
+        let function = &call["function"];
+        let name = function["name"]
+            .as_str()
+            .context("Tool call without a function name")?
+            .to_string();
+        let arguments = match &function["arguments"] {
+            Value::String(encoded) => serde_json::from_str(encoded)
+                .with_context(|| format!("Invalid arguments in call to '{name}'"))?,
+            other => other.clone(),
+        };
+        Ok(Self { name, arguments })
+    }
+}
+
+impl ChatResponse {
+    /// Reads the first choice of an OpenRouter chat completion response,
+    /// including the `reasoning` field that reasoning models add next to the
+    /// answer and any `tool_calls`. A reply made only of tool calls may have
+    /// no content.
+    pub fn from_json(response: &Value) -> Result<Self> {
+        let choice = &response["choices"][0];
+        let tool_calls = match choice["message"]["tool_calls"].as_array() {
+            Some(calls) => calls
+                .iter()
+                .map(ToolCall::from_json)
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        let text = choice["message"]["content"].as_str().unwrap_or_default();
+        if text.trim().is_empty() && tool_calls.is_empty() {
+            if let Some(message) = soft_error_note(response) {
+                return Err(SoftApiError { message }.into());
+            }
+        }
+        let content = match choice["message"]["content"].as_str() {
+            Some(content) => content.to_string(),
+            None if !tool_calls.is_empty() => String::new(),
+            None => anyhow::bail!("No content in OpenRouter response"),
+        };
+        let reasoning = choice["message"]["reasoning"]
+            .as_str()
+            .filter(|r| !r.trim().is_empty())
+            .map(String::from);
+        Ok(Self {
+            content,
+            finish_reason: choice["finish_reason"].as_str().map(String::from),
+            reasoning,
+            tool_calls,
+            usage: response["usage"]
+                .is_object()
+                .then(|| Usage::from_response(response)),
+        })
+    }
+
+    /// Returns `true` if generation stopped because it hit the token limit.
+    pub fn is_truncated(&self) -> bool {
+        self.finish_reason.as_deref() == Some("length")
+    }
+}
+
+/// Reassembles a streamed chat completion (server-sent events) into the
+/// response the same request returns without streaming.
+#[derive(Debug, Default)]
+pub struct StreamAccumulator {
+    content: String,
+    reasoning: String,
+    finish_reason: Option<String>,
+    usage: Value,
+    error: Value,
+    done: bool,
+}
+
+impl StreamAccumulator {
+    /// Reads one line of the event stream and returns the reply text it
+    /// adds, if any. Comments, blank lines and `[DONE]` add nothing.
+    pub fn feed_line(&mut self, line: &str) -> Result<Option<String>> {
+        let Some(data) = line.trim_end().strip_prefix("data:") else {
+            return Ok(None);
+        };
+        let data = data.trim_start();
+        if data == "[DONE]" {
+            self.done = true;
+            return Ok(None);
+        }
+        let chunk: Value =
+            serde_json::from_str(data).context("Failed to parse OpenRouter stream chunk")?;
+        let choice = &chunk["choices"][0];
+        for error in [&chunk["error"], &choice["error"]] {
+            if !error.is_null() {
+                self.error = error.clone();
+            }
+        }
+        if chunk["usage"].is_object() {
+            self.usage = chunk["usage"].clone();
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            self.finish_reason = Some(reason.to_string());
+        }
+        if let Some(reasoning) = choice["delta"]["reasoning"].as_str() {
+            self.reasoning.push_str(reasoning);
+        }
+        let text = choice["delta"]["content"]
+            .as_str()
+            .filter(|t| !t.is_empty());
+        if let Some(text) = text {
+            self.content.push_str(text);
+        }
+        Ok(text.map(String::from))
+    }
+
+    /// Returns `true` once the stream has sent `[DONE]`.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns the reply in the shape of a non-streamed response. Text sent
+    /// before an error chunk is incomplete and left out, so the reply fails
+    /// as a [`SoftApiError`].
+    pub fn into_json(mut self) -> Value {
+        if !self.error.is_null() {
+            self.content.clear();
+        }
+        json!({
+            "choices": [{
+                "message": {"content": self.content, "reasoning": self.reasoning},
+                "finish_reason": self.finish_reason,
+            }],
+            "usage": self.usage,
+            "error": self.error,
+        })
+    }
+}
+
+/// Returns the error note of a reply that reports a failure despite HTTP 200:
+/// an `error` object at the top level or on the choice, or a finish reason of
+/// `error`.
+fn soft_error_note(response: &Value) -> Option<String> {
+    let choice = &response["choices"][0];
+    for error in [&response["error"], &choice["error"]] {
+        match error {
+            Value::String(message) => return Some(message.clone()),
+            Value::Object(_) => {
+                let message = error["message"].as_str().map(String::from);
+                return Some(message.unwrap_or_else(|| error.to_string()));
+            }
+            _ => {}
+        }
+    }
+    (choice["finish_reason"].as_str() == Some("error"))
+        .then(|| "finish reason \"error\"".to_string())
+}
+
+/// Price of a model in USD per million tokens.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPricing {
+    #[serde(default)]
+    pub prompt: f64,
+    #[serde(default)]
+    pub completion: f64,
+}
+
+impl ModelPricing {
+    /// Returns the cost of `usage` in USD.
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt
+            + usage.completion_tokens as f64 * self.completion)
+            / 1_000_000.0
+    }
+}
+
+/// Kind of network failure that prevented a request from completing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkErrorKind {
+    /// The API host name could not be resolved.
+    Dns,
+    /// The connection could not be established.
+    Connect,
+    /// The connection was reset or aborted by the peer.
+    Reset,
+    /// The request timed out.
+    Timeout,
+    /// The TLS handshake or certificate validation failed.
+    Tls,
+    /// Any other transport error.
+    Other,
+}
+
+impl NetworkErrorKind {
+    fn describe(self) -> &'static str {
+        match self {
+            NetworkErrorKind::Dns => "DNS lookup failed",
+            NetworkErrorKind::Connect => "connection failed",
+            NetworkErrorKind::Reset => "connection reset",
+            NetworkErrorKind::Timeout => "request timed out",
+            NetworkErrorKind::Tls => "TLS error",
+            NetworkErrorKind::Other => "network error",
+        }
+    }
+}
+
+/// A classified transport error from the HTTP client.
+#[derive(Debug)]
+pub struct NetworkError {
+    pub kind: NetworkErrorKind,
+    action: &'static str,
+    source: reqwest::Error,
+}
+
+impl NetworkError {
+    /// Classifies `source`, which interrupted `action` (e.g. "Failed to send
+    /// request to OpenRouter").
+    fn classify(action: &'static str, source: reqwest::Error) -> Self {
+        let mut kind = if source.is_timeout() {
+            NetworkErrorKind::Timeout
+        } else if source.is_connect() {
+            NetworkErrorKind::Connect
+        } else {
+            NetworkErrorKind::Other
+        };
+        // reqwest only flags timeouts and connect failures; the finer kinds are
+        // recognised from the underlying errors.
+        let mut cause: Option<&(dyn std::error::Error + 'static)> = Some(&source);
+        while let Some(err) = cause {
+            if let Some(io) = err.downcast_ref::<std::io::Error>() {
+                if matches!(
+                    io.kind(),
+                    std::io::ErrorKind::ConnectionReset
+                        | std::io::ErrorKind::ConnectionAborted
+                        | std::io::ErrorKind::BrokenPipe
+                ) {
+                    kind = NetworkErrorKind::Reset;
+                    break;
+                }
+            }
+            let message = err.to_string().to_lowercase();
+            if message.contains("dns error") || message.contains("failed to lookup address") {
+                kind = NetworkErrorKind::Dns;
+                break;
+            }
+            if message.contains("certificate") || message.contains("tls") || message.contains("ssl")
+            {
+                kind = NetworkErrorKind::Tls;
+                break;
+            }
+            cause = err.source();
+        }
+        Self {
+            kind,
+            action,
+            source,
+        }
+    }
+
+    /// Returns `true` for transient failures that are worth retrying: DNS,
+    /// connect, reset and timeout errors. TLS errors need fixing, not retries.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self.kind, NetworkErrorKind::Tls | NetworkErrorKind::Other)
+    }
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.action, self.kind.describe())?;
+        if self.kind == NetworkErrorKind::Tls {
+            write!(
+                f,
+                " (check the system clock, the CA certificates and any HTTPS proxy \
+                 intercepting the connection)"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// A non-success HTTP response from OpenRouter.
+#[derive(Debug)]
+pub struct ApiStatusError {
+    pub status: reqwest::StatusCode,
+    body: String,
+    /// Wait requested by the server's `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+impl ApiStatusError {
+    /// Returns `true` for rate limiting (429) and the transient server errors
+    /// (500, 502, 503, 504), which may succeed when repeated.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.status.as_u16(), 429 | 500 | 502 | 503 | 504)
+    }
+
+    /// Returns `true` if the error body names the upstream provider that
+    /// failed (`error.metadata.provider_name`), rather than OpenRouter itself.
+    fn is_provider_error(&self) -> bool {
+        serde_json::from_str::<Value>(&self.body).is_ok_and(|body| {
+            body["error"]["metadata"]["provider_name"]
+                .as_str()
+                .is_some_and(|name| !name.is_empty())
+        })
+    }
+}
+
+/// Parses a `Retry-After` header value: a number of seconds or an HTTP date.
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+impl std::fmt::Display for ApiStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OpenRouter API error ({}): {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for ApiStatusError {}
+
+/// A successful HTTP response with no content and an error note instead, as
+/// some providers send when the model is overloaded.
+#[derive(Debug)]
+pub struct SoftApiError {
+    pub message: String,
+}
+
+impl std::fmt::Display for SoftApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OpenRouter returned no content: {}", self.message)
+    }
+}
+
+impl std::error::Error for SoftApiError {}
+
+/// Returns `true` if a failed request is worth repeating: a transient
+/// network error, rate limiting, a server error or an empty reply with an
+/// error note.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    if let Some(e) = error.downcast_ref::<NetworkError>() {
+        return e.is_retryable();
+    }
+    if error.is::<SoftApiError>() {
+        return true;
+    }
+    error
+        .downcast_ref::<ApiStatusError>()
+        .is_some_and(ApiStatusError::is_retryable)
+}
+
+/// Returns `true` if a failed request may succeed with another model: the
+/// model is overloaded (529), rate limited (429) or unavailable (404, 502,
+/// 503), its provider reported the error, or the reply was empty with an
+/// error note. Invalid or oversized requests (400, 413), account errors and
+/// network errors are not model-specific.
+pub fn is_model_error(error: &anyhow::Error) -> bool {
+    if error.is::<SoftApiError>() {
+        return true;
+    }
+    error
+        .downcast_ref::<ApiStatusError>()
+        .is_some_and(|e| match e.status.as_u16() {
+            400 | 401 | 402 | 413 => false,
+            404 | 429 | 502 | 503 | 529 => true,
+            _ => e.is_provider_error(),
+        })
+}
+
+/// Backoff before the first retry of a request; it doubles with every
+/// further retry of the same request, up to [`MAX_RETRY_DELAY`].
+pub const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound on the backoff between two attempts of a request.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Retries of a single request by default.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Returns the backoff before retry number `retry` (1-based) of a request.
+pub fn retry_delay(retry: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Adds a random 0-25% to `delay`, so that parallel requests that failed
+/// together do not retry in lockstep.
+fn with_jitter(delay: Duration) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    delay + delay.mul_f64((random % 1000) as f64 / 4000.0)
+}
+
+/// Number of retries left for the whole run, shared by every request made
+/// through one client so a persistent outage cannot multiply the run time.
+#[derive(Debug)]
+pub struct RetryBudget {
+    remaining: AtomicU32,
+}
+
+impl RetryBudget {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            remaining: AtomicU32::new(retries),
+        }
+    }
+
+    /// Uses up one retry, returning `false` if none is left.
+    pub fn try_take(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// Returns the number of retries left.
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::Relaxed)
+    }
+}
+
+/// Spaces request starts evenly so that no more than `requests_per_minute`
+/// begin in any minute: a token bucket holding a single token, refilled every
+/// `60 / requests_per_minute` seconds, so bursts are smoothed out rather than
+/// let through and then blocked. Each caller reserves its slot before
+/// waiting, so concurrent callers queue up in turn.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Returns a limiter for `requests_per_minute`, or `None` for 0 (no limit).
+    pub fn per_minute(requests_per_minute: u32) -> Option<Self> {
+        (requests_per_minute > 0).then(|| Self {
+            interval: Duration::from_secs(60) / requests_per_minute,
+            next_slot: Mutex::new(None),
+        })
+    }
+
+    /// Reserves the next free slot at or after `now` and returns how long
+    /// the caller must wait for it.
+    pub fn reserve(&self, now: Instant) -> Duration {
+        let Ok(mut next_slot) = self.next_slot.lock() else {
+            return Duration::ZERO;
+        };
+        let slot = next_slot.map_or(now, |next| next.max(now));
+        *next_slot = Some(slot + self.interval);
+        slot - now
+    }
+}
+
+/// A chat-completion backend.
+///
+/// [`OpenRouterClient`] is the real implementation; the extraction and summary
+/// pipeline only depends on this trait so it can run against recorded
+/// interactions (see [`crate::cassette`]).
+#[async_trait]
+pub trait ChatApi: Send + Sync {
+    /// Sends a chat completion request with optional generation parameters and
+    /// returns the full response.
+    async fn chat_with_params(
+        &self,
+        model: &str,
+        messages: Vec<Value>,
+        params: &ChatParams,
+    ) -> Result<ChatResponse>;
+
+    /// Sends a chat completion request and returns the response content.
+    async fn chat(&self, model: &str, messages: Vec<Value>) -> Result<String> {
+        let response = self
+            .chat_with_params(model, messages, &ChatParams::default())
+            .await?;
+        Ok(response.content)
+    }
+
+    /// Returns `true` once the run deadline (if any) has passed.
+    fn deadline_exceeded(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` once the cost cap (if any) has been reached.
+    fn cost_cap_reached(&self) -> bool {
+        false
+    }
+
+    /// Returns the estimated cost of the run so far in USD.
+    fn estimated_cost(&self) -> f64 {
+        0.0
+    }
+}
+
 /// Wrapper around the OpenRouter API.
 pub struct OpenRouterClient {
     client: Client,
     api_key: String,
+    /// Chat completions endpoint.
+    api_url: String,
+    min_request_interval: Duration,
+    request_spacing: Mutex<RequestSpacing>,
+    deadline: Option<Instant>,
+    responses: AtomicU64,
+    response_wire_bytes: AtomicU64,
+    response_bytes: AtomicU64,
+    usage: Mutex<HashMap<String, Usage>>,
+    pricing: HashMap<String, ModelPricing>,
+    max_cost_usd: Option<f64>,
+    request_slots: Option<Semaphore>,
+    rate_limiter: Option<RateLimiter>,
+    logger: Option<Arc<Logger>>,
+    body_log_max_bytes: usize,
+    repair_escapes: bool,
+    retry_soft_errors: bool,
+    clock: Arc<dyn Clock>,
+    max_retries: u32,
+    retry_budget: Option<RetryBudget>,
+    /// `HTTP-Referer` and `X-Title` headers for OpenRouter app attribution.
+    attribution: Vec<(&'static str, HeaderValue)>,
+    /// Models tried in order when a model fails, by the model requested.
+    fallbacks: HashMap<String, Vec<String>>,
+    /// Where streamed replies are printed; stdout when unset.
+    stream_output: Option<Arc<Mutex<dyn std::io::Write + Send>>>,
+}
+
+/// Printed after the streamed text of an attempt that failed, before any
+/// retry or fallback prints its own reply.
+const DISCARDED_STREAM: &str = "\n--- reply interrupted; the text above is discarded ---\n";
+
+/// When requests last started and finished, for `min_request_interval`.
+#[derive(Debug, Default)]
+struct RequestSpacing {
+    last_start: Option<Instant>,
+    last_finished: Option<Instant>,
+}
+
+/// Compressions accepted for responses that are not streamed.
+const ACCEPTED_ENCODINGS: &str = "gzip, br, deflate";
+
+/// Responses received by an [`OpenRouterClient`] and their total size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResponseTraffic {
+    pub responses: u64,
+    /// Bytes received, before decompression.
+    pub wire_bytes: u64,
+    /// Bytes after decompression.
+    pub decoded_bytes: u64,
+}
+
+/// Undoes the `Content-Encoding` of a response body.
+fn decode_body(body: &[u8], encoding: Option<&str>) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let result = match encoding.map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("" | "identity") => return Ok(body.to_vec()),
+        Some("gzip" | "x-gzip") => flate2::read::GzDecoder::new(body).read_to_end(&mut decoded),
+        Some("deflate") => flate2::read::ZlibDecoder::new(body).read_to_end(&mut decoded),
+        Some("br") => brotli_decompressor::Decompressor::new(body, 4096).read_to_end(&mut decoded),
+        Some(other) => anyhow::bail!("Unsupported response encoding '{other}'"),
+    };
+    result.context("Failed to decompress the response")?;
+    Ok(decoded)
+}
+
+/// A response as read by [`OpenRouterClient::send`].
+struct RawResponse {
+    status: reqwest::StatusCode,
+    /// How long the `Retry-After` header asks to wait, if sent.
+    retry_after: Option<Duration>,
+    text: String,
+    /// Part of the reply was printed while it streamed in.
+    printed: bool,
+}
+
+/// Returns the chat completions endpoint of the API at `base_url`.
+fn chat_completions_url(base_url: &str) -> String {
+    format!("{}/chat/completions", base_url.trim_end_matches('/'))
 }
 
 impl OpenRouterClient {
     /// Creates a new client with the given API key and sensible defaults.
     pub fn new(api_key: String) -> Result<Self> {
+        // Responses are decompressed by `send` rather than by reqwest, which
+        // drops `Content-Length` and hands back only the decoded body, leaving
+        // no way to count the bytes received on the wire.
         let client = Client::builder()
             .timeout(REQUEST_TIMEOUT)
             .build()
             .context("Failed to build HTTP client")?;
 
-        Ok(Self { client, api_key })
+        Ok(Self {
+            client,
+            api_key,
+            api_url: chat_completions_url(DEFAULT_BASE_URL),
+            min_request_interval: Duration::ZERO,
+            request_spacing: Mutex::new(RequestSpacing::default()),
+            deadline: None,
+            responses: AtomicU64::new(0),
+            response_wire_bytes: AtomicU64::new(0),
+            response_bytes: AtomicU64::new(0),
+            usage: Mutex::new(HashMap::new()),
+            pricing: HashMap::new(),
+            max_cost_usd: None,
+            request_slots: None,
+            rate_limiter: None,
+            logger: None,
+            body_log_max_bytes: 0,
+            repair_escapes: true,
+            retry_soft_errors: true,
+            clock: Arc::new(SystemClock),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_budget: None,
+            attribution: Vec::new(),
+            fallbacks: HashMap::new(),
+            stream_output: None,
+        })
     }
 
-    /// Sends a chat completion request and returns the response content.
-    pub async fn chat(&self, model: &str, messages: Vec<Value>) -> Result<String> {
-        let body = json!({
-            "model": model,
-            "messages": messages,
-        });
+    /// Sends requests to the API at `base_url` (such as a proxy or a
+    /// self-hosted gateway) instead of OpenRouter; `/chat/completions` is
+    /// appended to it.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.api_url = chat_completions_url(base_url);
+        self
+    }
 
-        let response = self
+    /// Retries a request for `model` that fails with a model-specific error
+    /// (see [`is_model_error`]), once its retries are used up, with each of
+    /// `fallbacks` in turn.
+    pub fn with_fallbacks(mut self, model: &str, fallbacks: &[String]) -> Self {
+        let chain = self.fallbacks.entry(model.to_string()).or_default();
+        for fallback in fallbacks {
+            if fallback != model && !chain.contains(fallback) {
+                chain.push(fallback.clone());
+            }
+        }
+        self
+    }
+
+    /// Sends the `HTTP-Referer` and `X-Title` headers OpenRouter uses to
+    /// attribute requests to an app, each only when set. Fails if a value
+    /// contains control characters such as line breaks.
+    pub fn with_attribution(
+        mut self,
+        referer: Option<&str>,
+        app_title: Option<&str>,
+    ) -> Result<Self> {
+        for (header, value) in [("HTTP-Referer", referer), ("X-Title", app_title)] {
+            if let Some(value) = value {
+                let value = HeaderValue::from_str(value)
+                    .with_context(|| format!("{header} {value:?} is not a valid header value"))?;
+                self.attribution.push((header, value));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Prints streamed replies to `output` instead of stdout.
+    pub fn with_stream_output(mut self, output: Arc<Mutex<dyn std::io::Write + Send>>) -> Self {
+        self.stream_output = Some(output);
+        self
+    }
+
+    /// Sets per-model prices used to estimate the cost of the run.
+    pub fn with_pricing(mut self, pricing: HashMap<String, ModelPricing>) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Refuses new requests once the estimated cost reaches `max_cost_usd`.
+    pub fn with_cost_cap(mut self, max_cost_usd: Option<f64>) -> Self {
+        self.max_cost_usd = max_cost_usd;
+        self
+    }
+
+    /// Returns the accumulated token usage per model.
+    pub fn usage_by_model(&self) -> HashMap<String, Usage> {
+        self.usage.lock().map(|u| u.clone()).unwrap_or_default()
+    }
+
+    /// Returns the estimated cost so far in USD. Models without configured
+    /// pricing count as free.
+    pub fn estimated_cost(&self) -> f64 {
+        self.usage_by_model()
+            .iter()
+            .filter_map(|(model, usage)| self.pricing.get(model).map(|p| p.cost(usage)))
+            .sum()
+    }
+
+    /// Returns `true` once the estimated cost has reached the cost cap (if any).
+    pub fn cost_cap_reached(&self) -> bool {
+        self.max_cost_usd
+            .is_some_and(|cap| self.estimated_cost() >= cap)
+    }
+
+    /// Returns `true` if calls to `model` are priced and count toward the cap.
+    pub fn has_pricing(&self, model: &str) -> bool {
+        self.pricing.contains_key(model)
+    }
+
+    /// Cancels any request still running at `deadline` and fails later ones
+    /// immediately. `None` means no deadline.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Returns `true` once the run deadline (if any) has passed.
+    pub fn deadline_exceeded(&self) -> bool {
+        self.deadline.is_some_and(|d| self.clock.now() >= d)
+    }
+
+    /// Replaces the system clock used for request intervals, retry backoff
+    /// and the deadline, e.g. with a [`crate::clock::MockClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Retries each failed request (network errors, 429, 500, 502, 503 and
+    /// 504 responses) up to `max_retries` times with exponential backoff,
+    /// waiting as long as a `Retry-After` header asks instead where one is
+    /// sent (default [`DEFAULT_MAX_RETRIES`]; 0 disables retrying).
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Caps the retries of all requests together over the client's
+    /// lifetime at `retries` (unlimited by default).
+    pub fn with_retry_budget(mut self, retries: u32) -> Self {
+        self.retry_budget = Some(RetryBudget::new(retries));
+        self
+    }
+
+    /// Returns the number of retries left in the budget, if one is set.
+    pub fn retries_left(&self) -> Option<u32> {
+        self.retry_budget.as_ref().map(RetryBudget::remaining)
+    }
+
+    /// Reports repaired responses (and, with body logging, request bodies)
+    /// to `logger`.
+    pub fn with_logger(mut self, logger: Arc<Logger>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Replaces lone surrogate escapes in responses with U+FFFD before
+    /// parsing them (on by default); with it off such responses fail to parse.
+    pub fn with_escape_repair(mut self, enabled: bool) -> Self {
+        self.repair_escapes = enabled;
+        self
+    }
+
+    /// Retries replies that carry an error note instead of content, like
+    /// HTTP errors (on by default); with it off they fail right away.
+    pub fn with_soft_error_retry(mut self, enabled: bool) -> Self {
+        self.retry_soft_errors = enabled;
+        self
+    }
+
+    /// Logs every outgoing request body to the logger, with inline base64 data
+    /// replaced by its size and the result cut to `max_bytes` (0 = off).
+    /// Headers (and with them the API key) are never logged.
+    pub fn with_body_logging(mut self, max_bytes: usize) -> Self {
+        self.body_log_max_bytes = max_bytes;
+        self
+    }
+
+    /// Limits how many requests may be in flight at once across every caller
+    /// sharing this client (0 = unlimited).
+    pub fn with_max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.request_slots = (limit > 0).then(|| Semaphore::new(limit));
+        self
+    }
+
+    /// Starts at most `requests_per_minute` requests a minute, retries
+    /// included, across every caller sharing this client (0 = unlimited).
+    pub fn with_requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.rate_limiter = RateLimiter::per_minute(requests_per_minute);
+        self
+    }
+
+    /// Enforces a minimum delay between the end of one request and the start
+    /// of the next, across every caller sharing this client. Requests started
+    /// while others are still in flight are also spaced by the delay from the
+    /// previous start, so that simultaneous callers do not all start at once.
+    /// `Duration::ZERO` disables the delay.
+    pub fn with_min_request_interval(mut self, interval: Duration) -> Self {
+        self.min_request_interval = interval;
+        self
+    }
+
+    /// Returns the number of responses received and their total size.
+    pub fn response_traffic(&self) -> ResponseTraffic {
+        ResponseTraffic {
+            responses: self.responses.load(Ordering::Relaxed),
+            wire_bytes: self.response_wire_bytes.load(Ordering::Relaxed),
+            decoded_bytes: self.response_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record_response(&self, wire_bytes: usize, decoded_bytes: usize) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        self.response_wire_bytes
+            .fetch_add(wire_bytes as u64, Ordering::Relaxed);
+        self.response_bytes
+            .fetch_add(decoded_bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Reserves a start at least `min_request_interval` after both the last
+    /// finished request and the previously reserved start, and sleeps until
+    /// it.
+    async fn wait_for_interval(&self) {
+        if self.min_request_interval.is_zero() {
+            return;
+        }
+        let now = self.clock.now();
+        let start = match self.request_spacing.lock() {
+            Ok(mut spacing) => {
+                let start = [spacing.last_finished, spacing.last_start]
+                    .into_iter()
+                    .flatten()
+                    .map(|earlier| earlier + self.min_request_interval)
+                    .fold(now, Instant::max);
+                spacing.last_start = Some(start);
+                start
+            }
+            Err(_) => now,
+        };
+        let wait = start.saturating_duration_since(now);
+        if !wait.is_zero() {
+            self.clock.sleep(wait).await;
+        }
+    }
+
+    fn mark_request_finished(&self) {
+        if let Ok(mut spacing) = self.request_spacing.lock() {
+            spacing.last_finished = Some(self.clock.now());
+        }
+    }
+
+    /// Sends a prepared request body for `model`, failing once the cost cap
+    /// is reached or when the run deadline passes first.
+    async fn chat_within_deadline(&self, model: &str, body: &Value) -> Result<ChatResponse> {
+        if self.cost_cap_reached() {
+            anyhow::bail!(COST_CAP_REACHED);
+        }
+
+        match self.deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(self.clock.now());
+                tokio::select! {
+                    biased;
+                    result = self.chat_body(model, body) => result,
+                    () = self.clock.sleep(remaining) => Err(anyhow::anyhow!(RUN_DEADLINE_EXCEEDED)),
+                }
+            }
+            None => self.chat_body(model, body).await,
+        }
+    }
+
+    /// Sends a prepared request body and extracts the response, retrying
+    /// transient failures up to `max_retries` times while the retry budget
+    /// lasts.
+    async fn chat_body(&self, model: &str, body: &Value) -> Result<ChatResponse> {
+        let mut retry = 0;
+        loop {
+            match self.attempt(model, body).await {
+                Err(e) if retry < self.max_retries && self.should_retry(&e) => {
+                    retry += 1;
+                    let delay = e
+                        .downcast_ref::<ApiStatusError>()
+                        .and_then(|e| e.retry_after)
+                        .unwrap_or_else(|| with_jitter(retry_delay(retry)));
+                    if let Some(logger) = &self.logger {
+                        let mut message = format!(
+                            "Request to {model} failed ({e}); retry {retry}/{} in {:.1}s",
+                            self.max_retries,
+                            delay.as_secs_f64()
+                        );
+                        if let Some(left) = self.retries_left() {
+                            let _ = write!(message, " ({left} left in the budget)");
+                        }
+                        logger.warn(&message);
+                    }
+                    self.clock.sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns `true` if `error` is worth retrying, taking a retry from the
+    /// budget if there is one.
+    fn should_retry(&self, error: &anyhow::Error) -> bool {
+        is_retryable(error)
+            && (self.retry_soft_errors || !error.is::<SoftApiError>())
+            && self.retry_budget.as_ref().is_none_or(RetryBudget::try_take)
+    }
+
+    /// Makes one attempt at a request.
+    async fn attempt(&self, model: &str, body: &Value) -> Result<ChatResponse> {
+        // The interval wait comes first, so it does not hold up a request slot.
+        self.wait_for_interval().await;
+        let _slot = match &self.request_slots {
+            Some(slots) => Some(slots.acquire().await?),
+            None => None,
+        };
+        if let Some(limiter) = &self.rate_limiter {
+            let wait = limiter.reserve(self.clock.now());
+            if !wait.is_zero() {
+                self.clock.sleep(wait).await;
+            }
+        }
+
+        if let Some(logger) = self.logger.as_ref().filter(|_| self.body_log_max_bytes > 0) {
+            logger.info(&format!(
+                "Request body for {model}: {}",
+                body_for_log(body, self.body_log_max_bytes)
+            ));
+        }
+        let result = self.send(body).await;
+        self.mark_request_finished();
+        let response = result?;
+        let printed = response.printed;
+        let result = self.parse_response(model, response);
+        // Streamed text only becomes the reply once the attempt succeeds.
+        if printed {
+            self.print_streamed(if result.is_ok() {
+                "\n"
+            } else {
+                DISCARDED_STREAM
+            });
+        }
+        result
+    }
+
+    /// Turns a response into the reply, recording its token usage.
+    fn parse_response(&self, model: &str, response: RawResponse) -> Result<ChatResponse> {
+        let RawResponse {
+            status,
+            retry_after,
+            text: response_text,
+            ..
+        } = response;
+        if !status.is_success() {
+            return Err(ApiStatusError {
+                status,
+                body: response_text,
+                retry_after,
+            }
+            .into());
+        }
+
+        let (response_text, repairs) = if self.repair_escapes {
+            sanitize::repair_json_escapes(&response_text)
+        } else {
+            (Cow::Borrowed(response_text.as_str()), 0)
+        };
+        if repairs > 0 {
+            if let Some(logger) = &self.logger {
+                logger.warn(&format!(
+                    "Replaced {repairs} invalid character escape(s) in the response from {model}"
+                ));
+            }
+        }
+        let response_json: Value =
+            serde_json::from_str(&response_text).context("Failed to parse OpenRouter response")?;
+        self.record_usage(model, Usage::from_response(&response_json));
+
+        ChatResponse::from_json(&response_json)
+    }
+
+    fn record_usage(&self, model: &str, usage: Usage) {
+        if let Ok(mut totals) = self.usage.lock() {
+            totals.entry(model.to_string()).or_default().add(usage);
+        }
+    }
+
+    /// Posts `body` and returns the response as received.
+    async fn send(&self, body: &Value) -> Result<RawResponse> {
+        let mut request = self
             .client
-            .post(OPENROUTER_API_URL)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body)
+            .post(&self.api_url)
+            .header("Authorization", format!("Bearer {}", self.api_key));
+        for (header, value) in &self.attribution {
+            request = request.header(*header, value.clone());
+        }
+        // Event streams are read as they arrive, so they are not compressed.
+        let streamed = body["stream"].as_bool() == Some(true);
+        request = request.header(
+            reqwest::header::ACCEPT_ENCODING,
+            if streamed {
+                "identity"
+            } else {
+                ACCEPTED_ENCODINGS
+            },
+        );
+        let response = request
+            .json(body)
             .send()
             .await
-            .context("Failed to send request to OpenRouter")?;
+            .map_err(|e| NetworkError::classify("Failed to send request to OpenRouter", e))?;
 
         let status = response.status();
-        let response_text = response
-            .text()
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, chrono::Utc::now()));
+        if status.is_success() && streamed {
+            let mut printed = false;
+            let result = self.read_stream(response, &mut printed).await;
+            if result.is_err() && printed {
+                self.print_streamed(DISCARDED_STREAM);
+            }
+            return Ok(RawResponse {
+                status,
+                retry_after,
+                text: result?,
+                printed,
+            });
+        }
+        let encoding = response
+            .headers()
+            .get(reqwest::header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let wire = response
+            .bytes()
             .await
-            .context("Failed to read response body")?;
+            .map_err(|e| NetworkError::classify("Failed to read response body", e))?;
+        let decoded = decode_body(&wire, encoding.as_deref())?;
+        self.record_response(wire.len(), decoded.len());
+        let response_text = String::from_utf8_lossy(&decoded).into_owned();
+        Ok(RawResponse {
+            status,
+            retry_after,
+            text: response_text,
+            printed: false,
+        })
+    }
 
-        if !status.is_success() {
-            anyhow::bail!("OpenRouter API error ({status}): {response_text}");
+    /// Reads a streamed reply, printing its text as it arrives (and setting
+    /// `printed` once it does), and returns it as the body of the equivalent
+    /// non-streamed response.
+    async fn read_stream(
+        &self,
+        mut response: reqwest::Response,
+        printed: &mut bool,
+    ) -> Result<String> {
+        let mut reply = StreamAccumulator::default();
+        let mut pending: Vec<u8> = Vec::new();
+        let mut received = 0;
+        loop {
+            let chunk = response
+                .chunk()
+                .await
+                .map_err(|e| NetworkError::classify("Failed to read response stream", e))?;
+            let at_end = chunk.is_none();
+            if let Some(chunk) = chunk {
+                received += chunk.len();
+                pending.extend_from_slice(&chunk);
+            }
+            // Lines are complete here, so no character is split between reads.
+            while let Some(end) = pending
+                .iter()
+                .position(|&b| b == b'\n')
+                .or_else(|| (at_end && !pending.is_empty()).then(|| pending.len() - 1))
+            {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let line = if self.repair_escapes {
+                    sanitize::repair_json_escapes(&line).0
+                } else {
+                    line
+                };
+                if let Some(text) = reply.feed_line(&line)? {
+                    self.print_streamed(&text);
+                    *printed = true;
+                }
+            }
+            if at_end || reply.is_done() {
+                break;
+            }
         }
+        self.record_response(received, received);
+        Ok(reply.into_json().to_string())
+    }
 
-        let response_json: Value =
-            serde_json::from_str(&response_text).context("Failed to parse OpenRouter response")?;
+    /// Writes streamed reply text to the stream output.
+    fn print_streamed(&self, text: &str) {
+        let write = |out: &mut dyn std::io::Write| {
+            let _ = out.write_all(text.as_bytes());
+            let _ = out.flush();
+        };
+        match &self.stream_output {
+            Some(output) => {
+                if let Ok(mut output) = output.lock() {
+                    write(&mut *output);
+                }
+            }
+            None => write(&mut std::io::stdout()),
+        }
+    }
+}
 
-        response_json["choices"][0]["message"]["content"]
-            .as_str()
-            .map(String::from)
-            .context("No content in OpenRouter response")
+#[async_trait]
+impl ChatApi for OpenRouterClient {
+    async fn chat_with_params(
+        &self,
+        model: &str,
+        messages: Vec<Value>,
+        params: &ChatParams,
+    ) -> Result<ChatResponse> {
+        let mut body = json!({
+            "model": model,
+            "messages": messages,
+        });
+        params.apply(&mut body);
+
+        let fallbacks = self.fallbacks.get(model).map_or(&[][..], Vec::as_slice);
+        let mut current = model;
+        let mut remaining = fallbacks.iter();
+        loop {
+            body["model"] = json!(current);
+            let result = self.chat_within_deadline(current, &body).await;
+            match (&result, remaining.next()) {
+                (Err(e), Some(next)) if is_model_error(e) => {
+                    if let Some(logger) = &self.logger {
+                        logger.warn(&format!(
+                            "Model {current} failed ({e}); falling back to {next}"
+                        ));
+                    }
+                    current = next;
+                }
+                _ => {
+                    if result.is_ok() && current != model {
+                        if let Some(logger) = &self.logger {
+                            logger.info(&format!("Response produced by fallback model {current}"));
+                        }
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    fn deadline_exceeded(&self) -> bool {
+        OpenRouterClient::deadline_exceeded(self)
+    }
+
+    fn cost_cap_reached(&self) -> bool {
+        OpenRouterClient::cost_cap_reached(self)
+    }
+
+    fn estimated_cost(&self) -> f64 {
+        OpenRouterClient::estimated_cost(self)
+    }
+}
+
+/// Serializes a request body for the debug log: `data:...;base64,...` values
+/// become `data:...;base64,<N bytes>` and the text is cut to `max_bytes`.
+fn body_for_log(body: &Value, max_bytes: usize) -> String {
+    let mut body = body.clone();
+    redact_inline_data(&mut body);
+    let mut text = body.to_string();
+    if text.len() > max_bytes {
+        let total = text.len();
+        let mut cut = max_bytes;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+        let _ = write!(text, "... ({total} bytes total)");
+    }
+    text
+}
+
+fn redact_inline_data(value: &mut Value) {
+    match value {
+        Value::String(s) if s.starts_with("data:") => {
+            if let Some((prefix, data)) = s.split_once(";base64,") {
+                *s = format!("{prefix};base64,<{} bytes>", data.len() / 4 * 3);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_inline_data),
+        Value::Object(fields) => fields.values_mut().for_each(redact_inline_data),
+        _ => {}
     }
 }
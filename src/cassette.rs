@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::api::{ChatApi, ChatParams, ChatResponse};
+use crate::output;
+
+/// A recorded request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub model: String,
+    pub messages: Vec<Value>,
+    #[serde(default)]
+    pub params: ChatParams,
+}
+
+/// The recorded outcome of a request: a response or an error message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordedOutcome {
+    Response(ChatResponse),
+    Error(String),
+}
+
+/// One request/outcome pair in a cassette file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    #[serde(flatten)]
+    pub outcome: RecordedOutcome,
+}
+
+enum Mode {
+    /// Forward to the inner client and append every interaction to the file.
+    Record(Arc<dyn ChatApi>),
+    /// Answer from the file; each interaction is used at most once.
+    Replay,
+}
+
+/// A [`ChatApi`] that records interactions to, or replays them from, a JSON
+/// cassette file.
+///
+/// Replay prefers the interaction whose model, messages and parameters match
+/// the request exactly, so it works regardless of request order. Otherwise it
+/// falls back to the next unused interaction for the same model, which keeps
+/// hand-written fixtures valid when prompt wording changes.
+pub struct CassetteClient {
+    path: PathBuf,
+    mode: Mode,
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl CassetteClient {
+    /// Wraps `inner`, writing every interaction to `path` (overwriting it).
+    pub fn record(inner: Arc<dyn ChatApi>, path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Record(inner),
+            interactions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Loads the cassette at `path` for replay without touching the network.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read cassette: {}", path.display()))?;
+        let interactions: Vec<Interaction> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse cassette: {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            interactions: Mutex::new(interactions.into_iter().map(|i| (i, false)).collect()),
+        })
+    }
+
+    /// Returns the number of recorded interactions not yet replayed.
+    pub fn remaining(&self) -> usize {
+        self.lock().iter().filter(|(_, used)| !used).count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(Interaction, bool)>> {
+        self.interactions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn save(&self, interactions: &[(Interaction, bool)]) -> Result<()> {
+        let recorded: Vec<&Interaction> = interactions.iter().map(|(i, _)| i).collect();
+        let json = serde_json::to_string_pretty(&recorded)?;
+        output::write_atomic(&self.path, json, false)
+            .with_context(|| format!("Failed to write cassette: {}", self.path.display()))
+    }
+
+    fn take_match(&self, request: &RecordedRequest) -> Result<RecordedOutcome> {
+        let mut interactions = self.lock();
+        let index = interactions
+            .iter()
+            .position(|(i, used)| !used && i.request == *request)
+            .or_else(|| {
+                interactions
+                    .iter()
+                    .position(|(i, used)| !used && i.request.model == request.model)
+            })
+            .with_context(|| {
+                format!(
+                    "No recorded interaction left in {} for model '{}'",
+                    self.path.display(),
+                    request.model
+                )
+            })?;
+        let (interaction, used) = &mut interactions[index];
+        *used = true;
+        Ok(interaction.outcome.clone())
+    }
+}
+
+#[async_trait]
+impl ChatApi for CassetteClient {
+    async fn chat_with_params(
+        &self,
+        model: &str,
+        messages: Vec<Value>,
+        params: &ChatParams,
+    ) -> Result<ChatResponse> {
+        let request = RecordedRequest {
+            model: model.to_string(),
+            messages,
+            params: params.clone(),
+        };
+
+        match &self.mode {
+            Mode::Replay => match self.take_match(&request)? {
+                RecordedOutcome::Response(response) => Ok(response),
+                RecordedOutcome::Error(message) => Err(anyhow::anyhow!(message)),
+            },
+            Mode::Record(inner) => {
+                let result = inner
+                    .chat_with_params(model, request.messages.clone(), params)
+                    .await;
+                let outcome = match &result {
+                    Ok(response) => RecordedOutcome::Response(response.clone()),
+                    Err(e) => RecordedOutcome::Error(format!("{e:#}")),
+                };
+                let mut interactions = self.lock();
+                interactions.push((Interaction { request, outcome }, true));
+                self.save(&interactions)?;
+                result
+            }
+        }
+    }
+
+    fn deadline_exceeded(&self) -> bool {
+        match &self.mode {
+            Mode::Record(inner) => inner.deadline_exceeded(),
+            Mode::Replay => false,
+        }
+    }
+
+    fn cost_cap_reached(&self) -> bool {
+        match &self.mode {
+            Mode::Record(inner) => inner.cost_cap_reached(),
+            Mode::Replay => false,
+        }
+    }
+
+    fn estimated_cost(&self) -> f64 {
+        match &self.mode {
+            Mode::Record(inner) => inner.estimated_cost(),
+            Mode::Replay => 0.0,
+        }
+    }
+}
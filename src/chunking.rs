@@ -0,0 +1,87 @@
+/// A piece of the combined text summarized on its own before the final
+/// summary (`max_input_chars`, `max_input_tokens`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// The last words of the previous chunk, repeated for context (empty for
+    /// the first chunk).
+    pub overlap: String,
+    /// Text that belongs to this chunk alone. The bodies of all chunks
+    /// concatenate back to the original text.
+    pub body: String,
+}
+
+impl Chunk {
+    /// Words of the body, i.e. not counting the repeated overlap.
+    pub fn words(&self) -> usize {
+        self.body.split_whitespace().count()
+    }
+
+    /// Length of the chunk as sent, overlap included, in characters.
+    pub fn chars(&self) -> usize {
+        let overlap = self.overlap.chars().count();
+        self.body.chars().count() + if overlap > 0 { overlap + 1 } else { 0 }
+    }
+}
+
+/// Splits `text` into chunks of at most `max_chars` characters each, overlap
+/// included. Chunks end at a line break where one falls in their second
+/// half, otherwise between words; a single word longer than a chunk is cut.
+/// Every chunk after the first repeats the last `overlap_words` words of the
+/// previous one, shortened if needed to at most half of `max_chars`.
+pub fn split(text: &str, max_chars: usize, overlap_words: usize) -> Vec<Chunk> {
+    let max_chars = max_chars.max(2);
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    let mut overlap = String::new();
+    while !rest.is_empty() {
+        let overlap_len = match overlap.chars().count() {
+            0 => 0,
+            n => n + 1,
+        };
+        let end = chunk_end(rest, max_chars.saturating_sub(overlap_len).max(1));
+        let body = &rest[..end];
+        rest = &rest[end..];
+        let next_overlap = tail_words(body, overlap_words, max_chars / 2);
+        chunks.push(Chunk {
+            overlap,
+            body: body.to_string(),
+        });
+        overlap = next_overlap;
+    }
+    chunks
+}
+
+/// Returns the byte length of the next chunk of `text`: at most `budget`
+/// characters, preferably ending at a line break or whitespace.
+fn chunk_end(text: &str, budget: usize) -> usize {
+    let hard_end = text
+        .char_indices()
+        .nth(budget)
+        .map_or(text.len(), |(i, _)| i);
+    if hard_end == text.len() {
+        return hard_end;
+    }
+    let window = &text[..hard_end];
+    let half = window.len() / 2;
+    if let Some(i) = window.rfind('\n').filter(|&i| i >= half) {
+        return i + 1;
+    }
+    match window.rfind(char::is_whitespace) {
+        Some(i) if i > 0 => i + window[i..].chars().next().map_or(1, char::len_utf8),
+        _ => hard_end,
+    }
+}
+
+/// Returns the last `count` words of `text` joined by spaces, dropping
+/// leading words until the result fits in `max_chars` characters.
+fn tail_words(text: &str, count: usize, max_chars: usize) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut tail = &words[words.len().saturating_sub(count)..];
+    loop {
+        let joined = tail.join(" ");
+        if joined.chars().count() <= max_chars || tail.is_empty() {
+            return joined;
+        }
+        tail = &tail[1..];
+    }
+}
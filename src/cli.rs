@@ -0,0 +1,111 @@
+use clap::Parser;
+use serde_yaml::{Mapping, Value};
+
+/// Config file used when `--config` is not given.
+pub const DEFAULT_CONFIG: &str = "config.yaml";
+
+/// Command-line arguments.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Extracts text from files and summarizes it via OpenRouter"
+)]
+pub struct Cli {
+    /// Summarize only this file instead of the configured `files_directory`.
+    #[arg(value_name = "FILE", conflicts_with = "combined_input")]
+    pub file: Option<String>,
+
+    /// Same as the FILE argument.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["file", "combined_input"])]
+    pub input_file: Option<String>,
+
+    /// Summarize text read from standard input instead of files.
+    #[arg(long, conflicts_with_all = ["file", "input_file", "files_dir", "combined_input", "dry_run"])]
+    pub stdin: bool,
+
+    /// Config source: a file path, `-` for stdin, or an http(s):// URL.
+    #[arg(long, default_value = DEFAULT_CONFIG)]
+    pub config: String,
+
+    /// Read files from this directory instead of `files_directory`.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["file", "input_file"])]
+    pub files_dir: Option<String>,
+
+    /// Write the summary here instead of `output_path`.
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<String>,
+
+    /// Summarize with this model instead of `summary_model`.
+    #[arg(long, value_name = "NAME")]
+    pub summary_model: Option<String>,
+
+    /// Target summary length in percent of the input, instead of `compress_summary`.
+    #[arg(long, value_name = "PCT", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub compress: Option<u8>,
+
+    /// OpenRouter API key, taking precedence over `api_key_source` and
+    /// `api_key_file`. Visible to other local users in the process list.
+    #[arg(long, value_name = "KEY")]
+    pub api_key: Option<String>,
+
+    /// Load environment variables from this file instead of searching for `.env`.
+    #[arg(long, value_name = "PATH")]
+    pub env_file: Option<String>,
+
+    /// Skip scanning and extraction; summarize this previously combined text file.
+    #[arg(long, value_name = "PATH")]
+    pub combined_input: Option<String>,
+
+    /// Revise the existing summary at the output path using the feedback in this file.
+    #[arg(long, value_name = "PATH")]
+    pub feedback: Option<String>,
+
+    /// Record every API request and response to this cassette file.
+    #[arg(long, value_name = "PATH")]
+    pub record_cassette: Option<String>,
+
+    /// Answer API requests from this cassette file instead of the network.
+    #[arg(long, value_name = "PATH")]
+    pub replay_cassette: Option<String>,
+
+    /// Neither read nor write the OCR cache (`cache_dir`).
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// List the files that would be read, by text or OCR, with their sizes
+    /// and an estimated word count; call no API and write no summary.
+    #[arg(long, conflicts_with_all = ["combined_input", "feedback"])]
+    pub dry_run: bool,
+
+    /// Log additional diagnostic details.
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Log per-phase and per-file timings at the end of the run.
+    #[arg(long)]
+    pub profile: bool,
+}
+
+impl Cli {
+    /// Returns the config fields set by flags, which take precedence over
+    /// the config file.
+    pub fn config_overrides(&self) -> Mapping {
+        let mut overrides = Mapping::new();
+        let mut set = |field: &str, value: Value| {
+            overrides.insert(Value::from(field), value);
+        };
+        if let Some(dir) = &self.files_dir {
+            set("files_directory", Value::from(dir.as_str()));
+        }
+        if let Some(path) = &self.output {
+            set("output_path", Value::from(path.as_str()));
+        }
+        if let Some(model) = &self.summary_model {
+            set("summary_model", Value::from(model.as_str()));
+        }
+        if let Some(pct) = self.compress {
+            set("compress_summary", Value::from(pct));
+        }
+        overrides
+    }
+}
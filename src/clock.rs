@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Source of time for the API client's timing logic (request intervals,
+/// retry backoff, run deadlines), so it can be driven by virtual time in
+/// tests.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+
+    /// Waits for `duration` to pass.
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The real clock: `Instant::now` and tokio's timer.
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// A virtual clock for tests: time stands still until [`MockClock::advance`]
+/// or [`Clock::sleep`] moves it forward, and sleeping returns immediately.
+pub struct MockClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    sleeps: Mutex<Vec<Duration>>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            sleeps: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Moves virtual time forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap_or_else(|p| p.into_inner()) += duration;
+    }
+
+    /// Returns the duration of every sleep so far, in order.
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.sleeps
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .push(duration);
+        self.advance(duration);
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+use serde_json::json;
+
+use crate::api::ChatApi;
+
+/// Confidence below which an extraction is flagged in the log when no
+/// `min_confidence` is configured.
+pub const LOW_CONFIDENCE: u8 = 50;
+
+/// Fragments (lowercase) with which OCR output admits unreadable text.
+const UNCERTAINTY_MARKERS: &[&str] = &[
+    "неразборчиво",
+    "нечитаемо",
+    "не удалось распознать",
+    "[?]",
+    "???",
+    "illegible",
+    "unreadable",
+    "[unclear]",
+    "\u{fffd}",
+];
+
+/// Confidence lost per uncertainty marker found by [`heuristic_confidence`].
+const PENALTY_PER_MARKER: u32 = 15;
+
+/// Estimates extraction confidence (0-100) from the OCR output alone: every
+/// admission of unreadable text lowers it, and empty output scores 0.
+pub fn heuristic_confidence(text: &str) -> u8 {
+    if text.trim().is_empty() {
+        return 0;
+    }
+    let lower = text.to_lowercase();
+    let markers: u32 = UNCERTAINTY_MARKERS
+        .iter()
+        .map(|m| lower.matches(m).count() as u32)
+        .sum();
+    100u32.saturating_sub(markers * PENALTY_PER_MARKER) as u8
+}
+
+/// Asks `model` to rate (0-100) how accurate and complete the OCR transcript
+/// looks. Only the text is sent, not the original image.
+pub async fn ask_confidence(api: &dyn ChatApi, model: &str, text: &str) -> Result<u8> {
+    let messages = vec![
+        json!({"role": "system", "content":
+            "Ты проверяешь качество распознавания текста (OCR). \
+             Оцени по шкале от 0 до 100, насколько приведённый текст похож на точное \
+             и полное распознавание: учитывай искажённые слова, обрывы, пометки о \
+             неразборчивых местах. Ответь ТОЛЬКО целым числом."}),
+        json!({"role": "user", "content": text}),
+    ];
+    let reply = api.chat(model, messages).await?;
+    parse_score(&reply)
+        .ok_or_else(|| anyhow::anyhow!("No confidence score in reply: {}", reply.trim()))
+}
+
+/// Returns the first integer in `reply`, capped at 100.
+fn parse_score(reply: &str) -> Option<u8> {
+    let digits: String = reply
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse::<u32>().ok().map(|n| n.min(100) as u8)
+}
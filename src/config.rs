@@ -1,41 +1,1067 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use serde_yaml::Mapping;
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
+
+use crate::api::{ModelPricing, DEFAULT_MAX_RETRIES};
+use crate::document_profile::{self, DocumentProfile, BUILTIN_PROFILES};
+use crate::extraction_tools::{self, ExtractionTool};
+use crate::file_filter::FileFilter;
+use crate::instructions::InstructionSet;
+use crate::keywords;
+use crate::language::OutputLanguage;
+use crate::logger::{LogFormat, LogLevel, LogStream};
+use crate::output_name::OutputNameTemplate;
+use crate::prompt_template::{self, PromptTemplate};
+use crate::secrets::ApiKeySource;
+use std::io::Read;
 
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
+    /// Layout version of the config file; older layouts are migrated on load.
+    /// Unset = an unversioned config from before versioning.
+    #[serde(default)]
+    pub config_version: u32,
+    /// Directory to summarize. Required unless a single file is passed on the
+    /// command line.
+    #[serde(default)]
     pub files_directory: String,
+    #[serde(default = "default_compress_summary")]
     pub compress_summary: u8,
+    /// Where to read the OpenRouter API key (default: the
+    /// `OPENROUTER_API_KEY` environment variable).
+    #[serde(default)]
+    pub api_key_source: ApiKeySource,
+    /// File holding the OpenRouter API key, read when `api_key_source`
+    /// yields none; surrounding whitespace is ignored.
+    #[serde(default)]
+    pub api_key_file: Option<String>,
+    /// Base URL of the OpenRouter-compatible API, e.g. a proxy or a local
+    /// gateway (default: the `OPENROUTER_BASE_URL` environment variable, or
+    /// OpenRouter itself). `/chat/completions` is appended to it.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Site URL sent as `HTTP-Referer` for OpenRouter app attribution
+    /// (default: the `OPENROUTER_HTTP_REFERER` environment variable, if set).
+    #[serde(default)]
+    pub http_referer: Option<String>,
+    /// App name sent as `X-Title` for OpenRouter app attribution (default:
+    /// the `OPENROUTER_APP_TITLE` environment variable, if set).
+    #[serde(default)]
+    pub app_title: Option<String>,
+    #[serde(default = "default_ocr_model")]
     pub ocr_model: String,
+    #[serde(default = "default_summary_model")]
     pub summary_model: String,
-    /// Output file path for the generated summary (defaults to "summary.md").
+    /// Models tried in order when `ocr_model` fails with a model-specific
+    /// error (an error status or an empty reply) after its retries.
+    #[serde(default)]
+    pub ocr_fallback_models: Vec<String>,
+    /// Models tried in order when `summary_model` fails likewise.
+    #[serde(default)]
+    pub summary_fallback_models: Vec<String>,
+    /// Output file path for the generated summary (defaults to "summary").
+    /// Without an extension, the one of `output_format` is appended
+    /// (`summary.md`).
     #[serde(default = "default_output_path")]
     pub output_path: String,
+    /// File name templates for the combined summary and its split parts.
+    /// After loading, `output_path` holds the resolved combined summary path.
+    #[serde(default)]
+    pub output_name_template: OutputNameTemplate,
+    /// Check that the disk has room before writing the summary and its side
+    /// files. Outputs are always written via a temporary file and renamed
+    /// into place, so a failed write never truncates them.
+    #[serde(default)]
+    pub check_disk_space: bool,
     /// Log file path (defaults to "app.log"). Set to empty string to disable file logging.
     #[serde(default = "default_log_file")]
     pub log_file: String,
+    /// Rotate the log file once it exceeds this many bytes (0 = never).
+    #[serde(default, alias = "max_log_size_bytes")]
+    pub log_max_bytes: u64,
+    /// Rotated log files to keep (`app.log.1` ... `app.log.N`).
+    #[serde(default = "default_log_max_files", alias = "max_log_backups")]
+    pub log_max_files: usize,
+    /// Show a progress bar while files are read. It only appears when
+    /// stdout is a terminal.
+    #[serde(default = "default_progress")]
+    pub progress: bool,
+    /// If set, NDJSON progress events (phases, per-file start/finish) are
+    /// written to this file, truncated at the start of each run.
+    #[serde(default)]
+    pub progress_file: Option<String>,
+    /// Console routing of log lines: `split` (default), `stderr` or `stdout`.
+    #[serde(default)]
+    pub log_stream: LogStream,
+    /// Layout of log lines on the console and in the file: `text` (default)
+    /// or `json`, one object per line.
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// Least severe level logged: `info` (default), `warn` or `error`.
+    #[serde(default)]
+    pub log_level: LogLevel,
+    /// What to do when the OCR model refuses to process a document.
+    #[serde(default)]
+    pub on_refusal: RefusalPolicy,
+    /// Ask the OCR model to reproduce tables as Markdown tables and code as
+    /// fenced blocks instead of describing them.
+    #[serde(default)]
+    pub ocr_preserve_tables: bool,
+    /// Also OCR images embedded in PDFs and append them as figures (the
+    /// encodings read are those listed for `pdf_image_fallback`).
+    #[serde(default)]
+    pub pdf_extract_images: bool,
+    /// Frames of an animated GIF sent for OCR, each converted to a static
+    /// PNG: 1 reads only the first frame; more reads that many frames spread
+    /// evenly over the animation, for GIFs that are slideshows.
+    #[serde(default = "default_gif_frames")]
+    pub gif_frames: usize,
+    /// When a PDF yields suspiciously little text for its size (e.g. a scan),
+    /// OCR its embedded images instead: JPEGs, and uncompressed or
+    /// Flate-compressed images converted to PNG. Pages are not rasterized, so
+    /// scans stored as CCITT fax, JBIG2 or JPEG 2000 are not supported; they
+    /// are skipped (and logged).
+    #[serde(default, alias = "pdf_jpeg_fallback")]
+    pub pdf_image_fallback: bool,
+    /// Split multi-page PDFs and OCR each page in its own request, so long
+    /// documents are not truncated by the model's output limit.
+    #[serde(default)]
+    pub split_pdf_pages: bool,
+    /// OCR local images referenced from Markdown files (`![](diagram.png)`)
+    /// and insert their text after each reference. Images outside the files
+    /// directory are skipped.
+    #[serde(default)]
+    pub inline_referenced_images: bool,
+    /// Exclude OCR'd files whose estimated confidence (0-100) is below this
+    /// (unset = keep every file, only flagging low scores in the log).
+    #[serde(default)]
+    pub min_confidence: Option<u8>,
+    /// Also ask the OCR model to rate each transcript's confidence, at the cost
+    /// of one extra text-only request per OCR'd file.
+    #[serde(default)]
+    pub confidence_check: bool,
+    /// Functions the OCR model is offered for every extracted file; the
+    /// arguments of its calls are kept as the file's structured fields.
+    /// Costs one extra text-only request per file (empty = off).
+    #[serde(default)]
+    pub extraction_tools: Vec<ExtractionTool>,
+    /// Minimum pause between the end of one API request and the start of the
+    /// next, in milliseconds (0 = none). Requests that overlap are spaced by
+    /// the same delay from one start to the next.
+    #[serde(default)]
+    pub min_request_interval_ms: u64,
+    /// Maximum number of API requests started per minute, spaced evenly and
+    /// counting retries, for rate-limited (e.g. free) models (0 = unlimited).
+    #[serde(default)]
+    pub requests_per_minute: u32,
+    /// Maximum number of API requests in flight at once across the whole run
+    /// (0 = unlimited). Concurrent summarization is bounded by this as well.
+    #[serde(default)]
+    pub max_concurrent_requests: usize,
+    /// Number of files extracted at once; above 1, PDF and image OCR
+    /// requests overlap. Results keep their order by file name.
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Retries of each failed request (network errors, 429, 500, 502, 503
+    /// and 504 responses), with exponential backoff from 1s up to 30s or the
+    /// wait a `Retry-After` header asks for (0 = never retry).
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Cap on the retries of all requests together across the whole run
+    /// (unset = only `max_retries` per request applies).
+    #[serde(default)]
+    pub retry_budget: Option<u32>,
+    /// Also retry replies that carry no content but an
+    /// error note such as "model is overloaded" despite HTTP 200.
+    #[serde(default = "default_retry_soft_errors")]
+    pub retry_soft_errors: bool,
+    /// With `--verbose`, log each request body cut to this many bytes, inline
+    /// images reduced to their size (0 = never log request bodies).
+    #[serde(default = "default_request_log_max_bytes")]
+    pub request_log_max_bytes: usize,
+    /// Fail with an error instead of exiting cleanly when no files were processed.
+    #[serde(default)]
+    pub error_on_empty: bool,
+    /// Create `files_directory` if it does not exist (the run then finds it
+    /// empty) instead of failing.
+    #[serde(default)]
+    pub create_missing_dir: bool,
+    /// Time budget for the whole run in seconds (0 = unlimited). Work still in
+    /// flight when it runs out is cancelled.
+    #[serde(default)]
+    pub max_run_secs: u64,
+    /// If set, the combined text sent to the summary model is also written here.
+    #[serde(default)]
+    pub dump_combined_input: Option<String>,
+    /// Per-model prices in USD per million tokens, keyed by model name.
+    #[serde(default)]
+    pub model_pricing: HashMap<String, ModelPricing>,
+    /// Stop making API calls once the estimated cost reaches this many USD.
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// Context window in tokens per model name, overriding the built-in
+    /// limits. Unlisted models fall back to [`DEFAULT_CONTEXT_LIMIT`].
+    #[serde(default)]
+    pub model_context_limits: HashMap<String, u64>,
+    /// Keep only the first and last lines of text files longer than this,
+    /// totalling this many lines (0 = unlimited).
+    #[serde(default)]
+    pub max_lines_per_file: usize,
+    /// Files larger than this many bytes are skipped without being read
+    /// (0 = no limit).
+    #[serde(default = "default_max_file_size_bytes")]
+    pub max_file_size_bytes: u64,
+    /// Read buffer size in KiB for text files large enough to be streamed.
+    #[serde(default = "default_read_buffer_kib")]
+    pub read_buffer_kib: usize,
+    /// JPEG, PNG and WebP images larger than this many KiB are downscaled
+    /// to `max_image_dimension` and re-encoded before OCR (0 = never).
+    #[serde(default = "default_image_resize_threshold_kib")]
+    pub image_resize_threshold_kib: u64,
+    /// Longest side in pixels of an image downscaled before OCR.
+    #[serde(default = "default_max_image_dimension")]
+    pub max_image_dimension: u32,
+    /// Output token limit for summary requests (unset = provider default).
+    #[serde(default)]
+    pub summary_max_tokens: Option<u32>,
+    /// Sampling temperature for summary requests; 0 for the most repeatable
+    /// output (unset = provider default).
+    #[serde(default)]
+    pub summary_temperature: Option<f32>,
+    /// Nucleus sampling (`top_p`) for summary requests (unset = provider
+    /// default).
+    #[serde(default)]
+    pub summary_top_p: Option<f32>,
+    /// Output token limit for OCR requests (unset = provider default).
+    #[serde(default)]
+    pub ocr_max_tokens: Option<u32>,
+    /// Sampling temperature for OCR requests; low values such as 0 keep the
+    /// transcription faithful (unset = provider default).
+    #[serde(default)]
+    pub ocr_temperature: Option<f32>,
+    /// Nucleus sampling (`top_p`) for OCR requests (unset = provider default).
+    #[serde(default)]
+    pub ocr_top_p: Option<f32>,
+    /// Seed passed with summary requests for reproducible output. Determinism
+    /// depends on the provider: those without seed support ignore it.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Let OpenRouter's `middle-out` transform shrink a summary prompt that
+    /// exceeds the model's context. Lossy: text is dropped from the middle of
+    /// the prompt instead of the request failing.
+    #[serde(default)]
+    pub middle_out: bool,
+    /// How many times to ask the model to continue a summary cut off by the
+    /// output token limit.
+    #[serde(default = "default_max_continuations")]
+    pub max_continuations: u32,
+    /// Additional extensions (lowercase, without the dot) to read as text.
+    #[serde(default)]
+    pub extra_text_extensions: Vec<String>,
+    /// Additional extensions (lowercase, without the dot) to OCR as images.
+    #[serde(default)]
+    pub extra_image_extensions: Vec<String>,
+    /// Also split the summary at heading boundaries into `<name>.partN.<ext>`
+    /// files of at most this many words each (0 = don't split).
+    #[serde(default)]
+    pub split_output_words: usize,
+    /// Append a "Ключевые термины" section with the most frequent terms of the
+    /// input, computed locally (no extra tokens).
+    #[serde(default)]
+    pub include_keywords: bool,
+    /// Number of terms listed when `include_keywords` is set.
+    #[serde(default = "default_keyword_count")]
+    pub keyword_count: usize,
+    /// Languages whose stopwords are excluded from the keywords (`ru`, `en`).
+    #[serde(default = "default_keyword_languages")]
+    pub keyword_languages: Vec<String>,
+    /// Form of the output: a Markdown, HTML or plain text summary, or
+    /// per-file key points as CSV.
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    /// Fixed top-level `#` heading for the summary, replacing the title the
+    /// model chooses (unset = keep the model's title).
+    #[serde(default)]
+    pub summary_title: Option<String>,
+    /// Who the summary is written for (e.g. "non-technical executives" or
+    /// "domain experts"); shapes tone and jargon. Empty = no specific audience.
+    #[serde(default)]
+    pub audience: String,
+    /// Language of the extracted text and the summary: a name (`English`) or
+    /// an ISO 639-1 code (`en`).
+    #[serde(default = "default_output_language")]
+    pub output_language: String,
+    /// Split the summary's word target between its sections and check the
+    /// result, asking the model once to rebalance sections that are far off.
+    /// Unset = one overall target.
+    #[serde(default)]
+    pub section_budgets: Option<SectionBudgets>,
+    /// When the summary's word count is off the target by more than this
+    /// percentage, ask the model once for a corrected summary (0 = never).
+    #[serde(default = "default_word_count_tolerance_pct")]
+    pub word_count_tolerance_pct: u8,
+    /// JSON file with the full messages array for the summary request,
+    /// replacing the built-in prompt. Contents may use the placeholders
+    /// `{combined_text}`, `{total_words}` and `{target_words}`.
+    #[serde(default)]
+    pub messages_template_path: Option<String>,
+    /// Prompt replacing the built-in OCR prompt for images and PDFs: a file
+    /// path or the text itself. Placeholders: `{filename}`, `{language}`.
+    /// Table and per-file instructions are still appended.
+    #[serde(default)]
+    pub ocr_prompt_template: Option<String>,
+    /// Prompt replacing the opening of the built-in summary system prompt: a
+    /// file path or the text itself. Must use `{target_words}`; may use
+    /// `{total_words}`, `{compress_pct}`, `{file_count}` and `{language}`.
+    /// Budgets, per-file instructions, audience, profile and the language
+    /// rule are still appended.
+    #[serde(default)]
+    pub summary_prompt_template: Option<String>,
+    /// Template loaded from `ocr_prompt_template`.
+    #[serde(skip)]
+    pub ocr_prompt: Option<PromptTemplate>,
+    /// Template loaded from `summary_prompt_template`.
+    #[serde(skip)]
+    pub summary_prompt: Option<PromptTemplate>,
+    /// Append the reasoning that reasoning models return next to the answer to
+    /// the summary, in a collapsible block (dropped by default).
+    #[serde(default)]
+    pub include_reasoning: bool,
+    /// Generate the summary section by section (overview, each file,
+    /// conclusion) with one request each, checkpointing every finished
+    /// section so a rerun after a crash resumes where it stopped.
+    #[serde(default)]
+    pub sectioned: bool,
+    /// Write `summary.meta.json` next to the summary: the input files with
+    /// their word counts and how each was read, the models, the compression
+    /// and a UTC timestamp.
+    #[serde(default)]
+    pub write_manifest: bool,
+    /// Print summary replies to stdout token by token as they are generated
+    /// (except `per_file_summaries`, which run concurrently). The saved
+    /// summary is the same either way.
+    #[serde(default)]
+    pub stream: bool,
+    /// Also summarize each file on its own, `max_concurrency` at a time, into
+    /// `summaries/<file name>.md` (or `.html`, `.txt` per `output_format`)
+    /// next to the combined summary. Names that clash once path separators
+    /// become `_` get a ` (2)`, ` (3)`... suffix.
+    #[serde(default)]
+    pub per_file_summaries: bool,
+    /// Summarize combined input longer than this many characters in chunks
+    /// first and then summarize the chunk summaries (0 = never chunk).
+    #[serde(default)]
+    pub max_input_chars: usize,
+    /// The same limit in estimated tokens (about three characters each, as
+    /// for the context warning); the stricter of the two applies (0 = none).
+    #[serde(default)]
+    pub max_input_tokens: usize,
+    /// Words from the end of each chunk repeated at the start of the next, so
+    /// chunk boundaries do not cut context (0 = no overlap).
+    #[serde(default)]
+    pub chunk_overlap: usize,
+    /// For a single image or PDF input, extract and summarize it in one
+    /// request instead of two. Requires `ocr_model` and `summary_model` to
+    /// be the same model; other inputs use the usual two-stage pipeline.
+    #[serde(default)]
+    pub single_pass: bool,
+    /// YAML file mapping file names or globs to custom `ocr` and `summary`
+    /// instructions for the matching files.
+    #[serde(default)]
+    pub instructions_path: Option<String>,
+    /// Instructions loaded from `instructions_path`.
+    #[serde(skip)]
+    pub instructions: InstructionSet,
+    /// Document profile applied to the summary prompt: a built-in one
+    /// (`resume`, `contract`, `invoice`) or a key of `profiles`.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// User-defined document profiles by name; these take precedence over
+    /// built-in profiles of the same name.
+    #[serde(default)]
+    pub profiles: HashMap<String, DocumentProfile>,
+    /// Read files with no or an unknown extension as text when their content
+    /// looks like valid UTF-8 text.
+    #[serde(default)]
+    pub treat_unknown_as_text: bool,
+    /// Scan subdirectories of `files_directory` as well. Symbolic links are
+    /// not followed, so link cycles cannot trap the scan.
+    #[serde(default)]
+    pub recursive: bool,
+    /// Globs of files to read, matched against the path relative to
+    /// `files_directory` or the base name (empty = every file).
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Globs of files to skip, matched like `include`, e.g.
+    /// `["**/node_modules/**", "*.min.js"]`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Directory caching OCR results of PDFs and images by file content and
+    /// `ocr_model`, so unchanged files are not sent again ("" = no cache).
+    #[serde(default = "default_cache_dir")]
+    pub cache_dir: String,
+    /// Filter compiled from `include` and `exclude`.
+    #[serde(skip)]
+    pub file_filter: FileFilter,
+    /// Threads used to walk the tree in recursive mode (0 = pick automatically).
+    #[serde(default)]
+    pub walk_threads: usize,
+    /// Upper bound on files and directories held open at once while scanning
+    /// and reading; keep it below `ulimit -n`.
+    #[serde(default = "default_max_open_files")]
+    pub max_open_files: usize,
+    /// Replace malformed UTF-8 in text files and lone surrogate escapes in
+    /// API responses with U+FFFD (logging a warning) instead of failing.
+    #[serde(default = "default_repair_invalid_utf8")]
+    pub repair_invalid_utf8: bool,
+    /// Drop a leading UTF-8 byte-order mark from text files and stdin, as
+    /// written by some Windows editors. Turn off to keep files byte-exact.
+    #[serde(default = "default_strip_bom")]
+    pub strip_bom: bool,
+    /// Weight of each file extension (lowercase, without the dot) in the target
+    /// word count: a file's words count `weight` times, so weights above 1 give
+    /// that type more room in the summary. Unlisted extensions weigh 1.
+    #[serde(default)]
+    pub compression_weights: HashMap<String, f64>,
+    /// Prepend an ASCII tree of the processed files to the summary input.
+    #[serde(default)]
+    pub include_tree: bool,
+    /// How files found in subdirectories are named: by relative path, or by
+    /// base name with repeats numbered.
+    #[serde(default)]
+    pub duplicate_names: DuplicateNames,
+    /// Warnings about migrated, deprecated or unknown fields found while
+    /// loading, to be logged once a logger exists.
+    #[serde(skip)]
+    pub load_warnings: Vec<String>,
+}
+
+/// Handling of content-policy refusals during extraction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefusalPolicy {
+    /// Skip the file immediately.
+    #[default]
+    Skip,
+    /// Retry once with a prompt clarifying the legitimate purpose.
+    Retry,
+}
+
+/// Form of the generated output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// A Markdown summary written to `output_path`.
+    #[default]
+    Markdown,
+    /// The Markdown summary rendered as an HTML page.
+    Html,
+    /// The Markdown summary stripped to plain text.
+    Text,
+    /// `file,key_point,category` rows written to `output_path` with a `.csv`
+    /// extension.
+    Csv,
+}
+
+impl OutputFormat {
+    /// Returns the extension given to an `output_path` without one. CSV
+    /// output still writes its Markdown fallback summary there.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Markdown | OutputFormat::Csv => "md",
+            OutputFormat::Html => "html",
+            OutputFormat::Text => "txt",
+        }
+    }
+}
+
+/// Naming of files found in subdirectories in recursive mode. Either way,
+/// base names shared by several files are logged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateNames {
+    /// Name every file by its path relative to `files_directory`
+    /// (`a/notes.txt`), whether or not its base name is unique.
+    #[default]
+    Path,
+    /// Name files by their base names, numbering repeats in path order
+    /// (`notes (2).txt`).
+    Suffix,
+}
+
+/// Characters per token assumed when converting `max_input_tokens`; matches
+/// [`crate::summary::estimate_tokens`].
+const CHARS_PER_TOKEN: usize = 3;
+
+/// Context window assumed for models with no known limit.
+pub const DEFAULT_CONTEXT_LIMIT: u64 = 16_384;
+
+/// Context windows (in tokens) of commonly used models.
+const BUILTIN_CONTEXT_LIMITS: &[(&str, u64)] = &[
+    ("deepseek/deepseek-chat", 64_000),
+    ("google/gemini-2.0-flash-001", 1_048_576),
+    ("google/gemini-flash-1.5", 1_000_000),
+    ("openai/gpt-4o", 128_000),
+    ("openai/gpt-4o-mini", 128_000),
+    ("anthropic/claude-3.5-sonnet", 200_000),
+    ("anthropic/claude-3-haiku", 200_000),
+    ("meta-llama/llama-3.1-70b-instruct", 131_072),
+];
+
+/// Shares (in percent, summing to 100) of the summary's word target given to
+/// the overview, all per-file sections together, and the conclusion. An
+/// empty mapping uses 20/60/20.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SectionBudgets {
+    pub overview: u8,
+    pub files: u8,
+    pub conclusion: u8,
+}
+
+impl Default for SectionBudgets {
+    fn default() -> Self {
+        Self {
+            overview: 20,
+            files: 60,
+            conclusion: 20,
+        }
+    }
+}
+
+fn default_word_count_tolerance_pct() -> u8 {
+    40
+}
+
+fn default_compress_summary() -> u8 {
+    10
+}
+
+fn default_ocr_model() -> String {
+    "google/gemini-2.0-flash-001".to_string()
+}
+
+fn default_summary_model() -> String {
+    "deepseek/deepseek-chat".to_string()
 }
 
 fn default_output_path() -> String {
-    "summary.md".to_string()
+    "summary".to_string()
 }
 
 fn default_log_file() -> String {
     "app.log".to_string()
 }
 
+fn default_log_max_files() -> usize {
+    5
+}
+
+fn default_request_log_max_bytes() -> usize {
+    2048
+}
+
+fn default_max_open_files() -> usize {
+    64
+}
+
+fn default_repair_invalid_utf8() -> bool {
+    true
+}
+
+fn default_strip_bom() -> bool {
+    true
+}
+
+fn default_output_language() -> String {
+    "Russian".to_string()
+}
+
+fn default_cache_dir() -> String {
+    ".ocr_cache".to_string()
+}
+
+fn default_max_concurrency() -> usize {
+    4
+}
+
+fn default_max_retries() -> u32 {
+    DEFAULT_MAX_RETRIES
+}
+
+fn default_retry_soft_errors() -> bool {
+    true
+}
+
+fn default_progress() -> bool {
+    true
+}
+
+fn default_gif_frames() -> usize {
+    1
+}
+
+fn default_max_file_size_bytes() -> u64 {
+    50 * 1024 * 1024
+}
+
+fn default_read_buffer_kib() -> usize {
+    64
+}
+
+fn default_image_resize_threshold_kib() -> u64 {
+    2048
+}
+
+fn default_max_image_dimension() -> u32 {
+    2048
+}
+
+fn default_keyword_count() -> usize {
+    20
+}
+
+fn default_keyword_languages() -> Vec<String> {
+    vec!["ru".to_string(), "en".to_string()]
+}
+
+fn default_max_continuations() -> u32 {
+    2
+}
+
 impl Config {
-    /// Loads configuration from a YAML file at the given path.
-    pub fn load(path: &str) -> Result<Self> {
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {path}"))?;
-        let config: Config =
-            serde_yaml::from_str(&content).context("Failed to parse config YAML")?;
+    /// Loads configuration from `source`: a file path, `-` for stdin, or an
+    /// `http://` / `https://` URL. YAML and JSON are both accepted.
+    pub async fn load(source: &str) -> Result<Self> {
+        Self::load_with_overrides(source, &Mapping::new()).await
+    }
+
+    /// Like [`Config::load`], with `overrides` (e.g. from the command line)
+    /// replacing the fields of the same name in the loaded config.
+    pub async fn load_with_overrides(source: &str, overrides: &Mapping) -> Result<Self> {
+        let content = if source == "-" {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .context("Failed to read config from stdin")?;
+            buf
+        } else if source.starts_with("http://") || source.starts_with("https://") {
+            fetch_config(source).await?
+        } else {
+            fs::read_to_string(source)
+                .with_context(|| format!("Failed to read config file: {source}"))?
+        };
+        Self::parse_with_overrides(&content, overrides)
+    }
+
+    /// Parses configuration from a YAML (or JSON) string, migrating older
+    /// layouts and dropping unknown fields with a warning.
+    pub fn parse(content: &str) -> Result<Self> {
+        Self::parse_with_overrides(content, &Mapping::new())
+    }
+
+    /// Like [`Config::parse`], with `overrides` taking precedence over the
+    /// fields in `content`. They are applied before validation, so an
+    /// override is checked like a value from the file.
+    pub fn parse_with_overrides(content: &str, overrides: &Mapping) -> Result<Self> {
+        let mut value: serde_yaml::Value =
+            serde_yaml::from_str(content).context("Failed to parse config YAML")?;
+        if value.is_null() {
+            value = serde_yaml::Value::Mapping(Default::default());
+        }
+        let mut warnings = migrate(&mut value)?;
+        if let Some(map) = value.as_mapping_mut() {
+            for (field, override_value) in overrides {
+                map.insert(field.clone(), override_value.clone());
+            }
+        }
+
+        let mut config: Config = loop {
+            match serde_yaml::from_value(value.clone()) {
+                Ok(config) => break config,
+                Err(e) => {
+                    // Deserializing from text also reports where the error is.
+                    let detailed = serde_yaml::to_string(&value)
+                        .ok()
+                        .and_then(|text| serde_yaml::from_str::<Config>(&text).err())
+                        .map_or_else(|| e.to_string(), |e| e.to_string());
+                    let removed = unknown_field(&detailed).filter(|(path, field)| {
+                        value_at_path(&mut value, path)
+                            .and_then(serde_yaml::Value::as_mapping_mut)
+                            .and_then(|map| map.remove(field.as_str()))
+                            .is_some()
+                    });
+                    let Some((path, field)) = removed else {
+                        // The original text gives the right line and column.
+                        let message = without_location(&detailed);
+                        let error = match serde_yaml::from_str::<Config>(content) {
+                            Err(orig) if without_location(&orig.to_string()) == message => {
+                                anyhow::Error::from(orig)
+                            }
+                            _ => anyhow::anyhow!("{message}"),
+                        };
+                        return Err(error).context("Failed to parse config YAML");
+                    };
+                    let field = match path.as_str() {
+                        "" => field,
+                        path => format!("{path}.{field}"),
+                    };
+                    warnings.push(format!(
+                        "Unknown config field '{field}' is ignored; check it for typos"
+                    ));
+                }
+            }
+        };
+        config.load_warnings = warnings;
+        config.validate()?;
+        if Path::new(&config.output_path).extension().is_none() {
+            config.output_path.push('.');
+            config
+                .output_path
+                .push_str(config.output_format.extension());
+        }
+        config.output_path = config
+            .output_name_template
+            .combined_path(&config.output_path, &config.language().code());
+        if let Some(path) = &config.instructions_path {
+            config.instructions = InstructionSet::load(path)?;
+        }
+        config.file_filter = FileFilter::new(&config.include, &config.exclude)?;
+        if let Some(value) = &config.ocr_prompt_template {
+            config.ocr_prompt = Some(PromptTemplate::load(
+                "ocr_prompt_template",
+                value,
+                prompt_template::OCR_PLACEHOLDERS,
+                &[],
+            )?);
+        }
+        if let Some(value) = &config.summary_prompt_template {
+            config.summary_prompt = Some(PromptTemplate::load(
+                "summary_prompt_template",
+                value,
+                prompt_template::SUMMARY_PLACEHOLDERS,
+                prompt_template::SUMMARY_REQUIRED,
+            )?);
+        }
         Ok(config)
     }
 
-    /// Returns `compress_summary` clamped to [1, 100].
+    /// Checks field values that deserialization alone cannot enforce.
+    fn validate(&self) -> Result<()> {
+        let weighted: Vec<String> = self.compression_weights.keys().cloned().collect();
+        for (field, extensions) in [
+            ("extra_text_extensions", &self.extra_text_extensions),
+            ("extra_image_extensions", &self.extra_image_extensions),
+            ("compression_weights", &weighted),
+        ] {
+            for ext in extensions {
+                if ext.is_empty() || ext.starts_with('.') || *ext != ext.to_lowercase() {
+                    anyhow::bail!(
+                        "{field}: '{ext}' must be a lowercase extension without the leading dot"
+                    );
+                }
+            }
+        }
+        for language in &self.keyword_languages {
+            if keywords::stopwords(language).is_none() {
+                anyhow::bail!(
+                    "keyword_languages: no stopword list for '{language}' (use ru or en)"
+                );
+            }
+        }
+        for (ext, weight) in &self.compression_weights {
+            if !weight.is_finite() || *weight < 0.0 {
+                anyhow::bail!(
+                    "compression_weights: weight for '{ext}' must be a non-negative number"
+                );
+            }
+        }
+        if let Some((model, _)) = self.model_context_limits.iter().find(|(_, l)| **l == 0) {
+            anyhow::bail!("model_context_limits: limit for '{model}' must be positive");
+        }
+        if let Some(b) = &self.section_budgets {
+            let sum = b.overview as u32 + b.files as u32 + b.conclusion as u32;
+            if sum != 100 {
+                anyhow::bail!("section_budgets: shares must add up to 100, not {sum}");
+            }
+        }
+        if let Some(url) = &self.base_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                anyhow::bail!("base_url: '{url}' must start with http:// or https://");
+            }
+        }
+        for (field, temperature) in [
+            ("summary_temperature", self.summary_temperature),
+            ("ocr_temperature", self.ocr_temperature),
+        ] {
+            if temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+                anyhow::bail!("{field} must be between 0 and 2");
+            }
+        }
+        for (field, top_p) in [
+            ("summary_top_p", self.summary_top_p),
+            ("ocr_top_p", self.ocr_top_p),
+        ] {
+            if top_p.is_some_and(|p| p <= 0.0 || p > 1.0) {
+                anyhow::bail!("{field} must be greater than 0 and at most 1");
+            }
+        }
+        for (field, model) in [
+            ("ocr_model", &self.ocr_model),
+            ("summary_model", &self.summary_model),
+        ] {
+            if model.trim().is_empty() {
+                anyhow::bail!("{field} must not be empty");
+            }
+        }
+        for (field, models) in [
+            ("ocr_fallback_models", &self.ocr_fallback_models),
+            ("summary_fallback_models", &self.summary_fallback_models),
+        ] {
+            if models.iter().any(|model| model.trim().is_empty()) {
+                anyhow::bail!("{field} must not contain an empty model name");
+            }
+        }
+        if !(1..=100).contains(&self.compress_summary) {
+            anyhow::bail!(
+                "compress_summary must be between 1 and 100, not {}",
+                self.compress_summary
+            );
+        }
+        if self.output_language.trim().is_empty() {
+            anyhow::bail!("output_language must not be empty");
+        }
+        if self.max_image_dimension == 0 {
+            anyhow::bail!("max_image_dimension must be at least 1");
+        }
+        if self.max_concurrency == 0 {
+            anyhow::bail!("max_concurrency must be at least 1");
+        }
+        self.output_name_template.validate()?;
+        self.api_key_source.validate()?;
+        if self.single_pass && self.ocr_model != self.summary_model {
+            anyhow::bail!(
+                "single_pass needs ocr_model and summary_model to be the same model \
+                 ('{}' vs '{}')",
+                self.ocr_model,
+                self.summary_model
+            );
+        }
+        extraction_tools::validate(&self.extraction_tools)?;
+        if self.sectioned && self.input_char_limit().is_some() {
+            anyhow::bail!("sectioned and max_input_chars/max_input_tokens cannot be used together");
+        }
+        if self.sectioned && self.messages_template_path.is_some() {
+            anyhow::bail!("sectioned and messages_template_path cannot be used together");
+        }
+        if self.summary_prompt_template.is_some() {
+            if self.sectioned {
+                anyhow::bail!("sectioned and summary_prompt_template cannot be used together");
+            }
+            if self.messages_template_path.is_some() {
+                anyhow::bail!(
+                    "messages_template_path and summary_prompt_template cannot be used together"
+                );
+            }
+        }
+        if let Some(name) = &self.profile {
+            if self.document_profile().is_none() {
+                let mut known: Vec<&str> = BUILTIN_PROFILES.to_vec();
+                known.extend(self.profiles.keys().map(String::as_str));
+                known.sort_unstable();
+                known.dedup();
+                anyhow::bail!(
+                    "profile: unknown profile '{name}' (known: {})",
+                    known.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the selected document profile, if any.
+    pub fn document_profile(&self) -> Option<DocumentProfile> {
+        let name = self.profile.as_deref()?;
+        self.profiles
+            .get(name)
+            .cloned()
+            .or_else(|| document_profile::builtin(name))
+    }
+
+    /// Returns the parsed `output_language`.
+    pub fn language(&self) -> OutputLanguage {
+        OutputLanguage::parse(&self.output_language)
+    }
+
+    /// Returns the length in characters above which the combined input is
+    /// summarized in chunks, from `max_input_chars` and `max_input_tokens`.
+    pub fn input_char_limit(&self) -> Option<usize> {
+        let from_tokens = self.max_input_tokens.saturating_mul(CHARS_PER_TOKEN);
+        [self.max_input_chars, from_tokens]
+            .into_iter()
+            .filter(|&limit| limit > 0)
+            .min()
+    }
+
+    /// Returns the context window of `model` in tokens: the configured limit,
+    /// else the built-in one, else [`DEFAULT_CONTEXT_LIMIT`].
+    pub fn context_limit_for(&self, model: &str) -> u64 {
+        self.model_context_limits
+            .get(model)
+            .copied()
+            .or_else(|| {
+                BUILTIN_CONTEXT_LIMITS
+                    .iter()
+                    .find(|(name, _)| *name == model)
+                    .map(|(_, limit)| *limit)
+            })
+            .unwrap_or(DEFAULT_CONTEXT_LIMIT)
+    }
+
+    /// Returns `compress_summary`, which loading checks is within [1, 100].
     pub fn compress_percent(&self) -> u8 {
-        self.compress_summary.clamp(1, 100)
+        self.compress_summary
+    }
+}
+
+/// Current layout version of the config file.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Upgrades a config in place by one layout version, returning warnings.
+type Migration = fn(&mut Mapping) -> Result<Vec<String>>;
+
+/// Upgrades from each older layout: step `n` turns a version `n` config into
+/// version `n + 1`, returning warnings about what it changed. Add a step (and
+/// bump [`CONFIG_VERSION`]) whenever the layout changes.
+const MIGRATIONS: &[Migration] = &[split_model_lists];
+
+const _: () = assert!(MIGRATIONS.len() == CONFIG_VERSION as usize);
+
+/// Upgrades a parsed config file to the current layout in place and returns
+/// warnings about the deprecated layouts it found. Fails for configs written
+/// for a newer version.
+fn migrate(value: &mut serde_yaml::Value) -> Result<Vec<String>> {
+    let Some(map) = value.as_mapping_mut() else {
+        anyhow::bail!("Failed to parse config YAML: expected a mapping of fields");
+    };
+    let version = match map.get("config_version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("config_version must be a non-negative integer")?,
+    };
+    if version > CONFIG_VERSION {
+        anyhow::bail!(
+            "config_version {version} is newer than the supported version {CONFIG_VERSION}; \
+             update the program"
+        );
+    }
+
+    let mut warnings = Vec::new();
+    for step in &MIGRATIONS[version as usize..] {
+        warnings.extend(step(map)?);
+    }
+    map.insert("config_version".into(), CONFIG_VERSION.into());
+    Ok(warnings)
+}
+
+/// Version 0 to 1: `ocr_model` and `summary_model` given as a list of models
+/// to try in turn become the first model, with the others put in front of
+/// `ocr_fallback_models` / `summary_fallback_models`.
+fn split_model_lists(map: &mut Mapping) -> Result<Vec<String>> {
+    let mut warnings = Vec::new();
+    for (field, fallbacks) in [
+        ("ocr_model", "ocr_fallback_models"),
+        ("summary_model", "summary_fallback_models"),
+    ] {
+        let Some(serde_yaml::Value::Sequence(models)) = map.get(field) else {
+            continue;
+        };
+        let mut models = models.clone();
+        if models.is_empty() {
+            anyhow::bail!("{field} must name at least one model");
+        }
+        let primary = models.remove(0);
+        if let Some(serde_yaml::Value::Sequence(existing)) = map.get(fallbacks) {
+            models.extend(existing.iter().cloned());
+        }
+        warnings.push(format!(
+            "A list of models in '{field}' is deprecated; set its first model there and \
+             the others in '{fallbacks}'"
+        ));
+        map.insert(field.into(), primary);
+        map.insert(fallbacks.into(), models.into());
+    }
+    Ok(warnings)
+}
+
+/// Splits a serde "unknown field" error into the path of the mapping holding
+/// the field (empty on the top level of the config) and the field's name.
+fn unknown_field(message: &str) -> Option<(String, String)> {
+    let (path, rest) = match message.split_once(": unknown field `") {
+        Some((path, rest)) => (path, rest),
+        None => ("", message.strip_prefix("unknown field `")?),
+    };
+    let (field, _) = rest.split_once('`')?;
+    Some((path.to_string(), field.to_string()))
+}
+
+/// Strips the " at line L column C" suffix from a serde_yaml error message.
+fn without_location(message: &str) -> &str {
+    message
+        .rsplit_once(" at line ")
+        .map_or(message, |(message, _)| message)
+}
+
+/// Returns the value at a serde error path such as `profiles.memo` or
+/// `extraction_tools[0]`. Keys may contain dots themselves (model names in
+/// `model_pricing`), so the longest key matching the path wins.
+fn value_at_path<'a>(
+    value: &'a mut serde_yaml::Value,
+    path: &str,
+) -> Option<&'a mut serde_yaml::Value> {
+    if path.is_empty() {
+        return Some(value);
+    }
+    if let Some(rest) = path.strip_prefix('[') {
+        let (index, rest) = rest.split_once(']')?;
+        let item = value
+            .as_sequence_mut()?
+            .get_mut(index.parse::<usize>().ok()?)?;
+        return value_at_path(item, rest.strip_prefix('.').unwrap_or(rest));
+    }
+    let map = value.as_mapping_mut()?;
+    let key = map
+        .keys()
+        .filter_map(serde_yaml::Value::as_str)
+        .filter(|key| {
+            path.strip_prefix(key)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+        })
+        .max_by_key(|key| key.len())?
+        .to_string();
+    let rest = &path[key.len()..];
+    value_at_path(
+        map.get_mut(key.as_str())?,
+        rest.strip_prefix('.').unwrap_or(rest),
+    )
+}
+
+async fn fetch_config(url: &str) -> Result<String> {
+    let response = reqwest::get(url)
+        .await
+        .with_context(|| format!("Failed to fetch config from {url}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Failed to fetch config from {url}: HTTP {status}");
     }
+    response
+        .text()
+        .await
+        .with_context(|| format!("Failed to read config body from {url}"))
 }
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+use std::fmt::Write;
+
+/// A named preset of summary prompt settings for one kind of document,
+/// selected with the `profile` config field.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DocumentProfile {
+    /// What the documents are and what the model should know about the domain.
+    pub context: String,
+    /// What the summary should concentrate on.
+    pub focus: String,
+    /// How the summary should be written.
+    pub style: String,
+    /// Fields to extract from every document into a table (empty = none).
+    pub schema: Vec<String>,
+}
+
+/// Names of the built-in profiles.
+pub const BUILTIN_PROFILES: &[&str] = &["resume", "contract", "invoice"];
+
+/// Returns the built-in profile called `name`, if there is one.
+pub fn builtin(name: &str) -> Option<DocumentProfile> {
+    let (context, focus, style, schema): (&str, &str, &str, &[&str]) = match name {
+        "resume" => (
+            "Документы — резюме кандидатов на вакансии.",
+            "Опыт работы, ключевые навыки, образование и достижения каждого кандидата.",
+            "Краткий деловой стиль, единая структура раздела для каждого кандидата.",
+            &[
+                "Кандидат",
+                "Должность",
+                "Опыт (лет)",
+                "Ключевые навыки",
+                "Образование",
+            ],
+        ),
+        "contract" => (
+            "Документы — договоры и соглашения.",
+            "Стороны, предмет, сроки, суммы, обязательства, ответственность и условия расторжения.",
+            "Точные юридические формулировки без упрощений; даты и суммы — как в тексте.",
+            &["Договор", "Стороны", "Предмет", "Срок действия", "Сумма"],
+        ),
+        "invoice" => (
+            "Документы — счета и счета-фактуры.",
+            "Поставщик, покупатель, позиции, суммы, налоги и сроки оплаты.",
+            "Сжато, с точными числами и валютой.",
+            &[
+                "Номер",
+                "Дата",
+                "Поставщик",
+                "Покупатель",
+                "Сумма",
+                "Срок оплаты",
+            ],
+        ),
+        _ => return None,
+    };
+    Some(DocumentProfile {
+        context: context.to_string(),
+        focus: focus.to_string(),
+        style: style.to_string(),
+        schema: schema.iter().map(|f| f.to_string()).collect(),
+    })
+}
+
+impl DocumentProfile {
+    /// Renders the profile as instructions for the summary system prompt.
+    /// Empty settings are left out.
+    pub fn prompt_section(&self) -> String {
+        let mut section = String::new();
+        for (label, value) in [
+            ("КОНТЕКСТ", &self.context),
+            ("ФОКУС", &self.focus),
+            ("СТИЛЬ", &self.style),
+        ] {
+            let value = value.trim();
+            if !value.is_empty() {
+                let _ = writeln!(section, "{label}: {value}");
+            }
+        }
+        if !self.schema.is_empty() {
+            let _ = writeln!(
+                section,
+                "Добавь Markdown-таблицу, в которой для каждого документа указаны поля: {}. \
+                 Если значение в документе отсутствует, ставь «—».",
+                self.schema.join(", ")
+            );
+        }
+        if !section.is_empty() {
+            section.push('\n');
+        }
+        section
+    }
+}
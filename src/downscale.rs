@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+
+/// JPEG quality of downscaled photos; high enough to keep small print legible.
+const JPEG_QUALITY: u8 = 85;
+
+/// An image re-encoded at a smaller size.
+pub struct Downscaled {
+    pub bytes: Vec<u8>,
+    pub mime: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Shrinks a JPEG, PNG or WebP image so that its longest side is at most
+/// `max_dimension` pixels, honouring the EXIF orientation of photos.
+/// Images with transparency are re-encoded as PNG, others as JPEG.
+///
+/// Returns `None` for other formats, for images that already fit and when
+/// the re-encoded image would not be smaller than `bytes`.
+pub fn downscale(bytes: &[u8], max_dimension: u32) -> Result<Option<Downscaled>> {
+    let reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    if !matches!(
+        reader.format(),
+        Some(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)
+    ) {
+        return Ok(None);
+    }
+    let mut decoder = reader.into_decoder().context("Unreadable image")?;
+    let (width, height) = decoder.dimensions();
+    if width.max(height) <= max_dimension {
+        return Ok(None);
+    }
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder).context("Unreadable image")?;
+    image.apply_orientation(orientation);
+    let image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+
+    let mut encoded = Vec::new();
+    let mime = if image.color().has_alpha() {
+        image.write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)?;
+        "image/png"
+    } else {
+        let encoder = JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY);
+        image.to_rgb8().write_with_encoder(encoder)?;
+        "image/jpeg"
+    };
+    if encoded.len() >= bytes.len() {
+        return Ok(None);
+    }
+    Ok(Some(Downscaled {
+        bytes: encoded,
+        mime,
+        width: image.width(),
+        height: image.height(),
+    }))
+}
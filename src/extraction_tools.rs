@@ -0,0 +1,87 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::api::{ChatApi, ChatParams, ToolCall};
+
+/// A function the model can call to report structured fields of a document,
+/// configured under `extraction_tools`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractionTool {
+    /// Function name: letters, digits, `_` and `-`, at most 64 characters.
+    pub name: String,
+    /// What the function records; tells the model when to call it.
+    #[serde(default)]
+    pub description: String,
+    /// JSON Schema of the arguments (an `object` schema).
+    pub parameters: Value,
+}
+
+impl ExtractionTool {
+    /// Returns the definition in OpenAI `tools` format.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            }
+        })
+    }
+}
+
+/// Checks the tool definitions: valid, unique names and object schemas.
+pub fn validate(tools: &[ExtractionTool]) -> Result<()> {
+    for (i, tool) in tools.iter().enumerate() {
+        let valid_name = !tool.name.is_empty()
+            && tool.name.len() <= 64
+            && tool
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            anyhow::bail!(
+                "extraction_tools: '{}' is not a valid function name \
+                 (letters, digits, '_' and '-', at most 64 characters)",
+                tool.name
+            );
+        }
+        if tools[..i].iter().any(|t| t.name == tool.name) {
+            anyhow::bail!("extraction_tools: '{}' is defined twice", tool.name);
+        }
+        if !tool.parameters.is_object() {
+            anyhow::bail!(
+                "extraction_tools: parameters of '{}' must be a JSON Schema object",
+                tool.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Offers `tools` to `model` together with the extracted `text` of a document
+/// and returns the calls the model made. A reply without calls yields an
+/// empty list.
+pub async fn extract_fields(
+    api: &dyn ChatApi,
+    model: &str,
+    tools: &[ExtractionTool],
+    text: &str,
+) -> Result<Vec<ToolCall>> {
+    let messages = vec![
+        json!({"role": "system", "content":
+            "Ты извлекаешь структурированные данные из документа. \
+             Для каждого набора сведений, который есть в документе, вызови подходящую \
+             функцию и передай значения точно так, как они указаны в тексте. \
+             Не придумывай значения, которых в документе нет."}),
+        json!({"role": "user", "content": text}),
+    ];
+    let params = ChatParams {
+        tools: Some(tools.iter().map(ExtractionTool::to_json).collect()),
+        ..ChatParams::default()
+    };
+    let response = api.chat_with_params(model, messages, &params).await?;
+    Ok(response.tool_calls)
+}
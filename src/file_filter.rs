@@ -0,0 +1,41 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// The `include` and `exclude` globs, matched against each file's path
+/// relative to the scanned directory or its base name.
+#[derive(Debug, Default)]
+pub struct FileFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl FileFilter {
+    /// Compiles the patterns; an empty `include` admits every file.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: compile("include", include)?,
+            exclude: compile("exclude", exclude)?,
+        })
+    }
+
+    /// Returns `true` if the file at `relative` passes both lists.
+    pub fn matches(&self, relative: &Path) -> bool {
+        let hit = |set: &GlobSet| {
+            set.is_match(relative) || relative.file_name().is_some_and(|b| set.is_match(b))
+        };
+        self.include.as_ref().is_none_or(hit) && !self.exclude.as_ref().is_some_and(hit)
+    }
+}
+
+fn compile(field: &str, patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder
+            .add(Glob::new(pattern).with_context(|| format!("{field}: invalid glob '{pattern}'"))?);
+    }
+    Ok(Some(builder.build()?))
+}
@@ -1,18 +1,39 @@
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::{future, stream, StreamExt};
 use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
 use std::fs;
-use std::path::Path;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::api::OpenRouterClient;
+use crate::api::{ChatApi, ChatParams, ToolCall, Usage};
+use crate::confidence::{self, LOW_CONFIDENCE};
+use crate::config::{Config, DuplicateNames, RefusalPolicy};
+use crate::downscale::{self, Downscaled};
+use crate::extraction_tools;
+use crate::gif::Gif;
+use crate::html::html_to_text;
+use crate::key_points::csv_output_path;
 use crate::logger::Logger;
+use crate::manifest::manifest_path;
+use crate::ocr_cache::OcrCache;
+use crate::office::{self, OFFICE_EXTENSIONS};
+use crate::progress::ProgressEvent;
+use crate::sanitize;
+use crate::summary::{part_output_path, partial_output_path, sections_checkpoint_dir};
 
 // ---------------------------------------------------------------------------
 // File type classification
 // ---------------------------------------------------------------------------
 
 const TEXT_EXTENSIONS: &[&str] = &[
-    "txt", "md", "rs", "toml", "yaml", "yml", "json", "csv", "log", "cfg", "ini", "xml", "html", "css", "js", "ts", "py", "sh", "bat", "c", "cpp", "h", "hpp", "java", "go", "rb", "php", "sql", "r", "swift", "kt", "scala", "tex", "rtf",
+    "txt", "md", "rs", "toml", "yaml", "yml", "json", "csv", "log", "cfg", "ini", "xml", "html",
+    "htm", "css", "js", "ts", "py", "sh", "bat", "c", "cpp", "h", "hpp", "java", "go", "rb", "php",
+    "sql", "r", "swift", "kt", "scala", "tex", "rtf",
 ];
 
 const IMAGE_EXTENSIONS: &[&str] = &[
@@ -23,20 +44,109 @@ enum FileType {
     Text,
     Pdf,
     Image,
+    /// Word and Excel documents, whose text is extracted locally.
+    Office,
 }
 
-fn classify_file(ext: &str) -> Option<FileType> {
+fn classify_file(ext: &str, config: &Config) -> Option<FileType> {
+    let configured = |extra: &[String]| extra.iter().any(|e| e == ext);
     if ext == "pdf" {
         Some(FileType::Pdf)
-    } else if IMAGE_EXTENSIONS.contains(&ext) {
+    } else if OFFICE_EXTENSIONS.contains(&ext) {
+        Some(FileType::Office)
+    } else if IMAGE_EXTENSIONS.contains(&ext) || configured(&config.extra_image_extensions) {
         Some(FileType::Image)
-    } else if TEXT_EXTENSIONS.contains(&ext) {
+    } else if TEXT_EXTENSIONS.contains(&ext) || configured(&config.extra_text_extensions) {
         Some(FileType::Text)
     } else {
         None
     }
 }
 
+/// Bytes read from the start of a file to recognize its type by content.
+const MAGIC_LEN: usize = 16;
+
+/// Classifies a file by its extension or, when that is missing or unknown,
+/// by its first bytes, so that a PDF or image saved without an extension or
+/// under a wrong one (`scan.dat`) still reaches the right extractor.
+fn detect_file_type(path: &Path, ext: &str, config: &Config) -> Option<FileType> {
+    classify_file(ext, config).or_else(|| sniff_file_type(path))
+}
+
+/// Recognizes PDFs and images by their magic bytes.
+fn sniff_file_type(path: &Path) -> Option<FileType> {
+    let mut head = Vec::with_capacity(MAGIC_LEN);
+    fs::File::open(path)
+        .ok()?
+        .take(MAGIC_LEN as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    if head.starts_with(b"%PDF") {
+        Some(FileType::Pdf)
+    } else {
+        sniff_image_mime(&head).map(|_| FileType::Image)
+    }
+}
+
+/// Returns the MIME type of an image, from its content where recognizable
+/// and otherwise from its extension.
+fn image_mime(bytes: &[u8], ext: &str) -> &'static str {
+    sniff_image_mime(bytes).unwrap_or_else(|| mime_type_for_image(ext))
+}
+
+/// Downscales an image larger than `image_resize_threshold_kib` before it
+/// is sent, logging the sizes before and after. Returns `None` if the image
+/// is sent as is: it is small or already fits, or could not be decoded.
+fn shrink_image(
+    bytes: &[u8],
+    filename: &str,
+    config: &Config,
+    logger: &Logger,
+) -> Option<Downscaled> {
+    let threshold = config.image_resize_threshold_kib;
+    if threshold == 0 || (bytes.len() as u64) <= threshold * 1024 {
+        return None;
+    }
+    match downscale::downscale(bytes, config.max_image_dimension) {
+        Ok(Some(image)) => {
+            logger.info(&format!(
+                "  Downscaled '{filename}' to {}x{}: {:.1} KiB -> {:.1} KiB",
+                image.width,
+                image.height,
+                bytes.len() as f64 / 1024.0,
+                image.bytes.len() as f64 / 1024.0
+            ));
+            Some(image)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            logger.warn(&format!(
+                "  Could not downscale '{filename}' ({e:#}); sending it as is"
+            ));
+            None
+        }
+    }
+}
+
+/// Returns the effective `(text, image)` extension lists: built-ins plus the
+/// configured extras.
+pub fn effective_extensions(config: &Config) -> (Vec<&str>, Vec<&str>) {
+    (
+        merge_extensions(TEXT_EXTENSIONS, &config.extra_text_extensions),
+        merge_extensions(IMAGE_EXTENSIONS, &config.extra_image_extensions),
+    )
+}
+
+fn merge_extensions<'a>(builtin: &[&'static str], extra: &'a [String]) -> Vec<&'a str> {
+    let mut merged: Vec<&str> = builtin.to_vec();
+    for ext in extra {
+        if !merged.contains(&ext.as_str()) {
+            merged.push(ext);
+        }
+    }
+    merged
+}
+
 /// Returns the MIME type for a given image file extension.
 fn mime_type_for_image(ext: &str) -> &'static str {
     match ext {
@@ -54,16 +164,469 @@ fn mime_type_for_image(ext: &str) -> &'static str {
 // Individual file extractors
 // ---------------------------------------------------------------------------
 
-/// Reads and base64-encodes a file, returning `(base64_string, extension)`.
-fn read_and_encode(path: &Path) -> Result<(String, String)> {
-    let bytes =
-        fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+/// Attempts made at a file operation that keeps failing because the process
+/// or the system is out of file descriptors.
+const FD_EXHAUSTED_ATTEMPTS: u32 = 5;
+
+/// Returns `true` if `error` is EMFILE or ENFILE (too many open files).
+fn is_fd_exhausted(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        use rustix::io::Errno;
+        matches!(
+            Errno::from_io_error(error),
+            Some(Errno::MFILE | Errno::NFILE)
+        )
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+/// Runs `op`, backing off and retrying while it fails for lack of file
+/// descriptors instead of failing the file outright.
+fn retry_on_fd_exhaustion<T>(mut op: impl FnMut() -> std::io::Result<T>) -> std::io::Result<T> {
+    let mut delay = Duration::from_millis(50);
+    for _ in 1..FD_EXHAUSTED_ATTEMPTS {
+        match op() {
+            Err(e) if is_fd_exhausted(&e) => {
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    op()
+}
+
+/// Decodes `bytes` as UTF-8. With `repair` set, invalid sequences become
+/// U+FFFD and their number is returned; otherwise they are an error.
+fn decode_text(bytes: Vec<u8>, repair: bool) -> std::io::Result<(String, usize)> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok((text, 0)),
+        Err(e) if repair => {
+            let (text, repairs) = sanitize::repair_utf8(e.as_bytes());
+            Ok((text.into_owned(), repairs))
+        }
+        Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+    }
+}
+
+/// Drops a leading byte-order mark (`strip_bom`) so it does not leak into
+/// the combined text or word counts.
+fn strip_bom(content: String) -> String {
+    match content.strip_prefix('\u{feff}') {
+        Some(stripped) => stripped.to_string(),
+        None => content,
+    }
+}
+
+/// Reads a UTF-8 text file, dropping a leading byte-order mark if `bom` says
+/// so. Returns the content and the number of invalid sequences repaired.
+fn read_text(path: &Path, repair: bool, bom: bool) -> std::io::Result<(String, usize)> {
+    let bytes = retry_on_fd_exhaustion(|| fs::read(path))?;
+    let (content, repairs) = decode_text(bytes, repair)?;
+    Ok((if bom { strip_bom(content) } else { content }, repairs))
+}
+
+/// Text files larger than this are streamed line by line instead of being
+/// read into memory at once.
+const LARGE_TEXT_FILE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// A text file as read by [`load_text`].
+struct LoadedText {
+    content: String,
+    /// Lines dropped by `max_lines_per_file`, if any.
+    omitted: Option<usize>,
+    /// Invalid UTF-8 sequences replaced with U+FFFD.
+    repairs: usize,
+}
+
+/// Reads a text file (converting HTML to text) and applies
+/// `max_lines_per_file`. Large files being truncated are streamed so the
+/// dropped lines are never held in memory; otherwise the whole file is needed
+/// anyway and is read at once.
+fn load_text(path: &Path, ext: &str, config: &Config) -> std::io::Result<LoadedText> {
+    let is_html = matches!(ext, "html" | "htm");
+    if !is_html
+        && config.max_lines_per_file > 0
+        && fs::metadata(path)?.len() > LARGE_TEXT_FILE_BYTES
+    {
+        return read_text_streaming(
+            path,
+            config.read_buffer_kib.max(1) * 1024,
+            config.max_lines_per_file,
+            config.repair_invalid_utf8,
+            config.strip_bom,
+        );
+    }
+
+    let (mut content, repairs) = read_text(path, config.repair_invalid_utf8, config.strip_bom)?;
+    if is_html {
+        content = html_to_text(&content);
+    }
+    let omitted = truncate_lines(&mut content, config.max_lines_per_file);
+    Ok(LoadedText {
+        content,
+        omitted,
+        repairs,
+    })
+}
+
+/// Streaming counterpart of [`read_text`] + [`truncate_lines`]: reads `path`
+/// through a `buffer_size` buffer, keeping only the first and last of at most
+/// `max_lines` lines. The result is the same as reading the whole file and
+/// truncating it, including the file's own line endings when nothing is cut.
+fn read_text_streaming(
+    path: &Path,
+    buffer_size: usize,
+    max_lines: usize,
+    repair: bool,
+    bom: bool,
+) -> std::io::Result<LoadedText> {
+    let file = retry_on_fd_exhaustion(|| fs::File::open(path))?;
+    let mut reader = BufReader::with_capacity(buffer_size, file);
+    let head_len = max_lines.div_ceil(2);
+    let tail_len = max_lines - head_len;
+
+    // Lines are kept with their line endings until we know whether any are cut.
+    let mut head = String::new();
+    let mut head_count = 0;
+    let mut tail: VecDeque<String> = VecDeque::with_capacity(tail_len);
+    let mut total = 0;
+    let mut repairs = 0;
+    let mut raw = Vec::new();
+    while reader.read_until(b'\n', &mut raw)? > 0 {
+        let (mut line, line_repairs) = decode_text(std::mem::take(&mut raw), repair)?;
+        repairs += line_repairs;
+        if total == 0 && bom {
+            line = strip_bom(line);
+        }
+        total += 1;
+        if head_count < head_len {
+            head.push_str(&line);
+            head_count += 1;
+        } else if tail_len > 0 {
+            if tail.len() == tail_len {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+    }
+
+    let omitted = total - head_count - tail.len();
+    if omitted == 0 {
+        let mut content = head;
+        content.extend(tail);
+        return Ok(LoadedText {
+            content,
+            omitted: None,
+            repairs,
+        });
+    }
+    let mut content = head.lines().collect::<Vec<_>>().join("\n");
+    let _ = write!(content, "\n[... {omitted} lines omitted ...]\n");
+    let tail: Vec<&str> = tail.iter().flat_map(|line| line.lines()).collect();
+    content.push_str(&tail.join("\n"));
+    Ok(LoadedText {
+        content,
+        omitted: Some(omitted),
+        repairs,
+    })
+}
+
+/// Counts whitespace-separated words in a file without building a `String`,
+/// for statistics where the content itself is not needed. Only ASCII
+/// whitespace separates words. With the `mmap` feature the file is
+/// memory-mapped instead of read in chunks.
+pub fn count_words(path: &Path) -> Result<usize> {
+    let file = retry_on_fd_exhaustion(|| fs::File::open(path))
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut counter = WordCounter::default();
+
+    #[cfg(feature = "mmap")]
+    {
+        // SAFETY: the map is only read, and only while `file` is open; a file
+        // modified concurrently can at worst yield a wrong count.
+        let map = unsafe { memmap2::Mmap::map(&file) }
+            .with_context(|| format!("Failed to map file: {}", path.display()))?;
+        counter.feed(&map);
+    }
+    #[cfg(not(feature = "mmap"))]
+    {
+        let mut reader = BufReader::with_capacity(64 * 1024, file);
+        loop {
+            let chunk = reader
+                .fill_buf()
+                .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            if chunk.is_empty() {
+                break;
+            }
+            counter.feed(chunk);
+            let consumed = chunk.len();
+            reader.consume(consumed);
+        }
+    }
+    Ok(counter.words)
+}
+
+/// Word counter that can be fed a file in chunks.
+#[derive(Default)]
+struct WordCounter {
+    words: usize,
+    in_word: bool,
+}
+
+impl WordCounter {
+    fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            let is_space = byte.is_ascii_whitespace();
+            if !is_space && !self.in_word {
+                self.words += 1;
+            }
+            self.in_word = !is_space;
+        }
+    }
+}
+
+/// Bytes inspected when deciding whether a file of unknown type is text.
+const SNIFF_LEN: usize = 8192;
+
+/// Reads a file of unknown type as text if its content looks like text:
+/// valid UTF-8, no NUL bytes near the start and no known binary signature.
+/// Returns `None` for anything that looks binary.
+fn sniff_text(path: &Path, bom: bool) -> Result<Option<String>> {
+    let bytes = retry_on_fd_exhaustion(|| fs::read(path))
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let head = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if head.contains(&0) || head.starts_with(b"%PDF") || sniff_image_mime(head).is_some() {
+        return Ok(None);
+    }
+    let Ok(content) = String::from_utf8(bytes) else {
+        return Ok(None);
+    };
+    Ok(Some(if bom { strip_bom(content) } else { content }))
+}
+
+/// Keeps the first and last lines of `content` so that at most `max_lines`
+/// remain, joined by an elision marker. Returns the number of omitted lines,
+/// or `None` if the content already fits.
+fn truncate_lines(content: &mut String, max_lines: usize) -> Option<usize> {
+    let lines: Vec<&str> = content.lines().collect();
+    if max_lines == 0 || lines.len() <= max_lines {
+        return None;
+    }
+    let head = max_lines.div_ceil(2);
+    let tail = max_lines - head;
+    let omitted = lines.len() - max_lines;
+
+    let mut truncated = lines[..head].join("\n");
+    let _ = write!(truncated, "\n[... {omitted} lines omitted ...]\n");
+    truncated.push_str(&lines[lines.len() - tail..].join("\n"));
+    *content = truncated;
+    Some(omitted)
+}
+
+/// Reads a file, returning `(bytes, lowercase_extension)`.
+fn read_bytes(path: &Path) -> Result<(Vec<u8>, String)> {
+    let bytes = retry_on_fd_exhaustion(|| fs::read(path))
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
     let ext = path
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("bin")
         .to_lowercase();
-    Ok((BASE64.encode(&bytes), ext))
+    Ok((bytes, ext))
+}
+
+/// Detects the image MIME type from the file's magic bytes.
+fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => Some("image/tiff"),
+        _ => None,
+    }
+}
+
+/// Error fragments (lowercase) that indicate the API rejected the image payload.
+const INVALID_IMAGE_MARKERS: &[&str] = &[
+    "invalid image",
+    "image format",
+    "unsupported image",
+    "could not process image",
+    "unable to process image",
+    "image data",
+    "invalid base64",
+];
+
+fn is_invalid_image_error(error: &anyhow::Error) -> bool {
+    let message = format!("{error:#}").to_lowercase();
+    INVALID_IMAGE_MARKERS.iter().any(|m| message.contains(m))
+}
+
+/// Resolves `path` to an absolute path without requiring the file itself to
+/// exist; only its parent directory is canonicalized.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(parent).ok()?.join(path.file_name()?))
+}
+
+/// Returns the resolved paths of every file this tool writes (summary, its
+/// `.partial`, `.prev`, `.csv` and `.meta.json` companions, existing split
+/// parts, section checkpoints, the combined-input dump and the progress
+/// file) plus the instructions file, so a misconfigured `output_path` inside
+/// `files_directory` is never read back as input.
+fn own_output_paths(config: &Config) -> Vec<PathBuf> {
+    let mut paths = vec![
+        config.output_path.clone(),
+        partial_output_path(&config.output_path),
+        format!("{}.prev", config.output_path),
+        csv_output_path(&config.output_path),
+        manifest_path(&config.output_path),
+    ];
+    paths.extend(
+        (1..)
+            .map(|part| part_output_path(config, part))
+            .take_while(|path| Path::new(path).exists()),
+    );
+    paths.extend(config.dump_combined_input.clone());
+    paths.extend(config.progress_file.clone());
+    paths.extend(config.instructions_path.clone());
+    if let Ok(checkpoints) = fs::read_dir(sections_checkpoint_dir(&config.output_path)) {
+        paths.extend(
+            checkpoints
+                .flatten()
+                .map(|entry| entry.path().to_string_lossy().into_owned()),
+        );
+    }
+    paths
+        .iter()
+        .filter_map(|p| resolve_path(Path::new(p)))
+        .collect()
+}
+
+/// Returns `path` relative to `dir`, which identifies a file uniquely even in
+/// recursive mode.
+fn relative_name(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Lists the files to process under `dir`, sorted by path.
+///
+/// The top-level scan uses a plain `read_dir`. In recursive mode the tree is
+/// walked in parallel with `config.walk_threads` threads (at most
+/// `max_open_files`, as each holds a directory open), since a single thread
+/// is slow on trees with tens of thousands of entries. A walk that runs out
+/// of file descriptors fails rather than silently missing directories.
+/// Files rejected by `include`/`exclude` and entries of the OCR cache are
+/// left out.
+fn collect_candidates(dir: &Path, config: &Config) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = if config.recursive {
+        let threads = match config.walk_threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let found = std::sync::Mutex::new(Vec::new());
+        let fd_exhausted = std::sync::atomic::AtomicBool::new(false);
+        ignore::WalkBuilder::new(dir)
+            .standard_filters(false)
+            .threads(threads.min(config.max_open_files.max(1)))
+            .build_parallel()
+            .run(|| {
+                Box::new(|entry| {
+                    match entry {
+                        Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => {
+                            found
+                                .lock()
+                                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                                .push(entry.into_path());
+                        }
+                        Err(e) if e.io_error().is_some_and(is_fd_exhausted) => {
+                            fd_exhausted.store(true, std::sync::atomic::Ordering::Relaxed);
+                            return ignore::WalkState::Quit;
+                        }
+                        _ => {}
+                    }
+                    ignore::WalkState::Continue
+                })
+            });
+        if fd_exhausted.into_inner() {
+            anyhow::bail!(
+                "Ran out of file descriptors while scanning '{}'; lower walk_threads or \
+                 max_open_files, or raise the limit with `ulimit -n`",
+                dir.display()
+            );
+        }
+        found
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    } else {
+        retry_on_fd_exhaustion(|| fs::read_dir(dir))
+            .context("Failed to read directory")?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .collect()
+    };
+    let cache_dir = OcrCache::new(&config.cache_dir).and_then(|c| fs::canonicalize(c.dir()).ok());
+    paths.retain(|path| {
+        config
+            .file_filter
+            .matches(path.strip_prefix(dir).unwrap_or(path))
+            && !cache_dir
+                .as_ref()
+                .is_some_and(|cache| resolve_path(path).is_some_and(|p| p.starts_with(cache)))
+    });
+    paths.sort();
+    Ok(paths)
+}
+
+/// Logs each base name shared by several files. Files keep their paths
+/// relative to the scanned directory as names, which are unique, unless
+/// `policy` is [`DuplicateNames::Suffix`]: then they are renamed to their base
+/// names, numbering repeats. `files` must be sorted by path so numbering is
+/// stable.
+fn disambiguate_names(files: &mut [ProcessedFile], policy: DuplicateNames, logger: &Logger) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for file in files.iter() {
+        *counts.entry(filename_of(&file.path)).or_default() += 1;
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for file in files.iter_mut() {
+        let base = filename_of(&file.path);
+        let count = counts[&base];
+        let occurrence = seen.entry(base.clone()).or_default();
+        *occurrence += 1;
+        if count > 1 && *occurrence == 1 {
+            logger.warn(&format!(
+                "  Name collision: {count} files are named '{base}'"
+            ));
+        }
+        match policy {
+            DuplicateNames::Path => {}
+            DuplicateNames::Suffix if *occurrence == 1 => file.name = base,
+            DuplicateNames::Suffix => {
+                let path = Path::new(&base);
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                file.name = match path.extension() {
+                    Some(ext) => format!("{stem} ({occurrence}).{}", ext.to_string_lossy()),
+                    None => format!("{stem} ({occurrence})"),
+                };
+            }
+        }
+    }
 }
 
 fn filename_of(path: &Path) -> String {
@@ -73,50 +636,572 @@ fn filename_of(path: &Path) -> String {
         .into_owned()
 }
 
+/// Prompt addition asking for tables and code to be reproduced verbatim
+/// instead of described (`ocr_preserve_tables`).
+const PRESERVE_TABLES_INSTRUCTION: &str = " Таблицы воспроизводи как Markdown-таблицы \
+     (с той же структурой строк и столбцов), а не описывай их словами. \
+     Программный код и листинги оформляй как блоки кода в тройных обратных кавычках.";
+
+/// Returns what is appended to every OCR prompt: the table instruction with
+/// `ocr_preserve_tables` and the file's custom `instruction`, if any.
+pub fn ocr_prompt_additions(config: &Config, instruction: Option<&str>) -> String {
+    let mut additions = String::new();
+    if config.ocr_preserve_tables {
+        additions.push_str(PRESERVE_TABLES_INSTRUCTION);
+    }
+    if let Some(instruction) = instruction {
+        let _ = write!(
+            additions,
+            "\n\nДополнительные указания: {}",
+            instruction.trim()
+        );
+    }
+    additions
+}
+
+/// Returns `ocr_prompt_template` filled in for `filename`, if one is set.
+fn custom_ocr_prompt(config: &Config, filename: &str) -> Option<String> {
+    let template = config.ocr_prompt.as_ref()?;
+    Some(template.render(&[
+        ("filename", filename.to_string()),
+        ("language", config.language().name().to_string()),
+    ]))
+}
+
+/// Returns the settings besides the model that shape the OCR text of a file
+/// with the per-file `instruction`, for the cache key: changing any of them
+/// must not return text extracted under the old ones.
+fn ocr_prompt_variant(config: &Config, instruction: Option<&str>) -> String {
+    json!({
+        "language": config.language().code(),
+        "prompt_template": config.ocr_prompt.as_ref().map(|t| t.text()),
+        "additions": ocr_prompt_additions(config, instruction),
+        "on_refusal": format!("{:?}", config.on_refusal),
+        "max_tokens": config.ocr_max_tokens,
+        "temperature": config.ocr_temperature,
+        "top_p": config.ocr_top_p,
+        "gif_frames": config.gif_frames,
+        "image_resize_threshold_kib": config.image_resize_threshold_kib,
+        "max_image_dimension": config.max_image_dimension,
+        "pdf_extract_images": config.pdf_extract_images,
+        "pdf_image_fallback": config.pdf_image_fallback,
+        "split_pdf_pages": config.split_pdf_pages,
+    })
+    .to_string()
+}
+
 /// Builds the OpenRouter messages payload for image OCR.
-fn build_image_messages(data_url: &str) -> Vec<Value> {
+fn build_image_messages(
+    filename: &str,
+    data_url: &str,
+    additions: &str,
+    config: &Config,
+) -> Vec<Value> {
+    let mut prompt = custom_ocr_prompt(config, filename).unwrap_or_else(|| {
+        format!(
+            "Проанализируй это изображение. \
+         Извлеки ВЕСЬ текст, который есть на изображении, сохраняя структуру и форматирование. \
+         Если на изображении есть графики, диаграммы, таблицы или другие визуальные элементы — \
+         опиши их содержимое и данные подробно. \
+         Отвечай {}.",
+            config.language().in_language()
+        )
+    });
+    prompt.push_str(additions);
+
     vec![json!({
         "role": "user",
         "content": [
             {
                 "type": "text",
-                "text": "Проанализируй это изображение. \
-                         Извлеки ВЕСЬ текст, который есть на изображении, сохраняя структуру и форматирование. \
-                         Если на изображении есть графики, диаграммы, таблицы или другие визуальные элементы — \
-                         опиши их содержимое и данные подробно. \
-                         Отвечай на русском языке."
+                "text": prompt
             },
-            {
-                "type": "image_url",
-                "image_url": { "url": data_url }
-            }
+            image_part(data_url)
         ]
     })]
 }
 
+/// Message content part carrying an image.
+fn image_part(data_url: &str) -> Value {
+    json!({
+        "type": "image_url",
+        "image_url": { "url": data_url }
+    })
+}
+
 /// Builds the OpenRouter messages payload for PDF extraction.
-fn build_pdf_messages(filename: &str, data_url: &str) -> Vec<Value> {
+fn build_pdf_messages(
+    filename: &str,
+    data_url: &str,
+    additions: &str,
+    config: &Config,
+) -> Vec<Value> {
+    let mut prompt = custom_ocr_prompt(config, filename).unwrap_or_else(|| {
+        format!(
+            "Извлеки ВЕСЬ текст из этого PDF документа, сохраняя структуру и форматирование. \
+         Если в документе есть графики, диаграммы, таблицы или изображения — \
+         опиши их содержимое подробно. \
+         Отвечай {}.",
+            config.language().in_language()
+        )
+    });
+    prompt.push_str(additions);
+
     vec![json!({
         "role": "user",
         "content": [
             {
                 "type": "text",
-                "text": "Извлеки ВЕСЬ текст из этого PDF документа, сохраняя структуру и форматирование. \
-                         Если в документе есть графики, диаграммы, таблицы или изображения — \
-                         опиши их содержимое подробно. \
-                         Отвечай на русском языке."
+                "text": prompt
             },
-            {
-                "type": "file",
-                "file": {
-                    "filename": filename,
-                    "file_data": data_url
-                }
-            }
+            pdf_part(filename, data_url)
         ]
     })]
 }
 
+/// Message content part carrying a PDF document.
+fn pdf_part(filename: &str, data_url: &str) -> Value {
+    json!({
+        "type": "file",
+        "file": {
+            "filename": filename,
+            "file_data": data_url
+        }
+    })
+}
+
+/// Returns the only image or PDF to process when the input is exactly one
+/// such file: `file` if given, otherwise the sole candidate in
+/// `files_directory`. Used to decide whether `single_pass` applies.
+pub fn single_document(config: &Config, file: Option<&Path>) -> Result<Option<PathBuf>> {
+    let candidates = match file {
+        Some(path) if path.is_dir() => return Ok(None),
+        Some(path) => vec![path.to_path_buf()],
+        None => {
+            let dir = Path::new(&config.files_directory);
+            if !dir.is_dir() {
+                return Ok(None);
+            }
+            let own_outputs = own_output_paths(config);
+            collect_candidates(dir, config)?
+                .into_iter()
+                .filter(|path| path.is_file())
+                .filter(|path| !resolve_path(path).is_some_and(|p| own_outputs.contains(&p)))
+                .collect()
+        }
+    };
+    let [path] = candidates.as_slice() else {
+        return Ok(None);
+    };
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let is_document = matches!(
+        detect_file_type(path, &ext, config),
+        Some(FileType::Image | FileType::Pdf)
+    );
+    Ok(is_document.then(|| path.clone()))
+}
+
+/// Reads the image or PDF at `path` into the message content part that sends
+/// it to the model.
+pub fn document_part(path: &Path, config: &Config) -> Result<Value> {
+    let (bytes, ext) = read_bytes(path)?;
+    let b64 = BASE64.encode(&bytes);
+    Ok(match detect_file_type(path, &ext, config) {
+        Some(FileType::Pdf) => pdf_part(
+            &filename_of(path),
+            &format!("data:application/pdf;base64,{b64}"),
+        ),
+        _ => image_part(&format!("data:{};base64,{b64}", image_mime(&bytes, &ext))),
+    })
+}
+
+/// Embedded images smaller than this (in either dimension) are treated as
+/// decorations rather than figures worth OCRing.
+const MIN_FIGURE_DIMENSION: i64 = 64;
+
+/// An image embedded in a PDF page, in a format the vision model accepts.
+struct PdfFigure {
+    page: u32,
+    /// `image/jpeg` or `image/png`.
+    mime: &'static str,
+    data: Vec<u8>,
+}
+
+/// Collects the images embedded in the PDF, in page order. JPEG
+/// (`DCTDecode`) images are forwarded as they are; uncompressed and
+/// Flate-compressed images with 8-bit gray, RGB or CMYK samples, or 1-bit
+/// gray ones, are converted to PNG.
+///
+/// Returns the figures plus the number of images skipped because of an
+/// encoding that cannot be converted (such as CCITT fax, JBIG2 or JPEG 2000).
+fn extract_pdf_figures(path: &Path) -> Result<(Vec<PdfFigure>, usize)> {
+    let doc = lopdf::Document::load(path)
+        .with_context(|| format!("Failed to parse PDF: {}", path.display()))?;
+    let mut seen = HashSet::new();
+    let mut figures = Vec::new();
+    let mut skipped = 0;
+
+    for (page, page_id) in doc.get_pages() {
+        let images = doc.get_page_images(page_id).unwrap_or_default();
+        for image in images {
+            if !seen.insert(image.id)
+                || image.width < MIN_FIGURE_DIMENSION
+                || image.height < MIN_FIGURE_DIMENSION
+            {
+                continue;
+            }
+            let filters = image.filters.as_deref().unwrap_or_default();
+            let figure = if filters == ["DCTDecode"] {
+                Some(PdfFigure {
+                    page,
+                    mime: "image/jpeg",
+                    data: image.content.to_vec(),
+                })
+            } else if filters.iter().all(|f| f == "FlateDecode") {
+                raw_image_to_png(&doc, &image).map(|data| PdfFigure {
+                    page,
+                    mime: "image/png",
+                    data,
+                })
+            } else {
+                None
+            };
+            match figure {
+                Some(figure) => figures.push(figure),
+                None => skipped += 1,
+            }
+        }
+    }
+
+    Ok((figures, skipped))
+}
+
+/// Converts an uncompressed or Flate-compressed PDF image to PNG, or returns
+/// `None` for sample layouts other than 8-bit gray/RGB/CMYK and 1-bit gray.
+fn raw_image_to_png(doc: &lopdf::Document, image: &lopdf::xobject::PdfImage) -> Option<Vec<u8>> {
+    let width = u32::try_from(image.width).ok()?;
+    let height = u32::try_from(image.height).ok()?;
+    let components = match image.color_space.as_deref()? {
+        "DeviceGray" | "CalGray" => 1,
+        "DeviceRGB" | "CalRGB" => 3,
+        "DeviceCMYK" => 4,
+        "ICCBased" => icc_components(doc, image.origin_dict)?,
+        _ => return None,
+    };
+    let bits = image.bits_per_component.unwrap_or(8);
+    if !(bits == 8 || (bits == 1 && components == 1)) {
+        return None;
+    }
+    let row_bytes = (width as usize * components * bits as usize).div_ceil(8);
+    let samples = doc
+        .get_object(image.id)
+        .ok()?
+        .as_stream()
+        .ok()?
+        .decompressed_content_with_limit(row_bytes * height as usize)
+        .ok()?;
+    if samples.len() < row_bytes * height as usize {
+        return None;
+    }
+    let rows = samples.chunks_exact(row_bytes).take(height as usize);
+
+    let decoded: image::DynamicImage = match (components, bits) {
+        (1, 1) => {
+            let pixels = rows
+                .flat_map(|row| {
+                    (0..width as usize).map(move |x| {
+                        if row[x / 8] & (0x80 >> (x % 8)) == 0 {
+                            0
+                        } else {
+                            255
+                        }
+                    })
+                })
+                .collect();
+            image::GrayImage::from_raw(width, height, pixels)?.into()
+        }
+        (1, _) => {
+            image::GrayImage::from_raw(width, height, rows.flatten().copied().collect())?.into()
+        }
+        (3, _) => {
+            image::RgbImage::from_raw(width, height, rows.flatten().copied().collect())?.into()
+        }
+        (4, _) => {
+            let pixels = rows
+                .flat_map(|row| row.chunks_exact(4))
+                .flat_map(|cmyk| {
+                    let white = 255 - u32::from(cmyk[3]);
+                    cmyk[..3]
+                        .iter()
+                        .map(move |&c| ((255 - u32::from(c)) * white / 255) as u8)
+                })
+                .collect();
+            image::RgbImage::from_raw(width, height, pixels)?.into()
+        }
+        _ => return None,
+    };
+    let mut png = Vec::new();
+    decoded
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(png)
+}
+
+/// Returns the number of color components (`N`) of an `ICCBased` color space.
+fn icc_components(doc: &lopdf::Document, image: &lopdf::Dictionary) -> Option<usize> {
+    let space = image.get(b"ColorSpace").ok()?.as_array().ok()?;
+    let profile = doc.get_object(space.get(1)?.as_reference().ok()?).ok()?;
+    let n = profile
+        .as_stream()
+        .ok()?
+        .dict
+        .get(b"N")
+        .ok()?
+        .as_i64()
+        .ok()?;
+    usize::try_from(n).ok()
+}
+
+/// Splits a PDF into standalone single-page PDFs, in page order. Each page
+/// copies only the objects it references, so splitting stays linear in the
+/// size of the document.
+pub fn split_pdf_pages(bytes: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let doc = lopdf::Document::load_mem(bytes).context("Failed to parse PDF")?;
+    doc.get_pages()
+        .into_iter()
+        .map(|(number, id)| {
+            let mut page = single_page_document(&doc, id)
+                .with_context(|| format!("Failed to extract page {number}"))?;
+            let mut pdf = Vec::new();
+            page.save_to(&mut pdf)
+                .with_context(|| format!("Failed to write page {number}"))?;
+            Ok(pdf)
+        })
+        .collect()
+}
+
+/// Page attributes a page may inherit from its ancestors in the page tree.
+const INHERITED_PAGE_KEYS: &[&[u8]] = &[b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Builds a one-page document holding page `id` of `doc` and the objects it
+/// reaches. Other pages it links to (annotations, destinations) are left out.
+fn single_page_document(doc: &lopdf::Document, id: lopdf::ObjectId) -> Result<lopdf::Document> {
+    let mut page = doc.get_dictionary(id)?.clone();
+    for &key in INHERITED_PAGE_KEYS {
+        if !page.has(key) {
+            if let Some(value) = inherited_page_attribute(doc, id, key) {
+                page.set(key, value.clone());
+            }
+        }
+    }
+    page.remove(b"Parent");
+
+    let mut single = lopdf::Document::with_version(doc.version.clone());
+    single.max_id = doc.max_id;
+    let mut pending = Vec::new();
+    collect_references(&lopdf::Object::Dictionary(page.clone()), &mut pending);
+    while let Some(reference) = pending.pop() {
+        if reference == id || single.objects.contains_key(&reference) {
+            continue;
+        }
+        let Ok(object) = doc.get_object(reference) else {
+            continue;
+        };
+        let is_page_node = object
+            .as_dict()
+            .ok()
+            .and_then(|d| d.get_type().ok())
+            .is_some_and(|t| t == b"Page" || t == b"Pages");
+        if is_page_node {
+            continue;
+        }
+        collect_references(object, &mut pending);
+        single.objects.insert(reference, object.clone());
+    }
+
+    let pages_id = single.new_object_id();
+    page.set("Parent", pages_id);
+    single.objects.insert(id, lopdf::Object::Dictionary(page));
+    let mut pages = lopdf::Dictionary::new();
+    pages.set("Type", "Pages");
+    pages.set("Kids", vec![lopdf::Object::Reference(id)]);
+    pages.set("Count", 1);
+    single
+        .objects
+        .insert(pages_id, lopdf::Object::Dictionary(pages));
+    let mut catalog = lopdf::Dictionary::new();
+    catalog.set("Type", "Catalog");
+    catalog.set("Pages", pages_id);
+    let catalog_id = single.add_object(catalog);
+    single.trailer.set("Root", catalog_id);
+    single.renumber_objects();
+    Ok(single)
+}
+
+/// Looks `key` up on the ancestors of page `id`, nearest first.
+fn inherited_page_attribute<'a>(
+    doc: &'a lopdf::Document,
+    id: lopdf::ObjectId,
+    key: &[u8],
+) -> Option<&'a lopdf::Object> {
+    let mut node = doc.get_dictionary(id).ok()?;
+    // Bounded so that a malformed, cyclic page tree cannot loop forever.
+    for _ in 0..64 {
+        let parent = node
+            .get(b"Parent")
+            .and_then(lopdf::Object::as_reference)
+            .ok()?;
+        node = doc.get_dictionary(parent).ok()?;
+        if let Ok(value) = node.get(key) {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Appends every object reference inside `object` to `out`.
+fn collect_references(object: &lopdf::Object, out: &mut Vec<lopdf::ObjectId>) {
+    match object {
+        lopdf::Object::Reference(id) => out.push(*id),
+        lopdf::Object::Array(items) => items.iter().for_each(|o| collect_references(o, out)),
+        lopdf::Object::Dictionary(dict) => {
+            dict.iter().for_each(|(_, o)| collect_references(o, out))
+        }
+        lopdf::Object::Stream(stream) => stream
+            .dict
+            .iter()
+            .for_each(|(_, o)| collect_references(o, out)),
+        _ => {}
+    }
+}
+
+/// Reason recorded for files the model declined to extract.
+const REFUSED_BY_MODEL: &str = "refused by model";
+
+/// Phrases that mark a short response as a refusal rather than extracted text.
+const REFUSAL_MARKERS: &[&str] = &[
+    "i can't help",
+    "i cannot help",
+    "i can't assist",
+    "i cannot assist",
+    "i'm unable to",
+    "i am unable to",
+    "i'm sorry, but",
+    "i can't process",
+    "i cannot process",
+    "не могу помочь",
+    "не могу обработать",
+    "не могу выполнить",
+    "не могу извлечь",
+    "извините, но",
+];
+
+/// Refusals are short; long responses containing these phrases are real content.
+const REFUSAL_MAX_CHARS: usize = 500;
+
+fn is_refusal(content: &str) -> bool {
+    if content.chars().count() > REFUSAL_MAX_CHARS {
+        return false;
+    }
+    let lower = content.to_lowercase();
+    REFUSAL_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+/// Prepends a system message explaining the legitimate purpose of the request.
+fn with_refusal_clarification(messages: Vec<Value>) -> Vec<Value> {
+    let mut clarified = vec![json!({
+        "role": "system",
+        "content": "Это легитимная задача обработки документов: пользователь — владелец \
+                    документа или уполномоченное лицо, и ему нужна точная текстовая копия \
+                    для архивирования и анализа. Не оценивай и не дополняй содержимое, \
+                    просто извлеки текст дословно."
+    })];
+    clarified.extend(messages);
+    clarified
+}
+
+/// Sends an extraction request, applying the configured refusal policy.
+async fn request_extraction(
+    api: &dyn ChatApi,
+    config: &Config,
+    messages: Vec<Value>,
+    filename: &str,
+    logger: &Logger,
+    metadata: &mut ExtractionMetadata,
+) -> Result<String> {
+    let retry_messages = match config.on_refusal {
+        RefusalPolicy::Retry => Some(with_refusal_clarification(messages.clone())),
+        RefusalPolicy::Skip => None,
+    };
+
+    let content = ocr_request(api, config, messages, metadata).await?;
+    if !is_refusal(&content) {
+        return Ok(content);
+    }
+
+    if let Some(retry_messages) = retry_messages {
+        logger.warn(&format!(
+            "  Model refused '{filename}', retrying with a clarified prompt..."
+        ));
+        let content = ocr_request(api, config, retry_messages, metadata).await?;
+        if !is_refusal(&content) {
+            return Ok(content);
+        }
+    }
+
+    anyhow::bail!(REFUSED_BY_MODEL)
+}
+
+/// Sends one OCR request and adds its token usage to `metadata`.
+async fn ocr_request(
+    api: &dyn ChatApi,
+    config: &Config,
+    messages: Vec<Value>,
+    metadata: &mut ExtractionMetadata,
+) -> Result<String> {
+    let params = ChatParams {
+        max_tokens: config.ocr_max_tokens,
+        temperature: config.ocr_temperature,
+        top_p: config.ocr_top_p,
+        ..ChatParams::default()
+    };
+    let response = api
+        .chat_with_params(&config.ocr_model, messages, &params)
+        .await?;
+    if let Some(usage) = response.usage {
+        metadata.usage.add(usage);
+    }
+    Ok(response.content)
+}
+
+/// Scores an OCR extraction with the local heuristic and, when
+/// `confidence_check` is set, the OCR model's own rating, keeping the lower.
+async fn extraction_confidence(
+    api: &dyn ChatApi,
+    config: &Config,
+    content: &str,
+    filename: &str,
+    logger: &Logger,
+) -> u8 {
+    let heuristic = confidence::heuristic_confidence(content);
+    if !config.confidence_check {
+        return heuristic;
+    }
+    match confidence::ask_confidence(api, &config.ocr_model, content).await {
+        Ok(rated) => rated.min(heuristic),
+        Err(e) => {
+            logger.warn(&format!(
+                "  Could not rate OCR confidence for '{filename}': {e}"
+            ));
+            heuristic
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
@@ -124,110 +1209,1253 @@ fn build_pdf_messages(filename: &str, data_url: &str) -> Vec<Value> {
 /// A processed file: name + extracted content.
 pub struct ProcessedFile {
     pub name: String,
+    /// Path relative to `files_directory`.
+    pub path: PathBuf,
     pub content: String,
+    /// Wall time spent on API extraction (`None` for files read locally).
+    pub ocr_time: Option<Duration>,
+    /// Estimated OCR confidence, 0-100 (`None` for files read locally).
+    pub confidence: Option<u8>,
+    /// Calls the model made to the configured `extraction_tools` (empty when
+    /// none are configured or the model made no call).
+    pub fields: Vec<ToolCall>,
+    /// How the content was extracted (`None` for files read locally).
+    pub metadata: Option<ExtractionMetadata>,
+}
+
+/// Facts about an API extraction besides its text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractionMetadata {
+    /// Model that read the file.
+    pub model: String,
+    /// Tokens reported for every request made for the file, including
+    /// refusal retries, GIF frames and PDF figures.
+    pub usage: Usage,
+    /// The text came from the OCR cache without any request.
+    pub cached: bool,
+}
+
+impl ExtractionMetadata {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..Self::default()
+        }
+    }
 }
 
-/// Reads all supported files from `dir`, using `api` + `ocr_model` for images/PDFs.
+/// Text returned by an API processor, with how it was obtained.
+struct Extraction {
+    content: String,
+    metadata: ExtractionMetadata,
+}
+
+/// A file that could not be processed, with the reason it was skipped.
+pub struct FileFailure {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Result of scanning a directory: extracted files plus per-file failures.
+#[derive(Default)]
+pub struct ReadOutcome {
+    pub files: Vec<ProcessedFile>,
+    pub failures: Vec<FileFailure>,
+    /// Time spent listing candidate files, before any of them was read.
+    pub scan_time: Duration,
+    /// Number of entries found to read, whatever became of them; 0 means
+    /// the directory was empty.
+    pub candidates: usize,
+    /// Files skipped because their type is not supported.
+    pub unsupported: usize,
+}
+
+impl ReadOutcome {
+    /// Tallies what became of the files, e.g. "3 processed, 5 unsupported,
+    /// 1 failed".
+    pub fn counts(&self) -> String {
+        format!(
+            "{} processed, {} unsupported, {} failed",
+            self.files.len(),
+            self.unsupported,
+            self.failures.len()
+        )
+    }
+
+    /// Logs a consolidated report of every failed file, if any.
+    pub fn log_failures(&self, logger: &Logger) {
+        if self.failures.is_empty() {
+            return;
+        }
+        logger.warn(&format!("{} file(s) failed:", self.failures.len()));
+        for failure in &self.failures {
+            logger.warn(&format!("  {}: {}", failure.name, failure.reason));
+        }
+    }
+}
+
+/// A user-supplied extractor turning a file into text.
+pub type Extractor = Box<dyn Fn(&Path) -> Result<String> + Send + Sync>;
+
+/// Custom extractors keyed by lowercase file extension.
+///
+/// Registered extractors take precedence over the built-in classification, so
+/// they can add new formats or replace how a built-in one is handled.
+#[derive(Default)]
+pub struct ExtractorRegistry {
+    extractors: HashMap<String, Extractor>,
+}
+
+impl ExtractorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `extractor` for files with extension `ext` (case-insensitive,
+    /// without the leading dot), replacing any previous registration.
+    pub fn register<F>(&mut self, ext: &str, extractor: F) -> &mut Self
+    where
+        F: Fn(&Path) -> Result<String> + Send + Sync + 'static,
+    {
+        let ext = ext.trim_start_matches('.').to_lowercase();
+        self.extractors.insert(ext, Box::new(extractor));
+        self
+    }
+
+    fn get(&self, ext: &str) -> Option<&Extractor> {
+        self.extractors.get(ext)
+    }
+}
+
+/// Reads all supported files from `config.files_directory`, using `api` +
+/// `config.ocr_model` for images/PDFs.
+///
+/// The returned files and failures are always sorted by path relative to
+/// `files_directory`, independent of the order in which extractions complete,
+/// so the combined text is reproducible. Files are named by their path
+/// relative to `files_directory`, or by their base name per
+/// [`DuplicateNames`].
 pub async fn read_all_files(
-    dir: &str,
-    api: &OpenRouterClient,
-    ocr_model: &str,
+    config: &Config,
+    api: &dyn ChatApi,
     logger: &Logger,
-) -> Result<Vec<ProcessedFile>> {
+) -> Result<ReadOutcome> {
+    read_all_files_with(config, api, logger, &ExtractorRegistry::default()).await
+}
+
+/// Like [`read_all_files`], but consults `extractors` before the built-in
+/// file classification.
+pub async fn read_all_files_with(
+    config: &Config,
+    api: &dyn ChatApi,
+    logger: &Logger,
+    extractors: &ExtractorRegistry,
+) -> Result<ReadOutcome> {
+    let dir = config.files_directory.as_str();
     let dir_path = Path::new(dir);
     if !dir_path.exists() {
-        anyhow::bail!("Directory '{dir}' does not exist");
+        if !config.create_missing_dir {
+            anyhow::bail!(
+                "Directory '{dir}' does not exist (set create_missing_dir: true to create it)"
+            );
+        }
+        fs::create_dir_all(dir_path)
+            .with_context(|| format!("Failed to create directory '{dir}'"))?;
+        logger.warn(&format!(
+            "Directory '{dir}' did not exist and was created; it has no files yet"
+        ));
+        return Ok(ReadOutcome::default());
+    }
+    if !dir_path.is_dir() {
+        anyhow::bail!("'{dir}' is not a directory");
     }
 
-    let mut results: Vec<ProcessedFile> = Vec::new();
+    let scan_started = Instant::now();
+    let entries = collect_candidates(dir_path, config)?;
+    let scan_time = scan_started.elapsed();
+
+    read_candidates(
+        config, api, logger, extractors, dir_path, entries, scan_time,
+    )
+    .await
+}
 
-    let mut entries: Vec<_> = fs::read_dir(dir_path)
-        .context("Failed to read directory")?
-        .filter_map(|e| e.ok())
-        .collect();
-    entries.sort_by_key(|e| e.file_name());
+/// Reads the single file at `path`, as when it is passed on the command line
+/// instead of configuring `files_directory`.
+///
+/// A directory is read like `files_directory` when `recursive` is set and
+/// skipped with a warning otherwise.
+pub async fn read_single_file(
+    path: &Path,
+    config: &Config,
+    api: &dyn ChatApi,
+    logger: &Logger,
+) -> Result<ReadOutcome> {
+    if path.is_dir() {
+        if !config.recursive {
+            logger.warn(&format!(
+                "Skipping '{}': it is a directory (set recursive: true to read it)",
+                path.display()
+            ));
+            return Ok(ReadOutcome::default());
+        }
+        let scan_started = Instant::now();
+        let entries = collect_candidates(path, config)?;
+        return read_candidates(
+            config,
+            api,
+            logger,
+            &ExtractorRegistry::default(),
+            path,
+            entries,
+            scan_started.elapsed(),
+        )
+        .await;
+    }
+    if !path.is_file() {
+        anyhow::bail!("'{}' is not a file", path.display());
+    }
+    let root = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    read_candidates(
+        config,
+        api,
+        logger,
+        &ExtractorRegistry::default(),
+        root,
+        vec![path.to_path_buf()],
+        Duration::ZERO,
+    )
+    .await
+}
 
-    for entry in entries {
-        let file_path = entry.path();
+/// Name of the single file read by [`read_stdin`].
+pub const STDIN_NAME: &str = "stdin";
 
-        if !file_path.is_file() {
+/// Reads text from `reader` (standard input) as a single file named
+/// [`STDIN_NAME`], applying `max_lines_per_file`, `repair_invalid_utf8` and
+/// `strip_bom` as for text files.
+pub fn read_stdin(mut reader: impl Read, config: &Config, logger: &Logger) -> Result<ReadOutcome> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .context("Failed to read standard input")?;
+    let (content, repairs) = decode_text(bytes, config.repair_invalid_utf8)
+        .context("Standard input is not valid UTF-8")?;
+    let mut content = if config.strip_bom {
+        strip_bom(content)
+    } else {
+        content
+    };
+    logger.info(&format!("  Read (text): {STDIN_NAME}"));
+    if repairs > 0 {
+        logger.warn(&format!(
+            "  Repaired {repairs} invalid UTF-8 sequence(s) in '{STDIN_NAME}'"
+        ));
+    }
+    if let Some(omitted) = truncate_lines(&mut content, config.max_lines_per_file) {
+        logger.info(&format!(
+            "  Truncated '{STDIN_NAME}': omitted {omitted} line(s) from the middle"
+        ));
+    }
+    Ok(ReadOutcome {
+        files: vec![ProcessedFile {
+            name: STDIN_NAME.to_string(),
+            path: PathBuf::from(STDIN_NAME),
+            content,
+            ocr_time: None,
+            confidence: None,
+            fields: Vec::new(),
+            metadata: None,
+        }],
+        candidates: 1,
+        ..ReadOutcome::default()
+    })
+}
+
+/// What a dry run (`--dry-run`) would do with a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlannedAction {
+    /// Read locally as text.
+    Text,
+    /// Read locally as a Word or Excel document.
+    Office,
+    /// Sent to `ocr_model` as a PDF.
+    OcrPdf,
+    /// Sent to `ocr_model` as an image.
+    OcrImage,
+    /// An image or PDF whose OCR result is already in the cache.
+    Cached,
+    /// Not a supported file.
+    Skip,
+    /// Larger than `max_file_size_bytes`, so not read.
+    TooLarge,
+}
+
+impl std::fmt::Display for PlannedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Text => "text",
+            Self::Office => "office",
+            Self::OcrPdf => "OCR (PDF)",
+            Self::OcrImage => "OCR (image)",
+            Self::Cached => "cached OCR",
+            Self::Skip => "skip (unsupported)",
+            Self::TooLarge => "skipped (too large)",
+        })
+    }
+}
+
+/// One file of a dry run with its planned action.
+#[derive(Debug)]
+pub struct PlannedFile {
+    /// Path relative to the scanned directory.
+    pub name: String,
+    pub action: PlannedAction,
+    pub size: u64,
+    /// Words of locally read files; `None` for files that need OCR or are
+    /// skipped.
+    pub words: Option<usize>,
+}
+
+/// Lists what reading `source` (a directory, or a single file) would do with
+/// each file, without calling the API: the files are classified, and those
+/// read locally are counted, as for [`read_all_files`].
+pub fn plan_files(source: &Path, config: &Config) -> Result<Vec<PlannedFile>> {
+    let (root, entries) = if source.is_dir() {
+        (source, collect_candidates(source, config)?)
+    } else if source.is_file() {
+        let root = match source.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        (root, vec![source.to_path_buf()])
+    } else {
+        anyhow::bail!("'{}' does not exist", source.display());
+    };
+    let own_outputs = own_output_paths(config);
+    let cache = OcrCache::new(&config.cache_dir);
+
+    let mut plan = Vec::new();
+    for path in entries {
+        if !path.is_file() || resolve_path(&path).is_some_and(|p| own_outputs.contains(&p)) {
             continue;
         }
+        let size = fs::metadata(&path).map_or(0, |m| m.len());
+        let name = relative_name(root, &path);
+        // The same check as `read_file`, before anything is read.
+        if config.max_file_size_bytes > 0 && size > config.max_file_size_bytes {
+            plan.push(PlannedFile {
+                name,
+                action: PlannedAction::TooLarge,
+                size,
+                words: None,
+            });
+            continue;
+        }
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        let (action, words) = match detect_file_type(&path, &ext, config) {
+            Some(FileType::Text) => (PlannedAction::Text, Some(count_words(&path)?)),
+            Some(FileType::Office) => {
+                let words = office::extract(&path, &ext)
+                    .map(|office| office.text.split_whitespace().count())
+                    .ok();
+                (PlannedAction::Office, words)
+            }
+            Some(file_type @ (FileType::Pdf | FileType::Image)) => {
+                let cached = cache.as_ref().is_some_and(|cache| {
+                    fs::read(&path).is_ok_and(|bytes| {
+                        let instruction = config.instructions.ocr_for(&name);
+                        let variant = ocr_prompt_variant(config, instruction);
+                        let key = OcrCache::key(&bytes, &config.ocr_model, &variant);
+                        cache.get(&key).is_some()
+                    })
+                });
+                let action = match file_type {
+                    _ if cached => PlannedAction::Cached,
+                    FileType::Pdf => PlannedAction::OcrPdf,
+                    _ => PlannedAction::OcrImage,
+                };
+                (action, None)
+            }
+            None if config.treat_unknown_as_text => match sniff_text(&path, config.strip_bom)? {
+                Some(text) => (PlannedAction::Text, Some(text.split_whitespace().count())),
+                None => (PlannedAction::Skip, None),
+            },
+            None => (PlannedAction::Skip, None),
+        };
+        plan.push(PlannedFile {
+            name,
+            action,
+            size,
+            words,
+        });
+    }
+    Ok(plan)
+}
+
+/// Extracts every file in `entries` (sorted by path), naming them relative to
+/// `dir_path`. Up to `max_concurrency` files are read at once.
+async fn read_candidates(
+    config: &Config,
+    api: &dyn ChatApi,
+    logger: &Logger,
+    extractors: &ExtractorRegistry,
+    dir_path: &Path,
+    entries: Vec<PathBuf>,
+    scan_time: Duration,
+) -> Result<ReadOutcome> {
+    let mut results: Vec<ProcessedFile> = Vec::new();
+    let mut failures: Vec<FileFailure> = Vec::new();
+    let mut unsupported = 0;
+
+    let own_outputs = own_output_paths(config);
+
+    let total = entries.len();
+    let candidates = entries.iter().filter(|path| path.is_file()).count();
+    // Checked as each file is about to start, so that files already in flight
+    // finish but no new one begins once the deadline or cost cap is hit.
+    let may_start = |index: usize| {
+        if api.deadline_exceeded() {
+            logger.warn(&format!(
+                "Run deadline reached; skipping the remaining {} entries",
+                total - index
+            ));
+            return false;
+        }
+        if api.cost_cap_reached() {
+            logger.warn(&format!(
+                "Cost cap reached (estimated ${:.4}); skipping the remaining {} entries",
+                api.estimated_cost(),
+                total - index
+            ));
+            return false;
+        }
+        true
+    };
+    let selected = entries.into_iter().enumerate().filter_map(|(index, file_path)| {
+        if !file_path.is_file() {
+            return None;
+        }
+
+        if resolve_path(&file_path).is_some_and(|p| own_outputs.contains(&p)) {
+            logger.warn(&format!(
+                "  Skipping '{}': it is this tool's own output (output_path points inside files_directory)",
+                relative_name(dir_path, &file_path)
+            ));
+            return None;
+        }
+
+        // Files without an extension may still be recognized by content.
+        let ext = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        Some((index, file_path, ext))
+    });
+
+    let mut outcomes = stream::iter(selected)
+        .take_while(|(index, _, _)| future::ready(may_start(*index)))
+        .map(|(index, file_path, ext)| async move {
+            let filename = relative_name(dir_path, &file_path);
+            let started = Instant::now();
+            logger.progress(ProgressEvent::FileStarted {
+                file: filename.clone(),
+                index: index + 1,
+                total,
+            });
+            let outcome =
+                read_file(dir_path, &file_path, &ext, config, api, logger, extractors).await;
+            (filename, started.elapsed(), outcome)
+        })
+        .buffer_unordered(config.max_concurrency.max(1));
 
-        let ext = match file_path.extension().and_then(|e| e.to_str()) {
-            Some(e) => e.to_lowercase(),
-            None => continue,
+    while let Some((filename, elapsed, outcome)) = outcomes.next().await {
+        let finished = |ok| ProgressEvent::FileFinished {
+            file: filename.clone(),
+            ok,
+            secs: elapsed.as_secs_f64(),
         };
+        match outcome {
+            FileOutcome::Processed(file) => {
+                logger.progress(finished(true));
+                results.push(file);
+            }
+            FileOutcome::Failed(failure) => {
+                logger.progress(finished(false));
+                failures.push(failure);
+            }
+            FileOutcome::Unsupported => {
+                unsupported += 1;
+                logger.progress(ProgressEvent::FileSkipped { file: filename });
+            }
+            FileOutcome::Skipped => logger.progress(ProgressEvent::FileSkipped { file: filename }),
+        }
+    }
+
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+    failures.sort_by(|a, b| a.name.cmp(&b.name));
+    disambiguate_names(&mut results, config.duplicate_names, logger);
+    let outcome = ReadOutcome {
+        files: results,
+        failures,
+        scan_time,
+        candidates,
+        unsupported,
+    };
+    logger.info(&format!("Files: {}", outcome.counts()));
+    Ok(outcome)
+}
 
-        let filename = filename_of(&file_path);
+// ---------------------------------------------------------------------------
+// Private helpers
+// ---------------------------------------------------------------------------
+
+/// What became of one candidate file.
+enum FileOutcome {
+    Processed(ProcessedFile),
+    Failed(FileFailure),
+    /// Not a supported file type.
+    Unsupported,
+    /// Left out for another reason; nothing to report beyond the log line.
+    Skipped,
+}
+
+/// Extracts one file of the scanned directory `root`, logging what happened.
+async fn read_file(
+    root: &Path,
+    file_path: &Path,
+    ext: &str,
+    config: &Config,
+    api: &dyn ChatApi,
+    logger: &Logger,
+    extractors: &ExtractorRegistry,
+) -> FileOutcome {
+    let filename = relative_name(root, file_path);
+    let started = Instant::now();
+    let mut ocr_time = None;
+    let mut metadata = None;
+
+    let limit = config.max_file_size_bytes;
+    if limit > 0 {
+        if let Ok(size) = fs::metadata(file_path).map(|m| m.len()) {
+            if size > limit {
+                logger.warn(&format!(
+                    "  Skipping '{filename}': exceeds max file size ({size} > {limit} bytes)"
+                ));
+                return FileOutcome::Skipped;
+            }
+        }
+    }
+
+    if let Some(extractor) = extractors.get(ext) {
+        return match extractor(file_path) {
+            Ok(content) => {
+                logger.info(&format!("  Read (custom extractor): {filename}"));
+                FileOutcome::Processed(ProcessedFile {
+                    path: PathBuf::from(&filename),
+                    name: filename,
+                    content,
+                    ocr_time: None,
+                    confidence: None,
+                    fields: Vec::new(),
+                    metadata: None,
+                })
+            }
+            Err(e) => {
+                logger.error(&format!("  Skipping '{filename}': {e}"));
+                FileOutcome::Failed(FileFailure {
+                    name: filename,
+                    reason: e.to_string(),
+                })
+            }
+        };
+    }
 
-        let content = match classify_file(&ext) {
-            Some(FileType::Text) => match fs::read_to_string(&file_path) {
-                Ok(c) => {
-                    logger.info(&format!("  Read (text): {filename}"));
-                    c
+    let ocr_instruction = config.instructions.ocr_for(&filename);
+    let mut content = match detect_file_type(file_path, ext, config) {
+        Some(FileType::Text) => match load_text(file_path, ext, config) {
+            Ok(text) => {
+                logger.info(&format!("  Read (text): {filename}"));
+                if text.repairs > 0 {
+                    logger.warn(&format!(
+                        "  Repaired {} invalid UTF-8 sequence(s) in '{filename}'",
+                        text.repairs
+                    ));
+                }
+                if let Some(omitted) = text.omitted {
+                    logger.info(&format!(
+                        "  Truncated '{filename}': omitted {omitted} line(s) from the middle"
+                    ));
+                }
+                text.content
+            }
+            Err(e) => {
+                logger.error(&format!("  Skipping '{filename}': {e}"));
+                return FileOutcome::Failed(FileFailure {
+                    name: filename,
+                    reason: e.to_string(),
+                });
+            }
+        },
+        Some(FileType::Office) => match process_office(file_path, ext, config, logger) {
+            Ok(text) => {
+                logger.info(&format!("  Read (office): {filename}"));
+                text
+            }
+            Err(e) => {
+                logger.error(&format!("  Skipping '{filename}': {e}"));
+                return FileOutcome::Failed(FileFailure {
+                    name: filename,
+                    reason: e.to_string(),
+                });
+            }
+        },
+        Some(FileType::Pdf) => {
+            logger.info(&format!("  Processing (PDF via API): {filename}..."));
+            match process_pdf(file_path, api, config, ocr_instruction, logger).await {
+                Ok(extraction) => {
+                    logger.info(&format!("  Done: {filename}"));
+                    ocr_time = Some(started.elapsed());
+                    metadata = Some(extraction.metadata);
+                    extraction.content
                 }
                 Err(e) => {
-                    logger.error(&format!("  Skipping '{filename}': {e}"));
-                    continue;
+                    logger.error(&format!("  Skipping PDF '{filename}': {e}"));
+                    return FileOutcome::Failed(FileFailure {
+                        name: filename,
+                        reason: e.to_string(),
+                    });
                 }
-            },
-            Some(FileType::Pdf) => {
-                logger.info(&format!("  Processing (PDF via API): {filename}..."));
-                match process_pdf(&file_path, api, ocr_model).await {
-                    Ok(c) => {
-                        logger.info(&format!("  Done: {filename}"));
-                        c
-                    }
-                    Err(e) => {
-                        logger.error(&format!("  Skipping PDF '{filename}': {e}"));
-                        continue;
-                    }
+            }
+        }
+        Some(FileType::Image) => {
+            logger.info(&format!("  Processing (image via API): {filename}..."));
+            match process_image(file_path, api, config, ocr_instruction, logger).await {
+                Ok(extraction) => {
+                    logger.info(&format!("  Done: {filename}"));
+                    ocr_time = Some(started.elapsed());
+                    metadata = Some(extraction.metadata);
+                    extraction.content
+                }
+                Err(e) => {
+                    logger.error(&format!("  Skipping image '{filename}': {e}"));
+                    return FileOutcome::Failed(FileFailure {
+                        name: filename,
+                        reason: e.to_string(),
+                    });
                 }
             }
-            Some(FileType::Image) => {
-                logger.info(&format!("  Processing (image via API): {filename}..."));
-                match process_image(&file_path, api, ocr_model).await {
-                    Ok(c) => {
-                        logger.info(&format!("  Done: {filename}"));
-                        c
-                    }
-                    Err(e) => {
-                        logger.error(&format!("  Skipping image '{filename}': {e}"));
-                        continue;
-                    }
+        }
+        None if config.treat_unknown_as_text => match sniff_text(file_path, config.strip_bom) {
+            Ok(Some(mut c)) => {
+                logger.info(&format!("  Read (unknown type as text): {filename}"));
+                if let Some(omitted) = truncate_lines(&mut c, config.max_lines_per_file) {
+                    logger.info(&format!(
+                        "  Truncated '{filename}': omitted {omitted} line(s) from the middle"
+                    ));
                 }
+                c
             }
-            None => {
-                logger.warn(&format!("  Skipping unsupported file: {filename}"));
-                continue;
+            Ok(None) => {
+                logger.warn(&format!("  Skipping unsupported binary file: {filename}"));
+                return FileOutcome::Unsupported;
+            }
+            Err(e) => {
+                logger.error(&format!("  Skipping '{filename}': {e}"));
+                return FileOutcome::Failed(FileFailure {
+                    name: filename,
+                    reason: e.to_string(),
+                });
             }
+        },
+        None => {
+            logger.warn(&format!("  Skipping unsupported file: {filename}"));
+            return FileOutcome::Unsupported;
+        }
+    };
+
+    if config.inline_referenced_images && MARKDOWN_EXTENSIONS.contains(&ext) {
+        content = inline_referenced_images(&content, file_path, root, api, config, logger).await;
+    }
+
+    let mut confidence = None;
+    if ocr_time.is_some() {
+        let score = extraction_confidence(api, config, &content, &filename, logger).await;
+        let threshold = config.min_confidence.unwrap_or(LOW_CONFIDENCE);
+        if score < threshold {
+            logger.warn(&format!(
+                "  Low OCR confidence for '{filename}': {score}/100"
+            ));
+            if config.min_confidence.is_some() {
+                return FileOutcome::Failed(FileFailure {
+                    name: filename,
+                    reason: format!("OCR confidence {score} is below min_confidence {threshold}"),
+                });
+            }
+        }
+        confidence = Some(score);
+    }
+
+    let mut fields = Vec::new();
+    if !config.extraction_tools.is_empty() {
+        match extraction_tools::extract_fields(
+            api,
+            &config.ocr_model,
+            &config.extraction_tools,
+            &content,
+        )
+        .await
+        {
+            Ok(calls) => {
+                logger.info(&format!(
+                    "  Extracted {} structured record(s) from '{filename}'",
+                    calls.len()
+                ));
+                fields = calls;
+            }
+            Err(e) => logger.warn(&format!(
+                "  Could not extract structured fields from '{filename}': {e}"
+            )),
+        }
+    }
+
+    FileOutcome::Processed(ProcessedFile {
+        path: PathBuf::from(&filename),
+        name: filename,
+        content,
+        ocr_time,
+        confidence,
+        fields,
+        metadata,
+    })
+}
+
+/// Extensions of Markdown files, whose image references can be inlined.
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// Finds Markdown image references (`![alt](target "title")`) in `text` and
+/// returns the byte offset just past each one together with its target.
+fn image_references(text: &str) -> Vec<(usize, String)> {
+    let mut refs = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = text[pos..].find("![") {
+        let start = pos + found;
+        let Some(close) = text[start..].find("](") else {
+            break;
         };
+        if text[start..start + close].contains('\n') {
+            pos = start + 2;
+            continue;
+        }
+        let open = start + close + 2;
+        let Some(len) = text[open..].find(')') else {
+            break;
+        };
+        let end = open + len + 1;
+        let inner = text[open..end - 1].trim();
+        let target = match inner.strip_prefix('<') {
+            Some(rest) => rest.split('>').next().unwrap_or_default(),
+            None => inner.split_whitespace().next().unwrap_or_default(),
+        };
+        if !target.is_empty() {
+            refs.push((end, target.to_string()));
+        }
+        pos = end;
+    }
+    refs
+}
+
+/// OCRs the local images referenced from the Markdown file at `path` and
+/// inserts each one's text after its reference. Remote and missing images are
+/// left as they are, and so are images outside the scanned directory `root`
+/// (absolute paths, `../` or symlinks out of it), which are never read or
+/// uploaded; missing and outside images and OCR failures are logged as
+/// warnings.
+async fn inline_referenced_images(
+    text: &str,
+    path: &Path,
+    root: &Path,
+    api: &dyn ChatApi,
+    config: &Config,
+    logger: &Logger,
+) -> String {
+    let filename = filename_of(path);
+    let base = path.parent().unwrap_or(Path::new(""));
+    let root = fs::canonicalize(root).ok();
+    let mut ocr_cache: HashMap<String, Option<String>> = HashMap::new();
+    let mut result = String::with_capacity(text.len());
+    let mut copied = 0;
+
+    for (end, target) in image_references(text) {
+        if target.contains("://") || target.starts_with("data:") {
+            continue;
+        }
+        if !ocr_cache.contains_key(&target) {
+            let image_path = base.join(&target);
+            let ext = image_path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let resolved = fs::canonicalize(&image_path).ok();
+            let ocr = if !image_path.is_file() {
+                logger.warn(&format!(
+                    "  Image '{target}' referenced from '{filename}' not found, skipped"
+                ));
+                None
+            } else if !resolved
+                .zip(root.as_deref())
+                .is_some_and(|(image, root)| image.starts_with(root))
+            {
+                logger.warn(&format!(
+                    "  Image '{target}' referenced from '{filename}' is outside the files \
+                     directory, skipped"
+                ));
+                None
+            } else if !matches!(classify_file(&ext, config), Some(FileType::Image)) {
+                None
+            } else {
+                logger.info(&format!(
+                    "  OCR of image '{target}' referenced from '{filename}'..."
+                ));
+                match process_image(&image_path, api, config, None, logger).await {
+                    Ok(extraction) => Some(extraction.content),
+                    Err(e) => {
+                        logger.warn(&format!(
+                            "  Could not OCR image '{target}' referenced from '{filename}': {e}"
+                        ));
+                        None
+                    }
+                }
+            };
+            ocr_cache.insert(target.clone(), ocr);
+        }
+        if let Some(Some(ocr)) = ocr_cache.get(&target) {
+            result.push_str(&text[copied..end]);
+            let _ = write!(
+                result,
+                "\n\n--- Изображение: {target} ---\n{}\n--- Конец изображения ---\n",
+                ocr.trim()
+            );
+            copied = end;
+        }
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+/// Extracts the text of a Word or Excel document locally, truncated to
+/// `max_lines_per_file`. Embedded objects and media are left out with a
+/// warning.
+fn process_office(path: &Path, ext: &str, config: &Config, logger: &Logger) -> Result<String> {
+    let mut office = office::extract(path, ext)?;
+    let filename = filename_of(path);
+    if !office.skipped.is_empty() {
+        logger.warn(&format!(
+            "  Ignored {} embedded object(s) in '{filename}'",
+            office.skipped.len()
+        ));
+    }
+    if let Some(omitted) = truncate_lines(&mut office.text, config.max_lines_per_file) {
+        logger.info(&format!(
+            "  Truncated '{filename}': omitted {omitted} line(s) from the middle"
+        ));
+    }
+    Ok(office.text)
+}
 
-        results.push(ProcessedFile {
-            name: filename,
+/// Returns the cached OCR result of `bytes` when `cache_dir` has one;
+/// otherwise awaits `extract` and caches the text it returns.
+async fn cached_extraction(
+    config: &Config,
+    bytes: &[u8],
+    filename: &str,
+    instruction: Option<&str>,
+    logger: &Logger,
+    extract: impl Future<Output = Result<Extraction>>,
+) -> Result<Extraction> {
+    let Some(cache) = OcrCache::new(&config.cache_dir) else {
+        return extract.await;
+    };
+    let variant = ocr_prompt_variant(config, instruction);
+    let key = OcrCache::key(bytes, &config.ocr_model, &variant);
+    if let Some(content) = cache.get(&key) {
+        logger.info(&format!("  Cached: {filename}"));
+        return Ok(Extraction {
             content,
+            metadata: ExtractionMetadata {
+                cached: true,
+                ..ExtractionMetadata::new(&config.ocr_model)
+            },
         });
     }
-
-    Ok(results)
+    let extraction = extract.await?;
+    if let Err(e) = cache.put(&key, &extraction.content) {
+        logger.warn(&format!(
+            "  Could not cache the OCR result of '{filename}': {e}"
+        ));
+    }
+    Ok(extraction)
 }
 
-// ---------------------------------------------------------------------------
-// Private helpers
-// ---------------------------------------------------------------------------
+async fn process_image(
+    path: &Path,
+    api: &dyn ChatApi,
+    config: &Config,
+    instruction: Option<&str>,
+    logger: &Logger,
+) -> Result<Extraction> {
+    let (bytes, ext) = read_bytes(path)?;
+    let filename = filename_of(path);
+    let extract = ocr_image(&bytes, &ext, &filename, api, config, instruction, logger);
+    cached_extraction(config, &bytes, &filename, instruction, logger, extract).await
+}
 
-async fn process_image(path: &Path, api: &OpenRouterClient, model: &str) -> Result<String> {
-    let (b64, ext) = read_and_encode(path)?;
-    let mime = mime_type_for_image(&ext);
+async fn ocr_image(
+    bytes: &[u8],
+    ext: &str,
+    filename: &str,
+    api: &dyn ChatApi,
+    config: &Config,
+    instruction: Option<&str>,
+    logger: &Logger,
+) -> Result<Extraction> {
+    let additions = ocr_prompt_additions(config, instruction);
+    let mut metadata = ExtractionMetadata::new(&config.ocr_model);
+    if sniff_image_mime(bytes) == Some("image/gif") {
+        match Gif::parse(bytes) {
+            Ok(gif) if gif.frame_count() > 1 => {
+                let content = ocr_gif_frames(
+                    &gif,
+                    api,
+                    config,
+                    &additions,
+                    filename,
+                    logger,
+                    &mut metadata,
+                )
+                .await?;
+                return Ok(Extraction { content, metadata });
+            }
+            Ok(_) => {}
+            Err(e) => logger.warn(&format!(
+                "  Could not read the frames of '{filename}' ({e}); sending it as is"
+            )),
+        }
+    }
+    let downscaled = shrink_image(bytes, filename, config, logger);
+    let (payload, mime) = match &downscaled {
+        Some(image) => (image.bytes.as_slice(), image.mime),
+        None => (bytes, image_mime(bytes, ext)),
+    };
+    let b64 = BASE64.encode(payload);
     let data_url = format!("data:{mime};base64,{b64}");
-    api.chat(model, build_image_messages(&data_url)).await
+    let result = request_extraction(
+        api,
+        config,
+        build_image_messages(filename, &data_url, &additions, config),
+        filename,
+        logger,
+        &mut metadata,
+    )
+    .await;
+
+    // The extension may lie about the format; retry once with the sniffed type.
+    match (result, sniff_image_mime(payload)) {
+        (Err(e), Some(sniffed)) if sniffed != mime && is_invalid_image_error(&e) => {
+            logger.warn(&format!(
+                "  Image '{filename}' was rejected as {mime}; retrying as {sniffed}"
+            ));
+            let data_url = format!("data:{sniffed};base64,{b64}");
+            request_extraction(
+                api,
+                config,
+                build_image_messages(filename, &data_url, &additions, config),
+                filename,
+                logger,
+                &mut metadata,
+            )
+            .await
+        }
+        (result, _) => result,
+    }
+    .map(|content| Extraction { content, metadata })
+}
+
+/// OCRs an animated GIF frame by frame as static PNGs: the first frame, or
+/// `gif_frames` frames spread evenly over the animation.
+async fn ocr_gif_frames(
+    gif: &Gif,
+    api: &dyn ChatApi,
+    config: &Config,
+    additions: &str,
+    filename: &str,
+    logger: &Logger,
+    metadata: &mut ExtractionMetadata,
+) -> Result<String> {
+    let total = gif.frame_count();
+    let wanted = config.gif_frames.clamp(1, total);
+    let indices: Vec<usize> = (0..wanted).map(|i| i * total / wanted).collect();
+    logger.info(&format!(
+        "  '{filename}' is an animated GIF with {total} frames; extracting {wanted} as PNG"
+    ));
+    let pngs = gif.frames_to_png(&indices)?;
+
+    let mut texts = Vec::new();
+    for (index, png) in indices.iter().zip(pngs) {
+        let data_url = format!("data:image/png;base64,{}", BASE64.encode(png));
+        let messages = build_image_messages(filename, &data_url, additions, config);
+        match request_extraction(api, config, messages, filename, logger, metadata).await {
+            Ok(text) if wanted == 1 => return Ok(text),
+            Ok(text) => texts.push(format!("--- Кадр {} из {total} ---\n{text}", index + 1)),
+            Err(e) if wanted == 1 => return Err(e),
+            Err(e) => logger.warn(&format!(
+                "  Skipping frame {} of '{filename}': {e}",
+                index + 1
+            )),
+        }
+    }
+    if texts.is_empty() {
+        anyhow::bail!("No frame of the animated GIF could be read");
+    }
+    Ok(texts.join("\n\n"))
+}
+
+async fn process_pdf(
+    path: &Path,
+    api: &dyn ChatApi,
+    config: &Config,
+    instruction: Option<&str>,
+    logger: &Logger,
+) -> Result<Extraction> {
+    let (bytes, _) = read_bytes(path)?;
+    let filename = filename_of(path);
+    let extract = ocr_pdf(path, &bytes, api, config, instruction, logger);
+    cached_extraction(config, &bytes, &filename, instruction, logger, extract).await
+}
+
+async fn ocr_pdf(
+    path: &Path,
+    bytes: &[u8],
+    api: &dyn ChatApi,
+    config: &Config,
+    instruction: Option<&str>,
+    logger: &Logger,
+) -> Result<Extraction> {
+    let filename = filename_of(path);
+    let additions = ocr_prompt_additions(config, instruction);
+    let mut metadata = ExtractionMetadata::new(&config.ocr_model);
+    let pages = if config.split_pdf_pages {
+        split_pdf_or_warn(bytes, &filename, logger)
+    } else {
+        Vec::new()
+    };
+    let mut content = if pages.len() > 1 {
+        ocr_pdf_pages(
+            &pages,
+            api,
+            config,
+            &additions,
+            &filename,
+            logger,
+            &mut metadata,
+        )
+        .await?
+    } else {
+        let data_url = format!("data:application/pdf;base64,{}", BASE64.encode(bytes));
+        request_extraction(
+            api,
+            config,
+            build_pdf_messages(&filename, &data_url, &additions, config),
+            &filename,
+            logger,
+            &mut metadata,
+        )
+        .await?
+    };
+
+    if config.pdf_image_fallback {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if is_underextracted(&content, size) {
+            logger.warn(&format!(
+                "  Only {} character(s) extracted from '{filename}' ({} KiB); \
+                 falling back to OCR of its embedded images",
+                content.chars().filter(|c| !c.is_whitespace()).count(),
+                size / 1024
+            ));
+            let pages =
+                ocr_pdf_figures(path, api, config, instruction, logger, &mut metadata).await;
+            if pages.is_empty() {
+                logger.warn(&format!(
+                    "  No embedded images to OCR in '{filename}' (pages are not \
+                     rasterized); keeping the native extraction"
+                ));
+            } else {
+                content.clear();
+                for (_, page, text) in pages {
+                    let _ = write!(content, "--- Страница {page} ---\n{text}\n\n");
+                }
+                return Ok(Extraction {
+                    content: content.trim_end().to_string(),
+                    metadata,
+                });
+            }
+        }
+    }
+
+    if config.pdf_extract_images {
+        let figures = ocr_pdf_figures(path, api, config, instruction, logger, &mut metadata).await;
+        for (number, page, text) in figures {
+            let _ = write!(
+                content,
+                "\n\n--- Рисунок {number} (стр. {page}) ---\n{text}"
+            );
+        }
+    }
+    Ok(Extraction { content, metadata })
+}
+
+/// Non-whitespace characters per KiB of PDF below which a native extraction
+/// is considered to have missed the content (typical of scanned PDFs).
+const PDF_MIN_CHARS_PER_KIB: f64 = 2.0;
+
+/// Returns `true` if `content` is suspiciously short for a PDF of `size` bytes.
+fn is_underextracted(content: &str, size: u64) -> bool {
+    let chars = content.chars().filter(|c| !c.is_whitespace()).count();
+    (chars as f64) < size as f64 / 1024.0 * PDF_MIN_CHARS_PER_KIB
+}
+
+/// Returns the pages of the PDF for `split_pdf_pages`, or nothing (so the
+/// PDF is sent whole) when it cannot be split.
+fn split_pdf_or_warn(bytes: &[u8], filename: &str, logger: &Logger) -> Vec<Vec<u8>> {
+    match split_pdf_pages(bytes) {
+        Ok(pages) => pages,
+        Err(e) => {
+            logger.warn(&format!(
+                "  Could not split '{filename}' into pages ({e:#}); sending it whole"
+            ));
+            Vec::new()
+        }
+    }
 }
 
-async fn process_pdf(path: &Path, api: &OpenRouterClient, model: &str) -> Result<String> {
-    let (b64, _) = read_and_encode(path)?;
-    let data_url = format!("data:application/pdf;base64,{b64}");
+/// OCRs each page of a split PDF in its own request and joins the texts in
+/// page order. Pages are read one after another within the file's
+/// `max_concurrency` slot, so splitting never multiplies the requests in
+/// flight. Pages that fail are logged and left out; fails only if no page
+/// could be read.
+async fn ocr_pdf_pages(
+    pages: &[Vec<u8>],
+    api: &dyn ChatApi,
+    config: &Config,
+    additions: &str,
+    filename: &str,
+    logger: &Logger,
+    metadata: &mut ExtractionMetadata,
+) -> Result<String> {
+    let total = pages.len();
+    logger.info(&format!(
+        "  OCR of '{filename}' page by page ({total} pages)..."
+    ));
+    let mut texts = Vec::new();
+    for (index, page) in pages.iter().enumerate() {
+        let data_url = format!("data:application/pdf;base64,{}", BASE64.encode(page));
+        let label = format!("{filename} (page {})", index + 1);
+        let mut page_metadata = ExtractionMetadata::default();
+        let result = request_extraction(
+            api,
+            config,
+            build_pdf_messages(filename, &data_url, additions, config),
+            &label,
+            logger,
+            &mut page_metadata,
+        )
+        .await;
+        metadata.usage.add(page_metadata.usage);
+        match result {
+            Ok(text) => texts.push(format!("--- Страница {} ---\n{text}", index + 1)),
+            Err(e) => logger.warn(&format!(
+                "  Skipping page {} of '{filename}': {e}",
+                index + 1
+            )),
+        }
+    }
+    if texts.is_empty() {
+        anyhow::bail!("No page of the PDF could be read");
+    }
+    if texts.len() < total {
+        logger.warn(&format!(
+            "  Read {} of {total} pages of '{filename}'",
+            texts.len()
+        ));
+    }
+    Ok(texts.join("\n\n"))
+}
+
+/// OCRs the PDF's embedded images individually, returning `(number, page,
+/// text)` for each one read successfully. Failures are logged and never fail the PDF.
+async fn ocr_pdf_figures(
+    path: &Path,
+    api: &dyn ChatApi,
+    config: &Config,
+    instruction: Option<&str>,
+    logger: &Logger,
+    metadata: &mut ExtractionMetadata,
+) -> Vec<(usize, u32, String)> {
+    let additions = ocr_prompt_additions(config, instruction);
     let filename = filename_of(path);
-    api.chat(model, build_pdf_messages(&filename, &data_url))
+    let (figures, skipped) = match extract_pdf_figures(path) {
+        Ok(found) => found,
+        Err(e) => {
+            logger.warn(&format!(
+                "  Could not extract images from '{filename}': {e}"
+            ));
+            return Vec::new();
+        }
+    };
+    if skipped > 0 {
+        logger.warn(&format!(
+            "  Skipping {skipped} embedded image(s) in '{filename}': only JPEG and \
+             uncompressed or Flate-compressed images can be OCR'd"
+        ));
+    }
+    if figures.is_empty() {
+        return Vec::new();
+    }
+
+    logger.info(&format!(
+        "  OCR of {} embedded image(s) in '{filename}'...",
+        figures.len()
+    ));
+    let mut texts = Vec::new();
+    for (index, figure) in figures.iter().enumerate() {
+        let number = index + 1;
+        let data_url = format!(
+            "data:{};base64,{}",
+            figure.mime,
+            BASE64.encode(&figure.data)
+        );
+        let label = format!("{filename} (figure {number})");
+        match request_extraction(
+            api,
+            config,
+            build_image_messages(&filename, &data_url, &additions, config),
+            &label,
+            logger,
+            metadata,
+        )
         .await
+        {
+            Ok(text) => texts.push((number, figure.page, text)),
+            Err(e) => logger.warn(&format!("  Skipping figure {number} of '{filename}': {e}")),
+        }
+    }
+    texts
 }
@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+
+/// An image frame of a GIF, still LZW-compressed.
+struct Frame {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    interlaced: bool,
+    local_palette: Option<Vec<u8>>,
+    min_code_size: u8,
+    data: Vec<u8>,
+    /// Disposal method from the preceding graphic control extension.
+    disposal: u8,
+    transparent: Option<u8>,
+}
+
+/// A parsed GIF: enough of the format to render its frames to PNG, which
+/// vision models read reliably, unlike animated GIFs.
+pub struct Gif {
+    width: usize,
+    height: usize,
+    global_palette: Option<Vec<u8>>,
+    frames: Vec<Frame>,
+}
+
+impl Gif {
+    /// Parses `bytes` as a GIF. Fails on anything that is not a well-formed
+    /// GIF87a/GIF89a stream.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader { bytes, pos: 0 };
+        let signature = r.take(6)?;
+        if signature != b"GIF87a" && signature != b"GIF89a" {
+            anyhow::bail!("Not a GIF image");
+        }
+        let width = r.u16()? as usize;
+        let height = r.u16()? as usize;
+        let packed = r.u8()?;
+        r.take(2)?; // background colour index, pixel aspect ratio
+        let global_palette = r.palette(packed)?;
+
+        let mut frames = Vec::new();
+        let (mut disposal, mut transparent) = (0, None);
+        loop {
+            match r.u8()? {
+                0x21 => {
+                    let label = r.u8()?;
+                    let data = r.sub_blocks()?;
+                    if label == 0xF9 && data.len() >= 4 {
+                        disposal = (data[0] >> 2) & 0x07;
+                        transparent = (data[0] & 0x01 == 1).then_some(data[3]);
+                    }
+                }
+                0x2C => {
+                    let left = r.u16()? as usize;
+                    let top = r.u16()? as usize;
+                    let frame_width = r.u16()? as usize;
+                    let frame_height = r.u16()? as usize;
+                    let packed = r.u8()?;
+                    let local_palette = r.palette(packed)?;
+                    let min_code_size = r.u8()?;
+                    if !(1..=11).contains(&min_code_size) {
+                        anyhow::bail!("Invalid LZW code size {min_code_size} in GIF");
+                    }
+                    frames.push(Frame {
+                        left,
+                        top,
+                        width: frame_width,
+                        height: frame_height,
+                        interlaced: packed & 0x40 != 0,
+                        local_palette,
+                        min_code_size,
+                        data: r.sub_blocks()?,
+                        disposal,
+                        transparent,
+                    });
+                    (disposal, transparent) = (0, None);
+                }
+                0x3B => break,
+                other => anyhow::bail!("Unexpected block 0x{other:02X} in GIF"),
+            }
+        }
+        if frames.is_empty() {
+            anyhow::bail!("GIF has no image frames");
+        }
+        Ok(Self {
+            width,
+            height,
+            global_palette,
+            frames,
+        })
+    }
+
+    /// Returns the number of frames.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Renders the frames at `indices` (ascending, 0-based) as PNG images of
+    /// the whole animation canvas, with transparent areas made white.
+    pub fn frames_to_png(&self, indices: &[usize]) -> Result<Vec<Vec<u8>>> {
+        let mut canvas = vec![None; self.width * self.height];
+        let mut pngs = Vec::with_capacity(indices.len());
+        let Some(&last) = indices.last() else {
+            return Ok(pngs);
+        };
+        for (index, frame) in self.frames.iter().enumerate().take(last + 1) {
+            let saved = (frame.disposal == 3).then(|| canvas.clone());
+            self.draw(frame, &mut canvas)?;
+            if indices.contains(&index) {
+                pngs.push(encode_png(self.width, self.height, &canvas)?);
+            }
+            match (frame.disposal, saved) {
+                (3, Some(saved)) => canvas = saved,
+                (2, _) => self.each_pixel(frame, |i, _, _| canvas[i] = None),
+                _ => {}
+            }
+        }
+        Ok(pngs)
+    }
+
+    fn draw(&self, frame: &Frame, canvas: &mut [Option<[u8; 3]>]) -> Result<()> {
+        let palette = frame
+            .local_palette
+            .as_ref()
+            .or(self.global_palette.as_ref())
+            .context("GIF frame has no colour table")?;
+        let mut indices = Vec::new();
+        // Streams without an end code are common; whatever decoded is used.
+        let _ = weezl::decode::Decoder::new(weezl::BitOrder::Lsb, frame.min_code_size)
+            .into_vec(&mut indices)
+            .decode_all(&frame.data);
+        indices.resize(frame.width * frame.height, 0);
+
+        self.each_pixel(frame, |i, x, y| {
+            let row = source_row(y, frame.height, frame.interlaced);
+            let index = indices[row * frame.width + x];
+            if Some(index) == frame.transparent {
+                return;
+            }
+            let at = index as usize * 3;
+            if let Some(rgb) = palette.get(at..at + 3) {
+                canvas[i] = Some([rgb[0], rgb[1], rgb[2]]);
+            }
+        });
+        Ok(())
+    }
+
+    /// Calls `f(canvas_index, x, y)` for every pixel of `frame` that lies on
+    /// the canvas, with `x`/`y` relative to the frame.
+    fn each_pixel(&self, frame: &Frame, mut f: impl FnMut(usize, usize, usize)) {
+        for y in 0..frame.height {
+            let canvas_y = frame.top + y;
+            if canvas_y >= self.height {
+                break;
+            }
+            for x in 0..frame.width {
+                let canvas_x = frame.left + x;
+                if canvas_x >= self.width {
+                    break;
+                }
+                f(canvas_y * self.width + canvas_x, x, y);
+            }
+        }
+    }
+}
+
+/// Returns which decoded row holds display row `y` of an image `height` rows
+/// high: interlaced GIFs store rows in four passes (every 8th from 0, every
+/// 8th from 4, every 4th from 2, every 2nd from 1).
+fn source_row(y: usize, height: usize, interlaced: bool) -> usize {
+    if !interlaced {
+        return y;
+    }
+    let mut row = 0;
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        if y >= start && (y - start).is_multiple_of(step) {
+            return row + (y - start) / step;
+        }
+        row += height.saturating_sub(start).div_ceil(step);
+    }
+    y
+}
+
+/// Encodes an RGB canvas as an 8-bit truecolour PNG; unset pixels are white.
+fn encode_png(width: usize, height: usize, canvas: &[Option<[u8; 3]>]) -> Result<Vec<u8>> {
+    let mut raw = Vec::with_capacity(height * (width * 3 + 1));
+    for row in canvas.chunks(width.max(1)) {
+        raw.push(0); // filter type: none
+        for pixel in row {
+            raw.extend_from_slice(&pixel.unwrap_or([0xFF; 3]));
+        }
+    }
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&raw)?;
+    let compressed = encoder.finish()?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit RGB, no interlace
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [
+        (b"IHDR", header.as_slice()),
+        (b"IDAT", &compressed),
+        (b"IEND", &[]),
+    ] {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let crc = crc32fast::hash(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    Ok(png)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let slice = self
+            .bytes
+            .get(self.pos..self.pos + n)
+            .context("GIF is truncated")?;
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    /// Reads the colour table announced by a descriptor's `packed` byte.
+    fn palette(&mut self, packed: u8) -> Result<Option<Vec<u8>>> {
+        if packed & 0x80 == 0 {
+            return Ok(None);
+        }
+        let entries = 2usize << (packed & 0x07);
+        Ok(Some(self.take(entries * 3)?.to_vec()))
+    }
+
+    /// Reads a chain of data sub-blocks up to the zero-length terminator.
+    fn sub_blocks(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        loop {
+            let len = self.u8()? as usize;
+            if len == 0 {
+                return Ok(data);
+            }
+            data.extend_from_slice(self.take(len)?);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use scraper::{ElementRef, Html, Node};
+
+/// Elements whose content is never visible text.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "svg", "head"];
+
+/// Elements that start a new line in the extracted text.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Extracts readable text from an HTML document.
+///
+/// Keeps visible text (including link text), the page title, image `alt`
+/// attributes and figure captions, and drops markup, scripts and styles.
+pub fn html_to_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let mut out = String::new();
+
+    if let Some(title) = document
+        .root_element()
+        .descendent_elements()
+        .find(|e| e.value().name() == "title")
+    {
+        let title = collapse_whitespace(&title.text().collect::<String>());
+        if !title.is_empty() {
+            out.push_str(&title);
+            out.push('\n');
+        }
+    }
+
+    walk(document.root_element(), &mut out);
+    tidy_lines(&out)
+}
+
+fn walk(element: ElementRef<'_>, out: &mut String) {
+    let name = element.value().name();
+    if SKIPPED_ELEMENTS.contains(&name) {
+        return;
+    }
+    let is_block = BLOCK_ELEMENTS.contains(&name);
+    if is_block {
+        out.push('\n');
+    }
+
+    if name == "img" {
+        if let Some(alt) = element.value().attr("alt").map(collapse_whitespace) {
+            if !alt.is_empty() {
+                out.push_str(&format!(" [Изображение: {alt}] "));
+            }
+        }
+    }
+
+    for child in element.children() {
+        if let Some(child_element) = ElementRef::wrap(child) {
+            walk(child_element, out);
+        } else if let Node::Text(text) = child.value() {
+            out.push_str(text);
+        }
+    }
+
+    if is_block {
+        out.push('\n');
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Collapses whitespace within lines and drops empty lines.
+fn tidy_lines(text: &str) -> String {
+    text.lines()
+        .map(collapse_whitespace)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Custom instructions for the files matching one pattern.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileInstructions {
+    /// Added to the OCR prompt of matching PDFs and images.
+    pub ocr: Option<String>,
+    /// Given to the summary model for the matching files.
+    pub summary: Option<String>,
+}
+
+/// Per-file instructions loaded from an `instructions.yaml` that maps file
+/// names or globs to [`FileInstructions`]. The first matching entry wins.
+#[derive(Debug, Default)]
+pub struct InstructionSet {
+    rules: Vec<(GlobMatcher, FileInstructions)>,
+}
+
+impl InstructionSet {
+    /// Loads the mapping at `path`, keeping the file's entry order.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read instructions file '{path}'"))?;
+        let entries: serde_yaml::Mapping = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse instructions file '{path}'"))?;
+
+        let mut rules = Vec::with_capacity(entries.len());
+        for (pattern, instructions) in entries {
+            let pattern = pattern
+                .as_str()
+                .with_context(|| format!("{path}: every key must be a file name or glob"))?
+                .to_string();
+            let matcher = Glob::new(&pattern)
+                .with_context(|| format!("{path}: invalid glob '{pattern}'"))?
+                .compile_matcher();
+            let instructions: FileInstructions = serde_yaml::from_value(instructions)
+                .with_context(|| format!("{path}: invalid instructions for '{pattern}'"))?;
+            rules.push((matcher, instructions));
+        }
+        Ok(Self { rules })
+    }
+
+    /// Returns the instructions for the file named `name` (relative to the
+    /// scanned directory), matching either that path or its base name.
+    pub fn for_file(&self, name: &str) -> Option<&FileInstructions> {
+        let base = Path::new(name).file_name().map(Path::new);
+        self.rules
+            .iter()
+            .find(|(matcher, _)| {
+                matcher.is_match(name) || base.is_some_and(|b| matcher.is_match(b))
+            })
+            .map(|(_, instructions)| instructions)
+    }
+
+    /// Returns the OCR instruction for `name`, if any.
+    pub fn ocr_for(&self, name: &str) -> Option<&str> {
+        self.for_file(name)?.ocr.as_deref()
+    }
+
+    /// Returns the summary instruction for `name`, if any.
+    pub fn summary_for(&self, name: &str) -> Option<&str> {
+        self.for_file(name)?.summary.as_deref()
+    }
+}
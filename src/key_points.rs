@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::language::OutputLanguage;
+use crate::summary::SummaryInput;
+
+/// One row of the CSV output: a key point of one file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KeyPoint {
+    pub file: String,
+    pub point: String,
+    pub category: String,
+}
+
+/// Returns where the CSV output is written: `output_path` with a `.csv`
+/// extension.
+pub fn csv_output_path(output_path: &str) -> String {
+    Path::new(output_path)
+        .with_extension("csv")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Builds the messages asking the model for key points as a JSON array.
+pub fn build_key_point_messages(input: &SummaryInput, language: &OutputLanguage) -> Vec<Value> {
+    let system_prompt = format!(
+        "Ты — аналитик, готовящий данные для таблицы. \
+         Пользователь предоставит содержимое нескольких файлов. \
+         Для КАЖДОГО файла выдели его ключевые тезисы.\n\n\
+         Верни ТОЛЬКО JSON-массив объектов без пояснений и без Markdown, в формате:\n\
+         [{{\"file\": \"имя файла\", \"point\": \"ключевой тезис\", \"category\": \"категория\"}}]\n\n\
+         Имя файла бери из заголовка `=== File: ... ===`. Категория — одно-два слова \
+         (например: «факт», «вывод», «риск», «требование»). \
+         Тезисы и категории пиши {}.",
+        language.in_language()
+    );
+
+    vec![
+        json!({"role": "system", "content": system_prompt}),
+        json!({"role": "user", "content": format!(
+            "Выдели ключевые тезисы следующих файлов:\n\n{}", input.combined_text
+        )}),
+    ]
+}
+
+/// Parses the model's reply into key points, tolerating a ```` ```json ````
+/// fence around the array. Fails on malformed JSON, an empty array or rows
+/// with empty fields.
+pub fn parse_key_points(reply: &str) -> Result<Vec<KeyPoint>> {
+    let trimmed = reply.trim();
+    let body = match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let rest = rest.split_once('\n').map_or("", |(_, body)| body);
+            rest.trim_end().strip_suffix("```").unwrap_or(rest)
+        }
+        None => trimmed,
+    };
+
+    let points: Vec<KeyPoint> =
+        serde_json::from_str(body.trim()).context("Reply is not a JSON array of key points")?;
+    if points.is_empty() {
+        anyhow::bail!("Reply contains no key points");
+    }
+    if let Some(bad) = points
+        .iter()
+        .find(|p| p.file.trim().is_empty() || p.point.trim().is_empty())
+    {
+        anyhow::bail!("Key point with an empty file or text: {bad:?}");
+    }
+    Ok(points)
+}
+
+/// Renders key points as CSV with a `file,key_point,category` header.
+pub fn to_csv(points: &[KeyPoint]) -> String {
+    let mut csv = String::from("file,key_point,category\n");
+    for p in points {
+        let _ = writeln!(
+            csv,
+            "{},{},{}",
+            csv_field(&p.file),
+            csv_field(&p.point),
+            csv_field(&p.category)
+        );
+    }
+    csv
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    let value = value.trim();
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+
+/// Heading of the keyword section appended to the summary.
+pub const KEYWORDS_HEADING: &str = "## Ключевые термины";
+
+/// Words shorter than this (in characters) are never keywords.
+const MIN_TERM_LEN: usize = 3;
+
+#[rustfmt::skip]
+const STOPWORDS_RU: &[&str] = &[
+    "без", "более", "больше", "будет", "будто", "бы", "был", "была", "были", "было", "быть", "вам",
+    "вас", "вот", "впрочем", "все", "всего", "всех", "всю", "вся", "где", "даже", "для", "его",
+    "ее", "её", "если", "есть", "еще", "ещё", "зачем", "здесь", "из", "или", "им", "иногда", "их",
+    "как", "какая", "какой", "когда", "кого", "кроме", "кто", "куда", "ли", "между", "меня", "мне",
+    "много", "может", "можно", "мой", "моя", "над", "надо", "наконец", "нас", "него", "нее", "неё",
+    "ней", "нельзя", "нет", "ним", "них", "ничего", "однако", "она", "они", "оно", "опять", "особо",
+    "от", "перед", "под", "после", "потом", "потому", "почти", "при", "про", "раз", "разве", "сам",
+    "свою", "себе", "себя", "сейчас", "со", "совсем", "так", "также", "такой", "там", "тебя", "тем",
+    "теперь", "то", "тогда", "того", "тоже", "только", "том", "тот", "три", "тут", "уже", "хорошо",
+    "хоть", "чего", "чем", "через", "что", "чтоб", "чтобы", "чуть", "эти", "этого", "этой", "этом",
+    "этот", "эту", "это", "является",
+];
+
+const STOPWORDS_EN: &[&str] = &[
+    "about", "above", "after", "again", "against", "all", "also", "and", "any", "are", "because",
+    "been", "before", "being", "below", "between", "both", "but", "can", "could", "did", "does",
+    "doing", "down", "during", "each", "few", "for", "from", "further", "had", "has", "have",
+    "having", "her", "here", "hers", "herself", "him", "himself", "his", "how", "into", "its",
+    "itself", "just", "more", "most", "not", "now", "off", "once", "only", "other", "our", "ours",
+    "out", "over", "own", "same", "she", "should", "some", "such", "than", "that", "the", "their",
+    "them", "then", "there", "these", "they", "this", "those", "through", "too", "under", "until",
+    "very", "was", "were", "what", "when", "where", "which", "while", "who", "whom", "why", "will",
+    "with", "would", "you", "your", "yours",
+];
+
+/// Returns the built-in stopword list for a language code, if there is one.
+pub fn stopwords(language: &str) -> Option<&'static [&'static str]> {
+    match language {
+        "ru" => Some(STOPWORDS_RU),
+        "en" => Some(STOPWORDS_EN),
+        _ => None,
+    }
+}
+
+/// Counts term frequencies in `text` and returns the `count` most frequent
+/// terms, most frequent first (ties alphabetically).
+///
+/// Terms are lowercase words of at least [`MIN_TERM_LEN`] letters that are
+/// not in the stopword lists of `languages`. `=== File: ... ===` headers of
+/// the combined input are ignored.
+pub fn top_terms(text: &str, count: usize, languages: &[String]) -> Vec<(String, usize)> {
+    let stop: HashSet<&str> = languages
+        .iter()
+        .filter_map(|l| stopwords(l))
+        .flatten()
+        .copied()
+        .collect();
+
+    let mut frequencies: HashMap<String, usize> = HashMap::new();
+    for line in text.lines().filter(|l| !l.starts_with("=== File: ")) {
+        for word in line.split(|c: char| !c.is_alphanumeric()) {
+            if word.chars().count() < MIN_TERM_LEN || !word.chars().any(char::is_alphabetic) {
+                continue;
+            }
+            let word = word.to_lowercase();
+            if !stop.contains(word.as_str()) {
+                *frequencies.entry(word).or_default() += 1;
+            }
+        }
+    }
+
+    let mut terms: Vec<(String, usize)> = frequencies.into_iter().collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms.truncate(count);
+    terms
+}
+
+/// Appends a [`KEYWORDS_HEADING`] section listing `terms` to `summary`,
+/// replacing one left over from a previous run (e.g. kept by a revision).
+pub fn append_keywords(summary: &str, terms: &[(String, usize)]) -> String {
+    let body = match summary.find(KEYWORDS_HEADING) {
+        Some(start) => &summary[..start],
+        None => summary,
+    };
+    let list: Vec<String> = terms
+        .iter()
+        .map(|(term, n)| format!("{term} ({n})"))
+        .collect();
+    format!(
+        "{}\n\n{KEYWORDS_HEADING}\n\n{}\n",
+        body.trim_end(),
+        list.join(", ")
+    )
+}
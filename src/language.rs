@@ -0,0 +1,79 @@
+/// Languages known by ISO 639-1 code: code, English name, and the Russian
+/// adjective used in the prompts ("на русском языке").
+const KNOWN_LANGUAGES: &[(&str, &str, &str)] = &[
+    ("ar", "Arabic", "арабском"),
+    ("be", "Belarusian", "белорусском"),
+    ("cs", "Czech", "чешском"),
+    ("de", "German", "немецком"),
+    ("en", "English", "английском"),
+    ("es", "Spanish", "испанском"),
+    ("fr", "French", "французском"),
+    ("he", "Hebrew", "иврите"),
+    ("hi", "Hindi", "хинди"),
+    ("it", "Italian", "итальянском"),
+    ("ja", "Japanese", "японском"),
+    ("kk", "Kazakh", "казахском"),
+    ("ko", "Korean", "корейском"),
+    ("nl", "Dutch", "нидерландском"),
+    ("pl", "Polish", "польском"),
+    ("pt", "Portuguese", "португальском"),
+    ("ru", "Russian", "русском"),
+    ("tr", "Turkish", "турецком"),
+    ("uk", "Ukrainian", "украинском"),
+    ("zh", "Chinese", "китайском"),
+];
+
+/// The language the models are asked to answer in (`output_language`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLanguage {
+    code: Option<&'static str>,
+    name: String,
+    adjective: Option<&'static str>,
+}
+
+impl OutputLanguage {
+    /// Reads a language name ("Spanish") or ISO 639-1 code ("es"), in any
+    /// case. Names not in the built-in list are used as given.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        let known = KNOWN_LANGUAGES.iter().find(|(code, name, _)| {
+            code.eq_ignore_ascii_case(value) || name.eq_ignore_ascii_case(value)
+        });
+        match known {
+            Some(&(code, name, adjective)) => Self {
+                code: Some(code),
+                name: name.to_string(),
+                adjective: Some(adjective),
+            },
+            None => Self {
+                code: None,
+                name: value.to_string(),
+                adjective: None,
+            },
+        }
+    }
+
+    /// Returns the human-readable name, e.g. `Spanish`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the ISO 639-1 code, or the lowercased name for languages
+    /// outside the built-in list.
+    pub fn code(&self) -> String {
+        match self.code {
+            Some(code) => code.to_string(),
+            None => self.name.to_lowercase(),
+        }
+    }
+
+    /// Returns the prompt phrase naming the language after "Отвечай",
+    /// e.g. `на испанском языке`.
+    pub fn in_language(&self) -> String {
+        match self.adjective {
+            Some(adjective) if adjective.ends_with("ом") => format!("на {adjective} языке"),
+            Some(adjective) => format!("на {adjective}"),
+            None => format!("на языке «{}»", self.name),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+pub mod api;
+pub mod cassette;
+pub mod chunking;
+pub mod clock;
+pub mod confidence;
+pub mod config;
+pub mod document_profile;
+pub mod downscale;
+pub mod extraction_tools;
+pub mod file_filter;
+pub mod file_processor;
+pub mod gif;
+pub mod html;
+pub mod instructions;
+pub mod key_points;
+pub mod keywords;
+pub mod language;
+pub mod logger;
+pub mod manifest;
+pub mod markdown;
+pub mod messages_template;
+pub mod ocr_cache;
+pub mod office;
+pub mod output;
+pub mod output_name;
+pub mod profile;
+pub mod progress;
+pub mod prompt_template;
+pub mod sanitize;
+pub mod secrets;
+pub mod sections;
+pub mod summary;
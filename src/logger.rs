@@ -1,11 +1,17 @@
 use chrono::Local;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
 use std::sync::Mutex;
 
-/// Log level for messages.
-#[derive(Clone, Copy)]
+use crate::progress::{ProgressDisplay, ProgressEvent, ProgressFile};
+
+/// Log level for messages, ordered by severity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LogLevel {
+    #[default]
     Info,
     Warn,
     Error,
@@ -21,54 +27,257 @@ impl LogLevel {
     }
 }
 
+/// Console stream(s) that log lines are written to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    /// INFO/WARN to stdout, ERROR to stderr.
+    #[default]
+    Split,
+    /// Everything to stderr, keeping stdout free for piped output.
+    Stderr,
+    /// Everything to stdout.
+    Stdout,
+}
+
+impl LogStream {
+    fn use_stderr(self, level: LogLevel) -> bool {
+        match self {
+            LogStream::Split => matches!(level, LogLevel::Error),
+            LogStream::Stderr => true,
+            LogStream::Stdout => false,
+        }
+    }
+}
+
+/// Layout of each log line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `[timestamp] [LEVEL] message`.
+    #[default]
+    Text,
+    /// One JSON object per line with `timestamp` (RFC 3339), `level` and
+    /// `message`.
+    Json,
+}
+
+impl LogFormat {
+    /// Formats a log line at the current time.
+    fn line(self, level: LogLevel, message: &str) -> String {
+        let now = Local::now();
+        match self {
+            LogFormat::Text => format!(
+                "[{}] [{}] {message}",
+                now.format("%Y-%m-%d %H:%M:%S"),
+                level.as_str()
+            ),
+            LogFormat::Json => serde_json::to_string(&JsonLine {
+                timestamp: now.to_rfc3339(),
+                level: level.as_str(),
+                message,
+            })
+            .unwrap_or_default(),
+        }
+    }
+
+    /// Formats a console notice: bare text, or a JSON line like any other.
+    fn notice(self, level: LogLevel, message: &str) -> String {
+        match self {
+            LogFormat::Text => message.to_string(),
+            LogFormat::Json => self.line(level, message),
+        }
+    }
+}
+
+/// A log line in [`LogFormat::Json`].
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    timestamp: String,
+    level: &'static str,
+    message: &'a str,
+}
+
+/// The open log file and how much it holds, for size-based rotation.
+struct LogFile {
+    file: File,
+    path: PathBuf,
+    len: u64,
+}
+
+impl LogFile {
+    fn open(path: &str) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            file,
+            path: PathBuf::from(path),
+            len,
+        })
+    }
+
+    /// Shifts `app.log.N` to `app.log.N+1` (dropping the oldest), renames the
+    /// current file to `app.log.1` and starts a fresh one.
+    fn rotate(&mut self, keep: usize) -> std::io::Result<()> {
+        let generation = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+        if keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(generation(keep));
+            for n in (1..keep).rev() {
+                let _ = fs::rename(generation(n), generation(n + 1));
+            }
+            fs::rename(&self.path, generation(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+/// Size-based log rotation settings.
+#[derive(Clone, Copy)]
+struct Rotation {
+    max_bytes: u64,
+    keep: usize,
+}
+
 /// A simple logger that writes to both stdout/stderr and an optional log file.
 pub struct Logger {
-    file: Option<Mutex<File>>,
+    file: Option<Mutex<LogFile>>,
+    stream: LogStream,
+    format: LogFormat,
+    min_level: LogLevel,
+    progress: Option<ProgressFile>,
+    bar: Option<ProgressDisplay>,
+    rotation: Option<Rotation>,
 }
 
 impl Logger {
-    /// Creates a new logger. If `log_path` is non-empty, log messages are also
-    /// appended to the specified file. If the file cannot be opened, logging
-    /// continues to the console only.
-    pub fn new(log_path: &str) -> Self {
+    /// Creates a new logger writing to the console according to `stream`. If
+    /// `log_path` is non-empty, log messages are also appended to the specified
+    /// file. If the file cannot be opened, logging continues to the console only.
+    pub fn new(log_path: &str, stream: LogStream) -> Self {
+        Self::with_format(log_path, stream, LogFormat::Text)
+    }
+
+    /// Like [`Logger::new`], but writes every line, including the notices
+    /// about the log file, in `format`.
+    pub fn with_format(log_path: &str, stream: LogStream, format: LogFormat) -> Self {
         let file = if log_path.is_empty() {
             None
         } else {
-            match OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(log_path)
-            {
+            match LogFile::open(log_path) {
                 Ok(f) => {
-                    println!("Logging to file: {log_path}");
+                    let notice =
+                        format.notice(LogLevel::Info, &format!("Logging to file: {log_path}"));
+                    if stream.use_stderr(LogLevel::Info) {
+                        eprintln!("{notice}");
+                    } else {
+                        println!("{notice}");
+                    }
                     Some(Mutex::new(f))
                 }
                 Err(e) => {
-                    eprintln!("Warning: could not open log file '{log_path}': {e}");
+                    let notice = format.notice(
+                        LogLevel::Warn,
+                        &format!("Warning: could not open log file '{log_path}': {e}"),
+                    );
+                    eprintln!("{notice}");
                     None
                 }
             }
         };
 
-        Self { file }
+        Self {
+            file,
+            stream,
+            format,
+            min_level: LogLevel::Info,
+            progress: None,
+            bar: None,
+            rotation: None,
+        }
+    }
+
+    /// Rotates the log file once it would grow beyond `max_bytes`, keeping
+    /// `keep` older generations (`app.log.1` is the newest). `max_bytes` of 0
+    /// disables rotation.
+    pub fn with_rotation(mut self, max_bytes: u64, keep: usize) -> Self {
+        self.rotation = (max_bytes > 0).then_some(Rotation { max_bytes, keep });
+        self
+    }
+
+    /// Drops messages below `level`, on the console and in the file.
+    pub fn with_min_level(mut self, level: LogLevel) -> Self {
+        self.min_level = level;
+        self
+    }
+
+    /// Also reports progress events to `progress`.
+    pub fn with_progress_file(mut self, progress: ProgressFile) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Shows a progress bar on the terminal while files are read, if
+    /// `enabled` and stdout is a terminal (never when output is piped).
+    pub fn with_progress_bar(mut self, enabled: bool) -> Self {
+        self.bar = (enabled && std::io::stdout().is_terminal()).then(ProgressDisplay::default);
+        self
+    }
+
+    /// Reports a progress event to the progress file and bar, if any.
+    pub fn progress(&self, event: ProgressEvent) {
+        if let Some(bar) = &self.bar {
+            bar.update(&event);
+        }
+        if let Some(progress) = &self.progress {
+            progress.emit(&event);
+        }
     }
 
     /// Logs a message at the given level to console and (optionally) to the file.
     pub fn log(&self, level: LogLevel, message: &str) {
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-        let level_str = level.as_str();
-        let formatted = format!("[{timestamp}] [{level_str}] {message}");
+        if level < self.min_level {
+            return;
+        }
+        let formatted = self.format.line(level, message);
 
         // Console output
-        match level {
-            LogLevel::Error => eprintln!("{formatted}"),
-            _ => println!("{formatted}"),
+        let print = || {
+            if self.stream.use_stderr(level) {
+                eprintln!("{formatted}");
+            } else {
+                println!("{formatted}");
+            }
+        };
+        match &self.bar {
+            Some(bar) => bar.suspend(print),
+            None => print(),
         }
 
         // File output
         if let Some(ref file_mutex) = self.file {
-            if let Ok(mut f) = file_mutex.lock() {
-                let _ = writeln!(f, "{formatted}");
+            if let Ok(mut log) = file_mutex.lock() {
+                let line_len = formatted.len() as u64 + 1;
+                if let Some(rotation) = self.rotation {
+                    if log.len > 0 && log.len + line_len > rotation.max_bytes {
+                        if let Err(e) = log.rotate(rotation.keep) {
+                            eprintln!("Warning: could not rotate log file: {e}");
+                        }
+                    }
+                }
+                if writeln!(log.file, "{formatted}").is_ok() {
+                    log.len += line_len;
+                }
             }
         }
     }
@@ -1,56 +1,486 @@
-mod api;
-mod config;
-mod file_processor;
-mod logger;
-mod summary;
+mod cli;
 
 use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::api::OpenRouterClient;
-use crate::config::Config;
-use crate::file_processor::read_all_files;
-use crate::logger::Logger;
-use crate::summary::SummaryInput;
+use files::api::{ChatApi, OpenRouterClient, DEFAULT_BASE_URL};
+use files::cassette::CassetteClient;
+use files::config::Config;
+use files::file_processor::{
+    document_part, effective_extensions, plan_files, read_all_files, read_single_file, read_stdin,
+    single_document, PlannedAction, ReadOutcome, STDIN_NAME,
+};
+use files::logger::Logger;
+use files::manifest;
+use files::messages_template;
+use files::profile::{self, RunProfile};
+use files::progress::{ProgressEvent, ProgressFile};
+use files::summary::{self, SummaryInput};
+
+use crate::cli::{Cli, DEFAULT_CONFIG};
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // .env is optional — environment variables may be set externally.
-    let _ = dotenvy::dotenv();
+async fn main() -> Result<ExitCode> {
+    let mut cli = Cli::parse();
+    // `--input-file` is the FILE argument under another name.
+    if let Some(path) = cli.input_file.take() {
+        cli.file = Some(path);
+    }
+    if cli.stdin && cli.config == "-" {
+        anyhow::bail!("--stdin and --config - cannot both read standard input");
+    }
+
+    match &cli.env_file {
+        Some(path) => {
+            dotenvy::from_path(path)
+                .with_context(|| format!("Failed to load env file '{path}'"))?;
+        }
+        // .env is optional — environment variables may be set externally.
+        None => {
+            let _ = dotenvy::dotenv();
+        }
+    }
+
+    // A single file or standard input can be summarized without any config
+    // file at all.
+    let single_input = cli.file.is_some() || cli.stdin;
+    let overrides = cli.config_overrides();
+    let mut config =
+        if single_input && cli.config == DEFAULT_CONFIG && !Path::new(DEFAULT_CONFIG).exists() {
+            Config::parse_with_overrides("{}", &overrides)?
+        } else {
+            Config::load_with_overrides(&cli.config, &overrides).await?
+        };
+    if cli.no_cache {
+        config.cache_dir.clear();
+    }
+    // An explicit file (or --stdin) replaces the configured files_directory;
+    // only --files-dir conflicts with it, which clap rejects.
+    if !single_input && cli.combined_input.is_none() && config.files_directory.is_empty() {
+        anyhow::bail!(
+            "files_directory is not set in the config; set it, pass --files-dir, \
+             or pass a file to summarize"
+        );
+    }
 
-    let config = Config::load("config.yaml")?;
+    let mut logger = Logger::with_format(&config.log_file, config.log_stream, config.log_format)
+        .with_min_level(config.log_level)
+        .with_rotation(config.log_max_bytes, config.log_max_files)
+        .with_progress_bar(config.progress);
+    if let Some(path) = &config.progress_file {
+        let progress = ProgressFile::create(path)
+            .with_context(|| format!("Failed to create progress file '{path}'"))?;
+        logger = logger.with_progress_file(progress);
+    }
+    for warning in &config.load_warnings {
+        logger.warn(warning);
+    }
 
-    let logger = Logger::new(&config.log_file);
+    let logger = Arc::new(logger);
+    let result = run(&cli, &config, &logger).await;
+    logger.progress(ProgressEvent::RunFinished { ok: result.is_ok() });
+    match result {
+        Err(e) if e.is::<SummaryFailed>() => {
+            eprintln!("Error: {e:?}");
+            Ok(ExitCode::from(EXIT_SUMMARY_FAILED))
+        }
+        result => result.map(|()| ExitCode::SUCCESS),
+    }
+}
 
-    let api_key =
-        std::env::var("OPENROUTER_API_KEY").context("OPENROUTER_API_KEY not set in .env")?;
+/// Exit code of a run whose files were extracted but whose summary failed;
+/// other errors exit with 1.
+const EXIT_SUMMARY_FAILED: u8 = 3;
 
-    let api = OpenRouterClient::new(api_key)?;
+/// Context of an error from the summary request, once the extracted text
+/// has been saved.
+#[derive(Debug)]
+struct SummaryFailed;
 
-    logger.info(&format!(
-        "Reading files from directory: '{}'",
-        config.files_directory
-    ));
-    logger.info(&format!("OCR model: {}", config.ocr_model));
+impl std::fmt::Display for SummaryFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Files were extracted but the summary could not be produced"
+        )
+    }
+}
+
+/// Runs extraction and summarization once the config and logger are set up.
+async fn run(cli: &Cli, config: &Config, logger: &Arc<Logger>) -> Result<()> {
+    if cli.dry_run {
+        return dry_run(cli, config, logger);
+    }
+
+    // Replaying a cassette never touches the network, so no key is needed.
+    let api_key = match (&cli.replay_cassette, &cli.api_key) {
+        (Some(_), _) => std::env::var("OPENROUTER_API_KEY").unwrap_or_default(),
+        (None, Some(key)) => key.clone(),
+        (None, None) => {
+            let source = config
+                .api_key_source
+                .secret_source_with_file(config.api_key_file.as_deref())?;
+            source.fetch().await.with_context(|| {
+                format!(
+                    "Failed to read the API key from {}; or pass --api-key",
+                    source.describe()
+                )
+            })?
+        }
+    };
+
+    let run_started = Instant::now();
+    let deadline =
+        (config.max_run_secs > 0).then(|| run_started + Duration::from_secs(config.max_run_secs));
+
+    let mut client = OpenRouterClient::new(api_key)?
+        .with_min_request_interval(Duration::from_millis(config.min_request_interval_ms))
+        .with_max_concurrent_requests(config.max_concurrent_requests)
+        .with_requests_per_minute(config.requests_per_minute)
+        .with_retries(config.max_retries)
+        .with_soft_error_retry(config.retry_soft_errors)
+        .with_deadline(deadline)
+        .with_pricing(config.model_pricing.clone())
+        .with_cost_cap(config.max_cost_usd)
+        .with_escape_repair(config.repair_invalid_utf8)
+        .with_base_url(
+            config_or_env(&config.base_url, "OPENROUTER_BASE_URL")
+                .as_deref()
+                .unwrap_or(DEFAULT_BASE_URL),
+        )
+        .with_fallbacks(&config.ocr_model, &config.ocr_fallback_models)
+        .with_fallbacks(&config.summary_model, &config.summary_fallback_models)
+        .with_attribution(
+            config_or_env(&config.http_referer, "OPENROUTER_HTTP_REFERER").as_deref(),
+            config_or_env(&config.app_title, "OPENROUTER_APP_TITLE").as_deref(),
+        )?;
+    if let Some(retries) = config.retry_budget {
+        client = client.with_retry_budget(retries);
+    }
+    client = client.with_logger(Arc::clone(logger));
+    if cli.verbose {
+        client = client.with_body_logging(config.request_log_max_bytes);
+    }
+    let client = Arc::new(client);
+    let api: Arc<dyn ChatApi> = match (&cli.record_cassette, &cli.replay_cassette) {
+        (Some(_), Some(_)) => {
+            anyhow::bail!("--record-cassette and --replay-cassette are mutually exclusive")
+        }
+        (Some(path), None) => {
+            logger.info(&format!("Recording API interactions to '{path}'"));
+            Arc::new(CassetteClient::record(client.clone(), path))
+        }
+        (None, Some(path)) => {
+            logger.info(&format!("Replaying API interactions from '{path}'"));
+            Arc::new(CassetteClient::replay(path)?)
+        }
+        (None, None) => client.clone(),
+    };
+    let api = api.as_ref();
+
+    if config.max_cost_usd.is_some() {
+        for model in [&config.ocr_model, &config.summary_model] {
+            if !client.has_pricing(model) {
+                logger.warn(&format!(
+                    "max_cost_usd is set but model_pricing has no entry for '{model}'; \
+                     its calls will not count toward the cap"
+                ));
+            }
+        }
+    }
+
+    let result = summarize(cli, config, api, &client, logger, run_started).await;
+    for line in profile::usage_report(&client.usage_by_model(), &config.model_pricing) {
+        logger.info(&line);
+    }
+    result
+}
+
+/// Extracts the input and writes the summary, once the API client is set up.
+async fn summarize(
+    cli: &Cli,
+    config: &Config,
+    api: &dyn ChatApi,
+    client: &OpenRouterClient,
+    logger: &Arc<Logger>,
+    run_started: Instant,
+) -> Result<()> {
     logger.info(&format!("Summary model: {}", config.summary_model));
 
-    let files = read_all_files(&config.files_directory, &api, &config.ocr_model, &logger).await?;
+    // Catch a broken template before spending time on extraction.
+    if let Some(path) = &config.messages_template_path {
+        messages_template::load(path)?;
+    }
 
-    if files.is_empty() {
-        logger.warn(&format!("No files found in '{}'.", config.files_directory));
+    if config.single_pass
+        && cli.combined_input.is_none()
+        && cli.feedback.is_none()
+        && !cli.stdin
+        && try_single_pass(cli, config, api, logger).await?
+    {
         return Ok(());
     }
 
-    let input = SummaryInput::from_files(&files);
+    let mut profile = RunProfile::new();
 
-    summary::generate_and_save(
-        &api,
-        &config.summary_model,
-        &input,
-        config.compress_percent(),
-        &config.output_path,
-        &logger,
-    )
-    .await?;
+    let (outcome, input) = match &cli.combined_input {
+        Some(path) => {
+            logger.info(&format!(
+                "Reading combined input from '{path}' (skipping extraction)"
+            ));
+            (
+                ReadOutcome::default(),
+                SummaryInput::from_combined_file(path)?,
+            )
+        }
+        None => {
+            let source = match &cli.file {
+                _ if cli.stdin => STDIN_NAME,
+                Some(file) => file,
+                None => &config.files_directory,
+            };
+            match &cli.file {
+                _ if cli.stdin => logger.info("Reading text from standard input"),
+                Some(file) => logger.info(&format!("Reading file: '{file}'")),
+                None => logger.info(&format!("Reading files from directory: '{source}'")),
+            }
+            logger.info(&format!("OCR model: {}", config.ocr_model));
+            if cli.verbose {
+                let (text, image) = effective_extensions(config);
+                logger.info(&format!("Text extensions: {}", text.join(", ")));
+                logger.info(&format!("Image extensions: {}", image.join(", ")));
+            }
+
+            logger.progress(ProgressEvent::PhaseStarted {
+                phase: "extraction".to_string(),
+            });
+            let phase_started = Instant::now();
+            let outcome = match &cli.file {
+                _ if cli.stdin => read_stdin(std::io::stdin().lock(), config, logger)?,
+                Some(file) => read_single_file(Path::new(file), config, api, logger).await?,
+                None => read_all_files(config, api, logger).await?,
+            };
+            logger.progress(ProgressEvent::PhaseFinished {
+                phase: "extraction".to_string(),
+                secs: phase_started.elapsed().as_secs_f64(),
+            });
+            profile.record("scan", outcome.scan_time);
+            profile.record(
+                "extraction",
+                phase_started.elapsed().saturating_sub(outcome.scan_time),
+            );
+
+            if outcome.files.is_empty() {
+                outcome.log_failures(logger);
+                let reason = match outcome.candidates {
+                    0 => format!("'{source}' contains no files"),
+                    n if n == outcome.unsupported => {
+                        format!("none of the {n} file(s) in '{source}' is of a supported type")
+                    }
+                    n => format!(
+                        "none of the {n} file(s) in '{source}' could be processed ({})",
+                        outcome.counts()
+                    ),
+                };
+                if config.error_on_empty {
+                    anyhow::bail!("Nothing to summarize: {reason} (error_on_empty is set)");
+                }
+                logger.warn(&format!("Nothing to summarize: {reason}."));
+                return Ok(());
+            }
+
+            let mut input =
+                SummaryInput::from_files_weighted(&outcome.files, &config.compression_weights);
+            if config.include_tree {
+                input.prepend_tree(&outcome.files, source);
+            }
+            (outcome, input)
+        }
+    };
+
+    let stop_reason = if api.deadline_exceeded() {
+        Some(format!("Run deadline of {}s reached", config.max_run_secs))
+    } else if api.cost_cap_reached() {
+        Some(format!(
+            "Cost cap of ${:.2} reached (estimated ${:.4})",
+            config.max_cost_usd.unwrap_or_default(),
+            api.estimated_cost()
+        ))
+    } else {
+        None
+    };
+    if let Some(reason) = stop_reason {
+        outcome.log_failures(logger);
+        let partial_path = summary::partial_output_path(&config.output_path);
+        input.dump(&partial_path)?;
+        logger.warn(&format!("Extracted text saved to '{partial_path}'"));
+        anyhow::bail!("{reason} before summarization; no summary written");
+    }
+
+    if let Some(path) = &config.dump_combined_input {
+        input.dump(path)?;
+        logger.info(&format!("Combined input written to '{path}'"));
+    }
+
+    if input.total_words == 0 {
+        outcome.log_failures(logger);
+        if config.error_on_empty {
+            anyhow::bail!("No text was extracted from any file (error_on_empty is set)");
+        }
+        logger.warn("No text was extracted from any file; skipping the summary request");
+        summary::save_empty_placeholder(config, logger)?;
+        return manifest::save(config, &outcome.files, logger);
+    }
+
+    logger.progress(ProgressEvent::PhaseStarted {
+        phase: "summary".to_string(),
+    });
+    let phase_started = Instant::now();
+    let summarized = match &cli.feedback {
+        Some(path) => {
+            let feedback = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read feedback file '{path}'"))?;
+            summary::revise_and_save(api, config, &input, &feedback, logger).await
+        }
+        None => summary::generate_and_save(api, config, &input, logger).await,
+    };
+    if let Err(e) = summarized {
+        // Keep the extracted text so the summary can be retried without
+        // extracting again.
+        let text_path = match &cli.combined_input {
+            Some(path) => path.clone(),
+            None => {
+                let partial_path = summary::partial_output_path(&config.output_path);
+                input.dump(&partial_path)?;
+                partial_path
+            }
+        };
+        logger.warn(&format!(
+            "Extracted text saved to '{text_path}'; retry the summary with \
+             --combined-input '{text_path}'"
+        ));
+        return Err(e.context(SummaryFailed));
+    }
+    if config.per_file_summaries {
+        summary::save_per_file_summaries(api, config, &input, logger).await?;
+    }
+    manifest::save(config, &outcome.files, logger)?;
+    logger.progress(ProgressEvent::PhaseFinished {
+        phase: "summary".to_string(),
+        secs: phase_started.elapsed().as_secs_f64(),
+    });
+    profile.record("summary", phase_started.elapsed());
+
+    if cli.profile {
+        profile.report(&outcome.files, logger);
+        let traffic = client.response_traffic();
+        logger.info(&format!(
+            "  API responses: {}, {:.1} KiB received, {:.1} KiB decompressed",
+            traffic.responses,
+            traffic.wire_bytes as f64 / 1024.0,
+            traffic.decoded_bytes as f64 / 1024.0
+        ));
+    }
+    if let Some(hint) = profile::concurrency_suggestion(
+        &outcome.files,
+        profile.phase("extraction"),
+        run_started.elapsed(),
+        config.max_concurrency,
+    ) {
+        logger.info(&hint);
+    }
+    outcome.log_failures(logger);
 
     Ok(())
+}
+
+/// Returns the config value, or else the non-empty environment variable `var`.
+fn config_or_env(value: &Option<String>, var: &str) -> Option<String> {
+    value
+        .clone()
+        .or_else(|| std::env::var(var).ok().filter(|v| !v.is_empty()))
+}
+
+/// Logs what a run would do with each file (`--dry-run`) and the estimated
+/// word count of the input, without calling the API.
+fn dry_run(cli: &Cli, config: &Config, logger: &Logger) -> Result<()> {
+    let source = cli.file.as_deref().unwrap_or(&config.files_directory);
+    logger.info(&format!("Dry run: planning '{source}' (no API calls)"));
+    let plan = plan_files(Path::new(source), config)?;
+    for file in &plan {
+        let words = file
+            .words
+            .map(|w| format!(", {w} words"))
+            .unwrap_or_default();
+        logger.info(&format!(
+            "  {}: {} ({:.1} KiB{words})",
+            file.action,
+            file.name,
+            file.size as f64 / 1024.0
+        ));
+    }
+
+    let count = |action| plan.iter().filter(|f| f.action == action).count();
+    let ocr = count(PlannedAction::OcrPdf) + count(PlannedAction::OcrImage);
+    let local_words: usize = plan.iter().filter_map(|f| f.words).sum();
+    logger.info(&format!(
+        "Dry run: {} file(s) read locally, {ocr} sent to OCR, {} cached, {} skipped",
+        count(PlannedAction::Text) + count(PlannedAction::Office),
+        count(PlannedAction::Cached),
+        count(PlannedAction::Skip) + count(PlannedAction::TooLarge)
+    ));
+    match ocr + count(PlannedAction::Cached) {
+        0 => logger.info(&format!("Estimated input: {local_words} words")),
+        n => logger.info(&format!(
+            "Estimated input: {local_words} words plus the text of {n} OCR file(s)"
+        )),
+    }
+    Ok(())
+}
+
+/// Extracts and summarizes the input in one request when it is a single
+/// image or PDF and the config allows it (`single_pass`). Returns `false` if
+/// the two-stage pipeline should run instead.
+async fn try_single_pass(
+    cli: &Cli,
+    config: &Config,
+    api: &dyn ChatApi,
+    logger: &Logger,
+) -> Result<bool> {
+    if !summary::single_pass_supported(config) {
+        logger.info(
+            "single_pass is not used with CSV output, sectioned mode, a messages template, \
+             section budgets or custom prompt templates",
+        );
+        return Ok(false);
+    }
+    let Some(path) = single_document(config, cli.file.as_deref().map(Path::new))? else {
+        return Ok(false);
+    };
+    let file_name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let document = document_part(&path, config)?;
+    match summary::single_pass_and_save(api, config, &file_name, document, logger).await? {
+        Some(input) => {
+            if let Some(dump_path) = &config.dump_combined_input {
+                input.dump(dump_path)?;
+                logger.info(&format!("Combined input written to '{dump_path}'"));
+            }
+            Ok(true)
+        }
+        None => {
+            logger.warn(
+                "Single-pass reply did not separate the text from the summary; \
+                 falling back to extraction and a separate summary request",
+            );
+            Ok(false)
+        }
+    }
 }
\ No newline at end of file
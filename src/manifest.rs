@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::file_processor::ProcessedFile;
+use crate::logger::Logger;
+use crate::output;
+
+/// How a file's text was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Extraction {
+    /// Read locally (text, HTML, Office documents).
+    Text,
+    /// Extracted by the OCR model, or taken from its cache.
+    Ocr,
+}
+
+/// One input file of the summary.
+#[derive(Debug, Serialize)]
+pub struct ManifestFile {
+    pub name: String,
+    pub words: usize,
+    pub extraction: Extraction,
+    /// Model that read the file (OCR'd files only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// The OCR text came from `cache_dir` without a request.
+    pub cached: bool,
+}
+
+/// What a summary was produced from (`write_manifest`). Fields keep their
+/// order and files their input order, so manifests of two runs diff cleanly.
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    /// UTC time the manifest was written, RFC 3339.
+    pub generated_at: String,
+    pub ocr_model: String,
+    pub summary_model: String,
+    pub compress_percent: u8,
+    pub files: Vec<ManifestFile>,
+}
+
+impl Manifest {
+    pub fn new(config: &Config, files: &[ProcessedFile], generated_at: DateTime<Utc>) -> Self {
+        Self {
+            generated_at: generated_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ocr_model: config.ocr_model.clone(),
+            summary_model: config.summary_model.clone(),
+            compress_percent: config.compress_percent(),
+            files: files
+                .iter()
+                .map(|file| ManifestFile {
+                    name: file.name.clone(),
+                    words: file.content.split_whitespace().count(),
+                    extraction: match file.metadata {
+                        Some(_) => Extraction::Ocr,
+                        None => Extraction::Text,
+                    },
+                    model: file.metadata.as_ref().map(|m| m.model.clone()),
+                    cached: file.metadata.as_ref().is_some_and(|m| m.cached),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the manifest as indented JSON.
+    pub fn to_json(&self) -> Result<String> {
+        let mut json =
+            serde_json::to_string_pretty(self).context("Failed to serialize manifest")?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// Returns where the manifest of the summary at `output_path` is written:
+/// `summary.md` becomes `summary.meta.json`.
+pub fn manifest_path(output_path: &str) -> String {
+    Path::new(output_path)
+        .with_extension("meta.json")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Writes the manifest of a summary made from `files` next to
+/// `config.output_path` when `write_manifest` is set.
+pub fn save(config: &Config, files: &[ProcessedFile], logger: &Logger) -> Result<()> {
+    if !config.write_manifest {
+        return Ok(());
+    }
+    let path = manifest_path(&config.output_path);
+    let json = Manifest::new(config, files, Utc::now()).to_json()?;
+    output::write_atomic(&path, json, config.check_disk_space)
+        .with_context(|| format!("Failed to write manifest to '{path}'"))?;
+    logger.info(&format!("Manifest written to '{path}'"));
+    Ok(())
+}
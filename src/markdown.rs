@@ -0,0 +1,484 @@
+use std::fmt::Write as _;
+
+/// Title of an HTML summary without any heading.
+const DEFAULT_TITLE: &str = "Резюме";
+
+/// A Markdown block, limited to the constructs models use in summaries.
+enum Block<'a> {
+    Heading(usize, &'a str),
+    Paragraph(Vec<&'a str>),
+    Code(Vec<&'a str>),
+    Quote(Vec<&'a str>),
+    List(Vec<ListItem<'a>>),
+    Table {
+        header: Vec<&'a str>,
+        rows: Vec<Vec<&'a str>>,
+    },
+    Rule,
+}
+
+struct ListItem<'a> {
+    /// Columns of leading whitespace, which decide the nesting.
+    indent: usize,
+    ordered: bool,
+    /// The item's marker as written (`-`, `3.`).
+    marker: &'a str,
+    text: String,
+}
+
+/// Renders `md` as a standalone UTF-8 HTML page in language `lang`, titled
+/// by its first heading.
+pub fn to_html_document(md: &str, lang: &str) -> String {
+    let blocks = parse(md);
+    let title = blocks
+        .iter()
+        .find_map(|block| match block {
+            Block::Heading(_, text) => Some(inline_text(text)),
+            _ => None,
+        })
+        .unwrap_or_else(|| DEFAULT_TITLE.to_string());
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(lang),
+        escape(&title),
+        blocks_html(&blocks)
+    )
+}
+
+/// Renders `md` as an HTML fragment.
+pub fn to_html(md: &str) -> String {
+    blocks_html(&parse(md))
+}
+
+/// Strips the Markdown syntax from `md`, leaving plain text: emphasis and
+/// headings lose their markers, links keep their text followed by the URL,
+/// list items get `•` bullets and table cells are separated by tabs.
+pub fn to_text(md: &str) -> String {
+    let mut text = Vec::new();
+    for block in parse(md) {
+        let rendered = match block {
+            Block::Heading(_, heading) => inline_text(heading),
+            Block::Paragraph(lines) => inline_text(&lines.join(" ")),
+            Block::Code(lines) => lines.join("\n"),
+            Block::Quote(lines) => lines
+                .iter()
+                .map(|line| inline_text(line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::List(items) => items
+                .iter()
+                .zip(list_levels(&items))
+                .map(|(item, level)| {
+                    let bullet = if item.ordered { item.marker } else { "•" };
+                    format!("{}{bullet} {}", "  ".repeat(level), inline_text(&item.text))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Table { header, rows } => std::iter::once(&header)
+                .chain(&rows)
+                .map(|cells| {
+                    cells
+                        .iter()
+                        .map(|cell| inline_text(cell))
+                        .collect::<Vec<_>>()
+                        .join("\t")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Rule => continue,
+        };
+        text.push(rendered);
+    }
+    let mut text = text.join("\n\n");
+    text.push('\n');
+    text
+}
+
+fn parse(md: &str) -> Vec<Block<'_>> {
+    let lines: Vec<&str> = md.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            i += 1;
+        } else if let Some(fence) = fence(trimmed) {
+            let mut code = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                code.push(lines[i]);
+                i += 1;
+            }
+            i += 1;
+            blocks.push(Block::Code(code));
+        } else if let Some((level, text)) = heading(trimmed) {
+            blocks.push(Block::Heading(level, text));
+            i += 1;
+        } else if is_rule(trimmed) {
+            blocks.push(Block::Rule);
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let mut quote = Vec::new();
+            while let Some(rest) = lines.get(i).and_then(|l| l.trim().strip_prefix('>')) {
+                quote.push(rest.trim());
+                i += 1;
+            }
+            blocks.push(Block::Quote(quote));
+        } else if list_item(line).is_some() {
+            let mut items: Vec<ListItem> = Vec::new();
+            while i < lines.len() {
+                if let Some(item) = list_item(lines[i]) {
+                    items.push(item);
+                } else if lines[i].starts_with([' ', '\t']) && !lines[i].trim().is_empty() {
+                    // A continuation line of the previous item.
+                    if let Some(last) = items.last_mut() {
+                        last.text.push(' ');
+                        last.text.push_str(lines[i].trim());
+                    }
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            blocks.push(Block::List(items));
+        } else if trimmed.starts_with('|') && lines.get(i + 1).is_some_and(|l| is_separator(l)) {
+            let header = cells(trimmed);
+            i += 2;
+            let mut rows = Vec::new();
+            while let Some(row) = lines
+                .get(i)
+                .map(|l| l.trim())
+                .filter(|l| l.starts_with('|'))
+            {
+                rows.push(cells(row));
+                i += 1;
+            }
+            blocks.push(Block::Table { header, rows });
+        } else {
+            let mut paragraph = vec![trimmed];
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && !starts_block(lines[i]) {
+                paragraph.push(lines[i].trim());
+                i += 1;
+            }
+            blocks.push(Block::Paragraph(paragraph));
+        }
+    }
+    blocks
+}
+
+/// Returns `true` if `line` opens a block other than a paragraph, which
+/// ends the paragraph before it.
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim();
+    fence(trimmed).is_some()
+        || heading(trimmed).is_some()
+        || is_rule(trimmed)
+        || trimmed.starts_with(['>', '|'])
+        || list_item(line).is_some()
+}
+
+fn fence(line: &str) -> Option<&'static str> {
+    ["```", "~~~"]
+        .into_iter()
+        .find(|fence| line.starts_with(fence))
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6)
+        .contains(&level)
+        .then(|| (level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+/// `---`, `***` or `___`, possibly spaced out.
+fn is_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ['-', '*', '_'].iter().any(|m| marks.iter().all(|c| c == m))
+}
+
+fn list_item(line: &str) -> Option<ListItem<'_>> {
+    let content = line.trim_start();
+    let indent = line[..line.len() - content.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let (marker, text) = content.split_once(' ')?;
+    let ordered = match marker {
+        "-" | "*" | "+" => false,
+        _ => {
+            let number = marker.strip_suffix(['.', ')'])?;
+            if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            true
+        }
+    };
+    Some(ListItem {
+        indent,
+        ordered,
+        marker,
+        text: text.trim().to_string(),
+    })
+}
+
+/// Returns the nesting level of each item: deeper indentation than the
+/// item before opens a sublist, shallower returns to the matching list.
+fn list_levels(items: &[ListItem]) -> Vec<usize> {
+    let mut indents: Vec<usize> = Vec::new();
+    items
+        .iter()
+        .map(|item| {
+            while indents.last().is_some_and(|&indent| indent > item.indent) {
+                indents.pop();
+            }
+            if indents.last() != Some(&item.indent) {
+                indents.push(item.indent);
+            }
+            indents.len() - 1
+        })
+        .collect()
+}
+
+/// The `|---|:---:|` line under a table header.
+fn is_separator(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with('|')
+        && line.contains('-')
+        && cells(line).iter().all(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.bytes().all(|b| b == b'-')
+        })
+}
+
+fn cells(row: &str) -> Vec<&str> {
+    let row = row.trim().trim_start_matches('|');
+    let row = row.strip_suffix('|').unwrap_or(row);
+    row.split('|').map(str::trim).collect()
+}
+
+fn blocks_html(blocks: &[Block]) -> String {
+    let mut html = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                let _ = writeln!(html, "<h{level}>{}</h{level}>", inline_html(text));
+            }
+            Block::Paragraph(lines) => {
+                let _ = writeln!(html, "<p>{}</p>", inline_html(&lines.join("\n")));
+            }
+            Block::Code(lines) => {
+                let _ = writeln!(
+                    html,
+                    "<pre><code>{}</code></pre>",
+                    escape(&lines.join("\n"))
+                );
+            }
+            Block::Quote(lines) => {
+                let _ = writeln!(
+                    html,
+                    "<blockquote><p>{}</p></blockquote>",
+                    inline_html(&lines.join("\n"))
+                );
+            }
+            Block::List(items) => list_html(items, &mut html),
+            Block::Table { header, rows } => {
+                html.push_str("<table>\n<thead>\n");
+                row_html(header, "th", &mut html);
+                html.push_str("</thead>\n<tbody>\n");
+                for row in rows {
+                    row_html(row, "td", &mut html);
+                }
+                html.push_str("</tbody>\n</table>\n");
+            }
+            Block::Rule => html.push_str("<hr>\n"),
+        }
+    }
+    html
+}
+
+fn list_html(items: &[ListItem], html: &mut String) {
+    let tag = |ordered: bool| if ordered { "ol" } else { "ul" };
+    // Whether each open list is ordered, outermost first.
+    let mut open: Vec<bool> = Vec::new();
+    for (item, level) in items.iter().zip(list_levels(items)) {
+        while open.len() > level + 1 {
+            let ordered = open.pop().unwrap_or_default();
+            let _ = write!(html, "</li>\n</{}>\n", tag(ordered));
+        }
+        if open.len() == level + 1 {
+            html.push_str("</li>\n");
+        } else {
+            let _ = writeln!(html, "<{}>", tag(item.ordered));
+            open.push(item.ordered);
+        }
+        let _ = write!(html, "<li>{}", inline_html(&item.text));
+    }
+    while let Some(ordered) = open.pop() {
+        let _ = write!(html, "</li>\n</{}>\n", tag(ordered));
+    }
+}
+
+fn row_html(cells: &[&str], tag: &str, html: &mut String) {
+    html.push_str("<tr>");
+    for cell in cells {
+        let _ = write!(html, "<{tag}>{}</{tag}>", inline_html(cell));
+    }
+    html.push_str("</tr>\n");
+}
+
+/// Inline Markdown markup.
+enum Span<'a> {
+    Code(&'a str),
+    Strong(&'a str),
+    Emphasis(&'a str),
+    Link(&'a str, &'a str),
+    Image(&'a str, &'a str),
+}
+
+/// Parses the span that `rest` starts with, if any, returning it and the
+/// text after it. `prev` is the character before `rest`: `_` only marks
+/// emphasis at word boundaries, so `snake_case` stays as is.
+fn span(rest: &str, prev: Option<char>) -> Option<(Span<'_>, &str)> {
+    if let Some(after) = rest.strip_prefix('`') {
+        let end = after.find('`')?;
+        return Some((Span::Code(&after[..end]), &after[end + 1..]));
+    }
+    if let Some(after) = rest.strip_prefix("![") {
+        let (alt, url, after) = link_parts(after)?;
+        return Some((Span::Image(alt, url), after));
+    }
+    if let Some(after) = rest.strip_prefix('[') {
+        let (text, url, after) = link_parts(after)?;
+        return Some((Span::Link(text, url), after));
+    }
+    for marker in ["**", "__", "*", "_"] {
+        if let Some((inner, after)) = delimited(rest, marker, prev) {
+            let span = if marker.len() == 2 {
+                Span::Strong(inner)
+            } else {
+                Span::Emphasis(inner)
+            };
+            return Some((span, after));
+        }
+    }
+    None
+}
+
+/// Splits `text](url)rest` into its parts.
+fn link_parts(after: &str) -> Option<(&str, &str, &str)> {
+    let (text, rest) = after.split_once("](")?;
+    let (url, rest) = rest.split_once(')')?;
+    Some((text, url.trim(), rest))
+}
+
+fn delimited<'a>(rest: &'a str, marker: &str, prev: Option<char>) -> Option<(&'a str, &'a str)> {
+    let after = rest.strip_prefix(marker)?;
+    let underscore = marker.starts_with('_');
+    if underscore && prev.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+    let end = after.find(marker)?;
+    let inner = &after[..end];
+    let rest = &after[end + marker.len()..];
+    let open = inner.is_empty()
+        || inner.starts_with(char::is_whitespace)
+        || inner.ends_with(char::is_whitespace)
+        || (underscore && rest.starts_with(char::is_alphanumeric));
+    (!open).then_some((inner, rest))
+}
+
+fn inline_html(text: &str) -> String {
+    let mut html = String::new();
+    walk_inline(text, &mut |piece| match piece {
+        Piece::Char(c) => html.push_str(&escape(c.encode_utf8(&mut [0; 4]))),
+        Piece::Span(Span::Code(code)) => {
+            let _ = write!(html, "<code>{}</code>", escape(code));
+        }
+        Piece::Span(Span::Strong(inner)) => {
+            let _ = write!(html, "<strong>{}</strong>", inline_html(inner));
+        }
+        Piece::Span(Span::Emphasis(inner)) => {
+            let _ = write!(html, "<em>{}</em>", inline_html(inner));
+        }
+        Piece::Span(Span::Link(text, url)) => {
+            let _ = write!(
+                html,
+                "<a href=\"{}\">{}</a>",
+                escape(url),
+                inline_html(text)
+            );
+        }
+        Piece::Span(Span::Image(alt, url)) => {
+            let _ = write!(
+                html,
+                "<img src=\"{}\" alt=\"{}\">",
+                escape(url),
+                escape(alt)
+            );
+        }
+    });
+    html
+}
+
+fn inline_text(text: &str) -> String {
+    let mut plain = String::new();
+    walk_inline(text, &mut |piece| match piece {
+        Piece::Char(c) => plain.push(c),
+        Piece::Span(Span::Code(code)) => plain.push_str(code),
+        Piece::Span(Span::Strong(inner) | Span::Emphasis(inner)) => {
+            plain.push_str(&inline_text(inner))
+        }
+        Piece::Span(Span::Link(text, url)) => {
+            let text = inline_text(text);
+            if text == url {
+                plain.push_str(url);
+            } else {
+                let _ = write!(plain, "{text} ({url})");
+            }
+        }
+        Piece::Span(Span::Image(alt, _)) => plain.push_str(alt),
+    });
+    plain
+}
+
+enum Piece<'a> {
+    Char(char),
+    Span(Span<'a>),
+}
+
+/// Calls `visit` with each span of `text` and each character outside one;
+/// a backslash makes the punctuation after it a plain character.
+fn walk_inline<'a>(text: &'a str, visit: &mut dyn FnMut(Piece<'a>)) {
+    let mut rest = text;
+    let mut prev = None;
+    while let Some(c) = rest.chars().next() {
+        if let Some((span, after)) = span(rest, prev) {
+            visit(Piece::Span(span));
+            prev = rest[..rest.len() - after.len()].chars().next_back();
+            rest = after;
+            continue;
+        }
+        let mut taken = c.len_utf8();
+        let mut plain = c;
+        if c == '\\' {
+            if let Some(next) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                plain = next;
+                taken += next.len_utf8();
+            }
+        }
+        visit(Piece::Char(plain));
+        prev = Some(plain);
+        rest = &rest[taken..];
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+
+/// Roles a template message may have.
+const ROLES: &[&str] = &["system", "user", "assistant"];
+
+/// Loads the summary messages template at `path`: a JSON array of
+/// `{"role": ..., "content": "..."}` objects. Fails unless it is a non-empty
+/// array of such messages with known roles and string contents.
+pub fn load(path: &str) -> Result<Vec<Value>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read messages template '{path}'"))?;
+    let value: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Messages template '{path}' is not valid JSON"))?;
+    let Value::Array(messages) = value else {
+        anyhow::bail!("Messages template '{path}' must be a JSON array of messages");
+    };
+    if messages.is_empty() {
+        anyhow::bail!("Messages template '{path}' contains no messages");
+    }
+    for (index, message) in messages.iter().enumerate() {
+        let role = message["role"].as_str().unwrap_or_default();
+        if !ROLES.contains(&role) {
+            anyhow::bail!(
+                "Messages template '{path}': message {index} needs a role of {}",
+                ROLES.join(", ")
+            );
+        }
+        if !message["content"].is_string() {
+            anyhow::bail!("Messages template '{path}': message {index} needs a string content");
+        }
+    }
+    Ok(messages)
+}
+
+/// Fills the placeholders `{total_words}`, `{target_words}` and
+/// `{combined_text}` in every message content. The combined text goes in last
+/// so that braces inside the documents are left alone.
+pub fn render(
+    messages: &[Value],
+    combined_text: &str,
+    total_words: usize,
+    target_words: usize,
+) -> Vec<Value> {
+    messages
+        .iter()
+        .map(|message| {
+            let mut message = message.clone();
+            if let Some(content) = message["content"].as_str() {
+                let content = content
+                    .replace("{total_words}", &total_words.to_string())
+                    .replace("{target_words}", &target_words.to_string())
+                    .replace("{combined_text}", combined_text);
+                message["content"] = Value::String(content);
+            }
+            message
+        })
+        .collect()
+}
@@ -0,0 +1,68 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extension of cache entries; nothing else in the cache directory is read.
+const ENTRY_EXTENSION: &str = "ocr";
+
+/// OCR results stored on disk (`cache_dir`), keyed by the file's bytes, the
+/// OCR model and the extraction settings so that a changed file, model,
+/// prompt or setting such as `split_pdf_pages` never hits a stale entry.
+pub struct OcrCache {
+    dir: PathBuf,
+}
+
+impl OcrCache {
+    /// Returns the cache in `dir`, or `None` if `dir` is empty (caching off).
+    pub fn new(dir: &str) -> Option<Self> {
+        (!dir.is_empty()).then(|| Self {
+            dir: PathBuf::from(dir),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the cache key of `bytes` read by `model` with the prompt
+    /// settings `variant`: a SHA-256 in hex.
+    pub fn key(bytes: &[u8], model: &str, variant: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(model.as_bytes());
+        hasher.update([0]);
+        hasher.update(variant.as_bytes());
+        hasher.update([0]);
+        hasher.update(bytes);
+        hasher
+            .finalize()
+            .iter()
+            .fold(String::with_capacity(64), |mut hex, b| {
+                let _ = write!(hex, "{b:02x}");
+                hex
+            })
+    }
+
+    /// Returns the text cached under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.entry_path(key)).ok()
+    }
+
+    /// Stores `text` under `key`. The entry is written to a temporary file
+    /// first, so an interrupted run never leaves a truncated entry behind.
+    pub fn put(&self, key: &str, text: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir).with_context(|| {
+            format!("Failed to create cache directory '{}'", self.dir.display())
+        })?;
+        let path = self.entry_path(key);
+        let partial = path.with_extension("tmp");
+        fs::write(&partial, text)
+            .and_then(|()| fs::rename(&partial, &path))
+            .with_context(|| format!("Failed to write cache entry '{}'", path.display()))
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{ENTRY_EXTENSION}"))
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Extensions of the Office Open XML formats read locally.
+pub const OFFICE_EXTENSIONS: &[&str] = &["docx", "xlsx"];
+
+/// Package directories holding embedded objects and media, which are not
+/// extracted.
+const EMBEDDED_DIRS: &[&str] = &[
+    "word/embeddings/",
+    "word/media/",
+    "xl/embeddings/",
+    "xl/media/",
+];
+
+/// Namespace of WordprocessingML elements.
+const WORD_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// Text of an Office document and the embedded parts that were left out.
+pub struct OfficeText {
+    pub text: String,
+    /// Package paths of embedded objects and media that were ignored.
+    pub skipped: Vec<String>,
+}
+
+/// Extracts the text of the `.docx` or `.xlsx` file at `path`: the
+/// paragraphs of a Word document, or the cell values of every worksheet of a
+/// workbook, one row per line.
+pub fn extract(path: &Path, ext: &str) -> Result<OfficeText> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut package = zip::ZipArchive::new(file).context("Not a valid Office document")?;
+    let skipped = package
+        .file_names()
+        .filter(|name| EMBEDDED_DIRS.iter().any(|dir| name.starts_with(dir)))
+        .map(String::from)
+        .collect();
+    let text = match ext {
+        "docx" => docx_text(&read_part(&mut package, "word/document.xml")?)?,
+        "xlsx" => xlsx_text(&mut package)?,
+        other => anyhow::bail!("Unsupported Office format: {other}"),
+    };
+    Ok(OfficeText { text, skipped })
+}
+
+fn read_part(package: &mut zip::ZipArchive<File>, name: &str) -> Result<String> {
+    let mut part = package
+        .by_name(name)
+        .with_context(|| format!("Office document has no '{name}'"))?;
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)
+        .with_context(|| format!("Failed to read '{name}'"))?;
+    Ok(xml)
+}
+
+fn parse(xml: &str) -> Result<Document<'_>> {
+    Document::parse(xml).context("Malformed XML in Office document")
+}
+
+/// Returns the paragraphs of a WordprocessingML body, one per line. Table
+/// cells and text boxes hold paragraphs too, so their text is included;
+/// a text box's paragraphs follow the paragraph it is anchored in.
+fn docx_text(xml: &str) -> Result<String> {
+    let doc = parse(xml)?;
+    let is_paragraph = |n: &Node| n.has_tag_name((WORD_NS, "p"));
+    let mut lines = Vec::new();
+    for paragraph in doc.descendants().filter(is_paragraph) {
+        let mut line = String::new();
+        for node in paragraph.descendants() {
+            if node.tag_name().namespace() != Some(WORD_NS)
+                || node.ancestors().find(is_paragraph) != Some(paragraph)
+            {
+                continue;
+            }
+            match node.tag_name().name() {
+                "t" => line.push_str(node.text().unwrap_or_default()),
+                "tab" => line.push('\t'),
+                "br" | "cr" => line.push('\n'),
+                _ => {}
+            }
+        }
+        lines.push(line);
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Returns the rows of every worksheet, cells separated by tabs, each sheet
+/// under a `--- Лист: name ---` header.
+fn xlsx_text(package: &mut zip::ZipArchive<File>) -> Result<String> {
+    let shared = if package.index_for_name("xl/sharedStrings.xml").is_some() {
+        shared_strings(&read_part(package, "xl/sharedStrings.xml")?)?
+    } else {
+        Vec::new()
+    };
+    let workbook = read_part(package, "xl/workbook.xml")?;
+    let rels = read_part(package, "xl/_rels/workbook.xml.rels")?;
+    let targets: HashMap<String, String> = parse(&rels)?
+        .descendants()
+        .filter(|n| n.tag_name().name() == "Relationship")
+        .filter_map(|n| Some((n.attribute("Id")?.to_string(), n.attribute("Target")?)))
+        .map(|(id, target)| match target.strip_prefix('/') {
+            Some(absolute) => (id, absolute.to_string()),
+            None => (id, format!("xl/{target}")),
+        })
+        .collect();
+
+    let mut sheets = Vec::new();
+    for sheet in parse(&workbook)?
+        .descendants()
+        .filter(|n| n.tag_name().name() == "sheet")
+    {
+        let name = sheet.attribute("name").unwrap_or_default();
+        let Some(target) = sheet
+            .attributes()
+            .find(|a| a.name() == "id")
+            .and_then(|id| targets.get(id.value()))
+        else {
+            continue;
+        };
+        let rows = sheet_rows(&read_part(package, target)?, &shared)?;
+        sheets.push(format!("--- Лист: {name} ---\n{}", rows.join("\n")));
+    }
+    Ok(sheets.join("\n\n"))
+}
+
+fn shared_strings(xml: &str) -> Result<Vec<String>> {
+    Ok(parse(xml)?
+        .descendants()
+        .filter(|n| n.tag_name().name() == "si")
+        .map(|si| inline_text(&si))
+        .collect())
+}
+
+/// Concatenates the `t` runs under `node`, skipping phonetic hints.
+fn inline_text(node: &Node) -> String {
+    node.descendants()
+        .filter(|n| n.tag_name().name() == "t")
+        .filter(|t| !t.ancestors().any(|a| a.tag_name().name() == "rPh"))
+        .filter_map(|t| t.text())
+        .collect()
+}
+
+/// Returns the non-empty rows of a worksheet with their cell values. Cells
+/// are placed in their columns, so values stay aligned across rows even
+/// though empty cells are not stored.
+fn sheet_rows(xml: &str, shared: &[String]) -> Result<Vec<String>> {
+    let doc = parse(xml)?;
+    let mut rows = Vec::new();
+    for row in doc.descendants().filter(|n| n.tag_name().name() == "row") {
+        let mut cells: Vec<String> = Vec::new();
+        for cell in row.children().filter(|n| n.tag_name().name() == "c") {
+            let column = cell
+                .attribute("r")
+                .and_then(column_index)
+                .unwrap_or(cells.len());
+            if column >= cells.len() {
+                cells.resize(column, String::new());
+                cells.push(cell_value(&cell, shared));
+            }
+        }
+        if cells.iter().any(|c| !c.is_empty()) {
+            rows.push(cells.join("\t").trim_end().to_string());
+        }
+    }
+    Ok(rows)
+}
+
+/// Returns the 0-based column of a cell reference such as `C7`.
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference
+        .bytes()
+        .take_while(u8::is_ascii_uppercase)
+        .collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let column = letters
+        .iter()
+        .fold(0usize, |n, &b| n * 26 + (b - b'A' + 1) as usize);
+    Some(column - 1)
+}
+
+fn cell_value(cell: &Node, shared: &[String]) -> String {
+    let value = cell
+        .children()
+        .find(|n| n.tag_name().name() == "v")
+        .and_then(|v| v.text())
+        .unwrap_or_default();
+    match cell.attribute("t") {
+        Some("s") => value
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| shared.get(i))
+            .cloned()
+            .unwrap_or_default(),
+        Some("inlineStr") => inline_text(cell),
+        Some("b") => if value == "1" { "TRUE" } else { "FALSE" }.to_string(),
+        _ => value.to_string(),
+    }
+}
@@ -0,0 +1,71 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes `contents` to `path` crash-safely: the data goes to a temporary
+/// file next to `path` that is then renamed over it, so a failed or
+/// interrupted write never leaves a truncated file behind.
+///
+/// With `check_space`, first fails if the target file system has less free
+/// space than `contents` needs.
+pub fn write_atomic(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+    check_space: bool,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    if check_space {
+        ensure_free_space(path, contents.len() as u64)?;
+    }
+
+    let tmp = temp_path(path);
+    let written = File::create(&tmp).and_then(|mut f| {
+        f.write_all(contents)?;
+        f.sync_all()
+    });
+    match written.and_then(|()| fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+/// Returns the hidden temporary path used while writing `path`
+/// (`dir/.name.tmp`).
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Fails with `StorageFull` when the file system holding `path` has fewer
+/// than `needed` bytes available.
+#[cfg(unix)]
+fn ensure_free_space(path: &Path, needed: u64) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let stats = rustix::fs::statvfs(dir)?;
+    let available = stats.f_bavail.saturating_mul(stats.f_frsize);
+    if available < needed {
+        return Err(io::Error::new(
+            io::ErrorKind::StorageFull,
+            format!(
+                "not enough disk space: {needed} bytes needed, {available} available in '{}'",
+                dir.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Free space is only checked on Unix.
+#[cfg(not(unix))]
+fn ensure_free_space(_path: &Path, _needed: u64) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use chrono::Local;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Placeholders accepted in output file name templates.
+pub const PLACEHOLDERS: &[&str] = &["name", "index", "lang", "variant", "date"];
+
+/// File name templates for the generated summary files. A template gives the
+/// file name without extension; the extension of `output_path` is appended
+/// and the file goes to the directory of `output_path`.
+///
+/// Placeholders: `{name}`, `{index}`, `{lang}` (the code of
+/// `output_language`, e.g. `ru`), `{variant}` (empty for
+/// these outputs) and `{date}` (`YYYY-MM-DD`, local time).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputNameTemplate {
+    /// The combined summary; `{name}` is the file stem of `output_path`.
+    pub combined: String,
+    /// Each part of a split summary; `{name}` is the file stem of the
+    /// combined summary and `{index}` the 1-based part number.
+    pub part: String,
+}
+
+impl Default for OutputNameTemplate {
+    fn default() -> Self {
+        Self {
+            combined: "{name}".to_string(),
+            part: "{name}.part{index}".to_string(),
+        }
+    }
+}
+
+impl OutputNameTemplate {
+    /// Rejects unknown placeholders, path separators and part names that
+    /// would not differ between parts.
+    pub fn validate(&self) -> Result<()> {
+        for (field, template) in [("combined", &self.combined), ("part", &self.part)] {
+            if template.trim().is_empty() {
+                anyhow::bail!("output_name_template.{field} must not be empty");
+            }
+            if template.contains(['/', '\\']) {
+                anyhow::bail!(
+                    "output_name_template.{field} must be a file name, not a path: '{template}'"
+                );
+            }
+            for placeholder in placeholders(template) {
+                if !PLACEHOLDERS.contains(&placeholder) {
+                    anyhow::bail!(
+                        "output_name_template.{field}: unknown placeholder '{{{placeholder}}}' \
+                         (use {})",
+                        PLACEHOLDERS
+                            .iter()
+                            .map(|p| format!("{{{p}}}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+            }
+        }
+        if !self.part.contains("{index}") {
+            anyhow::bail!(
+                "output_name_template.part must contain {{index}} so parts get distinct names"
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the path of the combined summary configured as `output_path`,
+    /// written in the language with code `lang`.
+    pub fn combined_path(&self, output_path: &str, lang: &str) -> String {
+        apply(&self.combined, output_path, None, lang)
+    }
+
+    /// Returns the path of part `part` (1-based) of the combined summary at
+    /// `output_path`.
+    pub fn part_path(&self, output_path: &str, part: usize, lang: &str) -> String {
+        apply(&self.part, output_path, Some(part), lang)
+    }
+}
+
+/// Returns the names inside `{...}` in `template`.
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+}
+
+fn apply(template: &str, output_path: &str, index: Option<usize>, lang: &str) -> String {
+    let path = Path::new(output_path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let index = index.map(|i| i.to_string()).unwrap_or_default();
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    let mut name = template.to_string();
+    for (placeholder, value) in [
+        ("{name}", stem.as_ref()),
+        ("{index}", index.as_str()),
+        ("{lang}", lang),
+        ("{variant}", ""),
+        ("{date}", date.as_str()),
+    ] {
+        name = name.replace(placeholder, value);
+    }
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::api::{ModelPricing, Usage};
+use crate::file_processor::ProcessedFile;
+use crate::logger::Logger;
+
+/// OCR share of total wall time above which a concurrency hint is logged.
+const OCR_DOMINANCE_THRESHOLD: f64 = 0.5;
+
+/// Parallelism assumed when estimating the speedup of concurrent OCR.
+const SUGGESTED_CONCURRENCY: usize = 4;
+
+/// Wall-clock timings of each pipeline phase.
+#[derive(Default)]
+pub struct RunProfile {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl RunProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records how long `phase` took.
+    pub fn record(&mut self, phase: &'static str, elapsed: Duration) {
+        self.phases.push((phase, elapsed));
+    }
+
+    /// Returns the recorded duration of `phase`, or zero if it never ran.
+    pub fn phase(&self, phase: &str) -> Duration {
+        self.phases
+            .iter()
+            .filter(|(name, _)| *name == phase)
+            .map(|(_, d)| *d)
+            .sum()
+    }
+
+    /// Logs the per-phase breakdown and the slowest API extractions.
+    pub fn report(&self, files: &[ProcessedFile], logger: &Logger) {
+        let total: Duration = self.phases.iter().map(|(_, d)| *d).sum();
+        logger.info(&format!("Profile: total {:.1}s", total.as_secs_f64()));
+        for (name, elapsed) in &self.phases {
+            logger.info(&format!(
+                "  {name}: {:.1}s ({:.0}%)",
+                elapsed.as_secs_f64(),
+                percent_of(*elapsed, total)
+            ));
+        }
+
+        let mut timed: Vec<_> = files
+            .iter()
+            .filter_map(|f| f.ocr_time.map(|t| (f, t)))
+            .collect();
+        timed.sort_by_key(|(_, t)| std::cmp::Reverse(*t));
+        for (file, elapsed) in timed {
+            let mut line = format!("  OCR {}: {:.1}s", file.name, elapsed.as_secs_f64());
+            match &file.metadata {
+                Some(meta) if meta.cached => line.push_str(" (cached)"),
+                Some(meta) => line.push_str(&format!(
+                    " ({}, {} tokens)",
+                    meta.model, meta.usage.total_tokens
+                )),
+                None => {}
+            }
+            logger.info(&line);
+        }
+    }
+}
+
+/// Returns the lines reporting the tokens used by the run, in total and per
+/// model, with the estimated cost of the models listed in `pricing`. Empty if
+/// no request reported usage.
+pub fn usage_report(
+    usage: &HashMap<String, Usage>,
+    pricing: &HashMap<String, ModelPricing>,
+) -> Vec<String> {
+    if usage.is_empty() {
+        return Vec::new();
+    }
+    let describe = |usage: &Usage, cost: Option<f64>| {
+        let mut line = format!(
+            "{} tokens ({} prompt, {} completion)",
+            usage.total_tokens, usage.prompt_tokens, usage.completion_tokens
+        );
+        if let Some(cost) = cost {
+            line.push_str(&format!(", ~${cost:.4}"));
+        }
+        line
+    };
+    let mut models: Vec<_> = usage.iter().collect();
+    models.sort_by(|a, b| a.0.cmp(b.0));
+    let mut total = Usage::default();
+    let mut total_cost = None;
+    let mut lines = vec![String::new()];
+    for (model, usage) in models {
+        total.add(*usage);
+        let cost = pricing.get(model).map(|p| p.cost(usage));
+        if let Some(cost) = cost {
+            *total_cost.get_or_insert(0.0) += cost;
+        }
+        lines.push(format!("  {model}: {}", describe(usage, cost)));
+    }
+    lines[0] = format!("Token usage: {}", describe(&total, total_cost));
+    lines
+}
+
+/// Suggests concurrent OCR when sequential extraction dominated the run.
+///
+/// The estimate assumes files are spread evenly across workers and that the
+/// run can never finish faster than its slowest single file.
+pub fn concurrency_suggestion(
+    files: &[ProcessedFile],
+    ocr_phase: Duration,
+    total: Duration,
+    concurrency: usize,
+) -> Option<String> {
+    let times: Vec<Duration> = files.iter().filter_map(|f| f.ocr_time).collect();
+    if concurrency > 1 || times.len() < 2 {
+        return None;
+    }
+    let share = percent_of(ocr_phase, total) / 100.0;
+    if share < OCR_DOMINANCE_THRESHOLD {
+        return None;
+    }
+
+    let sequential: Duration = times.iter().sum();
+    let slowest = times.iter().max().copied().unwrap_or_default();
+    let workers = SUGGESTED_CONCURRENCY.min(times.len()) as u32;
+    let parallel = (sequential / workers).max(slowest);
+    let speedup = sequential.as_secs_f64() / parallel.as_secs_f64().max(f64::EPSILON);
+    if speedup < 1.2 {
+        return None;
+    }
+
+    Some(format!(
+        "OCR took {:.0}% of the run and processed {} file(s) sequentially; \
+         running them in parallel would speed this up (estimated {speedup:.1}x)",
+        share * 100.0,
+        times.len()
+    ))
+}
+
+fn percent_of(part: Duration, total: Duration) -> f64 {
+    if total.is_zero() {
+        0.0
+    } else {
+        part.as_secs_f64() / total.as_secs_f64() * 100.0
+    }
+}
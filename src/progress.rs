@@ -0,0 +1,125 @@
+use chrono::Local;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// A machine-readable progress event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    PhaseStarted {
+        phase: String,
+    },
+    PhaseFinished {
+        phase: String,
+        secs: f64,
+    },
+    /// `index` is 1-based; `total` counts every scanned entry.
+    FileStarted {
+        file: String,
+        index: usize,
+        total: usize,
+    },
+    FileFinished {
+        file: String,
+        ok: bool,
+        secs: f64,
+    },
+    FileSkipped {
+        file: String,
+    },
+    RunFinished {
+        ok: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    ts: String,
+    #[serde(flatten)]
+    event: &'a ProgressEvent,
+}
+
+/// Appends progress events as NDJSON (one timestamped JSON object per line)
+/// to a file that dashboards can tail. Safe to share between tasks.
+pub struct ProgressFile {
+    file: Mutex<File>,
+}
+
+impl ProgressFile {
+    /// Creates (or truncates) the file at `path`, so each run starts fresh.
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        Ok(Self {
+            file: Mutex::new(File::create(path)?),
+        })
+    }
+
+    /// Appends `event` as one line, flushed immediately for tailing readers.
+    /// Write errors are ignored: progress reporting never fails a run.
+    pub fn emit(&self, event: &ProgressEvent) {
+        let record = Record {
+            ts: Local::now().to_rfc3339(),
+            event,
+        };
+        let Ok(line) = serde_json::to_string(&record) else {
+            return;
+        };
+        if let Ok(mut f) = self.file.lock() {
+            let _ = writeln!(f, "{line}");
+            let _ = f.flush();
+        }
+    }
+}
+
+/// Terminal progress bar of the extraction phase ("12/47 files" and the file
+/// last started), driven by the same events as [`ProgressFile`].
+#[derive(Default)]
+pub struct ProgressDisplay {
+    bar: Mutex<Option<ProgressBar>>,
+}
+
+impl ProgressDisplay {
+    /// Advances the bar for `event`: it appears with the first file and is
+    /// cleared when the phase or run ends.
+    pub fn update(&self, event: &ProgressEvent) {
+        let Ok(mut bar) = self.bar.lock() else {
+            return;
+        };
+        match event {
+            ProgressEvent::FileStarted { file, total, .. } => {
+                let bar = bar.get_or_insert_with(|| {
+                    let bar = ProgressBar::new(*total as u64);
+                    if let Ok(style) =
+                        ProgressStyle::with_template("{bar:30} {pos}/{len} files {wide_msg}")
+                    {
+                        bar.set_style(style);
+                    }
+                    bar
+                });
+                bar.set_message(file.clone());
+            }
+            ProgressEvent::FileFinished { .. } | ProgressEvent::FileSkipped { .. } => {
+                if let Some(bar) = bar.as_ref() {
+                    bar.inc(1);
+                }
+            }
+            ProgressEvent::PhaseFinished { .. } | ProgressEvent::RunFinished { .. } => {
+                if let Some(bar) = bar.take() {
+                    bar.finish_and_clear();
+                }
+            }
+            ProgressEvent::PhaseStarted { .. } => {}
+        }
+    }
+
+    /// Runs `print` with the bar hidden, so that console lines written
+    /// meanwhile do not garble it.
+    pub fn suspend(&self, print: impl FnOnce()) {
+        match self.bar.lock().as_deref() {
+            Ok(Some(bar)) => bar.suspend(print),
+            _ => print(),
+        }
+    }
+}
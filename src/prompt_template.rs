@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Placeholders of `ocr_prompt_template`.
+pub const OCR_PLACEHOLDERS: &[&str] = &["filename", "language"];
+/// Placeholders of `summary_prompt_template`.
+pub const SUMMARY_PLACEHOLDERS: &[&str] = &[
+    "total_words",
+    "target_words",
+    "compress_pct",
+    "file_count",
+    "language",
+];
+/// Placeholders `summary_prompt_template` must use: without the target the
+/// summary length is left to the model.
+pub const SUMMARY_REQUIRED: &[&str] = &["target_words"];
+
+/// A user-supplied prompt replacing a built-in one, with `{name}`
+/// placeholders filled in at request time.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    text: String,
+}
+
+impl PromptTemplate {
+    /// Loads the template configured as `field`: the contents of the file
+    /// named by `value` if there is one, otherwise `value` itself. Fails if
+    /// the template uses a placeholder outside `known` or lacks one of
+    /// `required`.
+    pub fn load(field: &str, value: &str, known: &[&str], required: &[&str]) -> Result<Self> {
+        let path = Path::new(value);
+        let text = if !value.contains('\n') && path.is_file() {
+            fs::read_to_string(path)
+                .with_context(|| format!("{field}: failed to read '{value}'"))?
+        } else {
+            value.to_string()
+        };
+        if text.trim().is_empty() {
+            anyhow::bail!("{field} is empty");
+        }
+        let used = placeholders(&text);
+        if let Some(unknown) = used.iter().find(|name| !known.contains(name)) {
+            anyhow::bail!(
+                "{field}: unknown placeholder {{{unknown}}}; available: {}",
+                known
+                    .iter()
+                    .map(|k| format!("{{{k}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        if let Some(missing) = required.iter().find(|name| !used.contains(name)) {
+            anyhow::bail!("{field} must contain the placeholder {{{missing}}}");
+        }
+        Ok(Self { text })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the template with each `{name}` of `values` replaced.
+    pub fn render(&self, values: &[(&str, String)]) -> String {
+        values
+            .iter()
+            .fold(self.text.clone(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+}
+
+/// Returns the names inside `{...}` that look like placeholders: a lowercase
+/// identifier, so JSON examples and other braces in the prompt pass through.
+fn placeholders(text: &str) -> Vec<&str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .filter(|name| {
+            !name.is_empty()
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+use std::borrow::Cow;
+
+/// Decodes `bytes` as UTF-8, replacing every invalid sequence with U+FFFD.
+/// Returns the text and the number of sequences replaced.
+pub fn repair_utf8(bytes: &[u8]) -> (Cow<'_, str>, usize) {
+    let repairs = bytes
+        .utf8_chunks()
+        .filter(|chunk| !chunk.invalid().is_empty())
+        .count();
+    (String::from_utf8_lossy(bytes), repairs)
+}
+
+/// Replaces `\uXXXX` escapes of lone UTF-16 surrogates in JSON text with
+/// `\uFFFD`, so that a response containing them still parses. Escaped
+/// backslashes and valid surrogate pairs are left alone. Returns the text and
+/// the number of escapes replaced.
+pub fn repair_json_escapes(json: &str) -> (Cow<'_, str>, usize) {
+    if !json.contains("\\u") {
+        return (Cow::Borrowed(json), 0);
+    }
+    let bytes = json.as_bytes();
+    let mut out = String::with_capacity(json.len());
+    let mut repairs = 0;
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            i += 1;
+            continue;
+        }
+        let Some(unit) = escaped_unit(bytes, i) else {
+            // Any other escape, including `\\`, is two bytes long.
+            i += 2;
+            continue;
+        };
+        let is_pair = (0xD800..0xDC00).contains(&unit)
+            && escaped_unit(bytes, i + 6).is_some_and(|low| (0xDC00..0xE000).contains(&low));
+        if is_pair {
+            i += 12;
+        } else if (0xD800..0xE000).contains(&unit) {
+            out.push_str(&json[copied..i]);
+            out.push_str("\\uFFFD");
+            repairs += 1;
+            i += 6;
+            copied = i;
+        } else {
+            i += 6;
+        }
+    }
+    if repairs == 0 {
+        return (Cow::Borrowed(json), 0);
+    }
+    out.push_str(&json[copied..]);
+    (Cow::Owned(out), repairs)
+}
+
+/// Returns the code unit of the `\uXXXX` escape starting at `at`, if any.
+fn escaped_unit(bytes: &[u8], at: usize) -> Option<u16> {
+    let escape = bytes.get(at..at + 6)?;
+    if &escape[..2] != b"\\u" {
+        return None;
+    }
+    u16::from_str_radix(std::str::from_utf8(&escape[2..]).ok()?, 16).ok()
+}
@@ -0,0 +1,243 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// Where the OpenRouter API key comes from (`api_key_source`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ApiKeySource {
+    /// An environment variable, which may be set from `.env`.
+    Env {
+        #[serde(default = "default_env_var")]
+        var: String,
+    },
+    /// A HashiCorp Vault secret, read with the token in `VAULT_TOKEN`.
+    /// Needs the `vault` feature.
+    Vault {
+        /// Vault server URL (defaults to `VAULT_ADDR`).
+        #[serde(default)]
+        address: Option<String>,
+        /// API path of the secret below `/v1/`, e.g. `secret/data/files`
+        /// for a KV version 2 engine mounted at `secret`.
+        path: String,
+        /// Field of the secret holding the key.
+        #[serde(default = "default_vault_field")]
+        field: String,
+    },
+}
+
+impl Default for ApiKeySource {
+    fn default() -> Self {
+        Self::Env {
+            var: default_env_var(),
+        }
+    }
+}
+
+const VAULT_FEATURE_MISSING: &str =
+    "api_key_source: type 'vault' needs a build with the `vault` feature \
+     (cargo build --features vault)";
+
+fn default_env_var() -> String {
+    "OPENROUTER_API_KEY".to_string()
+}
+
+fn default_vault_field() -> String {
+    "api_key".to_string()
+}
+
+impl ApiKeySource {
+    /// Fails if the source needs a feature this build lacks.
+    pub fn validate(&self) -> Result<()> {
+        if matches!(self, Self::Vault { .. }) && !cfg!(feature = "vault") {
+            anyhow::bail!(VAULT_FEATURE_MISSING);
+        }
+        Ok(())
+    }
+
+    /// Returns the [`SecretSource`] that reads the key, falling back to the
+    /// contents of `key_file` (`api_key_file`) if given.
+    pub fn secret_source_with_file(&self, key_file: Option<&str>) -> Result<Box<dyn SecretSource>> {
+        let source = self.secret_source()?;
+        Ok(match key_file {
+            Some(path) => Box::new(SecretChain::new(vec![
+                source,
+                Box::new(FileSecret {
+                    path: path.to_string(),
+                }),
+            ])),
+            None => source,
+        })
+    }
+
+    /// Returns the [`SecretSource`] that reads the key.
+    pub fn secret_source(&self) -> Result<Box<dyn SecretSource>> {
+        match self {
+            Self::Env { var } => Ok(Box::new(EnvSecret { var: var.clone() })),
+            #[cfg(feature = "vault")]
+            Self::Vault {
+                address,
+                path,
+                field,
+            } => Ok(Box::new(VaultSecret::new(
+                address.clone(),
+                path.clone(),
+                field.clone(),
+            )?)),
+            #[cfg(not(feature = "vault"))]
+            Self::Vault { .. } => anyhow::bail!(VAULT_FEATURE_MISSING),
+        }
+    }
+}
+
+/// A place a secret can be read from.
+#[async_trait]
+pub trait SecretSource: Send + Sync {
+    /// Describes the source for error messages, without the secret itself.
+    fn describe(&self) -> String;
+
+    /// Reads the secret.
+    async fn fetch(&self) -> Result<String>;
+}
+
+/// A secret held in an environment variable.
+pub struct EnvSecret {
+    pub var: String,
+}
+
+#[async_trait]
+impl SecretSource for EnvSecret {
+    fn describe(&self) -> String {
+        format!("environment variable {}", self.var)
+    }
+
+    async fn fetch(&self) -> Result<String> {
+        std::env::var(&self.var).with_context(|| format!("{} not set in .env", self.var))
+    }
+}
+
+/// A secret stored in a file, without surrounding whitespace.
+pub struct FileSecret {
+    pub path: String,
+}
+
+#[async_trait]
+impl SecretSource for FileSecret {
+    fn describe(&self) -> String {
+        format!("file '{}'", self.path)
+    }
+
+    async fn fetch(&self) -> Result<String> {
+        let secret = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("Failed to read '{}'", self.path))?;
+        Ok(secret.trim().to_string())
+    }
+}
+
+/// Sources tried in order until one yields a non-empty secret.
+pub struct SecretChain {
+    sources: Vec<Box<dyn SecretSource>>,
+}
+
+impl SecretChain {
+    pub fn new(sources: Vec<Box<dyn SecretSource>>) -> Self {
+        Self { sources }
+    }
+}
+
+#[async_trait]
+impl SecretSource for SecretChain {
+    fn describe(&self) -> String {
+        let names: Vec<String> = self.sources.iter().map(|s| s.describe()).collect();
+        match names.split_last() {
+            Some((last, rest)) if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+            _ => names.concat(),
+        }
+    }
+
+    async fn fetch(&self) -> Result<String> {
+        let mut failures = Vec::new();
+        for source in &self.sources {
+            match source.fetch().await {
+                Ok(secret) if !secret.is_empty() => return Ok(secret),
+                Ok(_) => failures.push(format!("{}: empty", source.describe())),
+                Err(e) => failures.push(format!("{}: {e:#}", source.describe())),
+            }
+        }
+        anyhow::bail!("looked in {}", failures.join("; "))
+    }
+}
+
+/// A field of a HashiCorp Vault secret (KV version 1 or 2).
+#[cfg(feature = "vault")]
+pub struct VaultSecret {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+    path: String,
+    field: String,
+}
+
+#[cfg(feature = "vault")]
+impl VaultSecret {
+    /// Reads the token from `VAULT_TOKEN` and, without `address`, the server
+    /// from `VAULT_ADDR`.
+    pub fn new(address: Option<String>, path: String, field: String) -> Result<Self> {
+        let address = match address {
+            Some(address) => address,
+            None => std::env::var("VAULT_ADDR")
+                .context("api_key_source: set address or VAULT_ADDR for the Vault server")?,
+        };
+        let token = std::env::var("VAULT_TOKEN").context("VAULT_TOKEN not set")?;
+        let url = format!(
+            "{}/v1/{}",
+            address.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url,
+            token,
+            path,
+            field,
+        })
+    }
+}
+
+#[cfg(feature = "vault")]
+#[async_trait]
+impl SecretSource for VaultSecret {
+    fn describe(&self) -> String {
+        format!("Vault secret '{}' (field '{}')", self.path, self.field)
+    }
+
+    async fn fetch(&self) -> Result<String> {
+        let response = self
+            .client
+            .get(&self.url)
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach Vault for {}", self.describe()))?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Vault returned {status} for {}", self.describe());
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse the Vault response")?;
+        // KV version 2 nests the secret one level deeper than version 1.
+        let data = &body["data"];
+        let data = if data["data"].is_object() {
+            &data["data"]
+        } else {
+            data
+        };
+        data[&self.field]
+            .as_str()
+            .map(String::from)
+            .with_context(|| format!("No string field in {}", self.describe()))
+    }
+}
@@ -0,0 +1,104 @@
+/// Heading keywords (lowercase) identifying the overview section.
+const OVERVIEW_KEYWORDS: &[&str] = &["обзор", "overview", "введение", "introduction"];
+
+/// Heading keywords (lowercase) identifying the conclusion section.
+const CONCLUSION_KEYWORDS: &[&str] = &["заключение", "conclusion", "вывод", "итог"];
+
+/// A `##`-level section of a summary: its heading text and Markdown body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummarySection {
+    pub heading: String,
+    pub body: String,
+}
+
+/// A generated summary split into its logical parts.
+///
+/// Every part is optional because models do not always follow the requested
+/// structure; sections that match neither the overview nor the conclusion are
+/// treated as per-file sections.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SummarySections {
+    /// Text of the top-level `#` heading.
+    pub title: Option<String>,
+    pub overview: Option<SummarySection>,
+    pub files: Vec<SummarySection>,
+    pub conclusion: Option<SummarySection>,
+}
+
+/// Splits a Markdown summary on its headings into [`SummarySections`].
+///
+/// A wrapping ```` ```markdown ```` fence is ignored. Text between the title and
+/// the first `##` heading becomes the overview when no explicit overview
+/// section exists.
+pub fn parse_summary(md: &str) -> SummarySections {
+    let mut sections = SummarySections::default();
+    let mut preamble = String::new();
+    let mut current: Option<SummarySection> = None;
+
+    for line in strip_code_fence(md).lines() {
+        if let Some(title) = line.strip_prefix("# ") {
+            if sections.title.is_none() && current.is_none() {
+                sections.title = Some(title.trim().to_string());
+                continue;
+            }
+        }
+        if let Some(heading) = line.strip_prefix("## ") {
+            if let Some(done) = current.take() {
+                sections.push(done);
+            }
+            current = Some(SummarySection {
+                heading: heading.trim().to_string(),
+                body: String::new(),
+            });
+            continue;
+        }
+        let body = match current.as_mut() {
+            Some(section) => &mut section.body,
+            None => &mut preamble,
+        };
+        body.push_str(line);
+        body.push('\n');
+    }
+    if let Some(done) = current {
+        sections.push(done);
+    }
+
+    let preamble = preamble.trim();
+    if sections.overview.is_none() && !preamble.is_empty() {
+        sections.overview = Some(SummarySection {
+            heading: String::new(),
+            body: preamble.to_string(),
+        });
+    }
+    sections
+}
+
+impl SummarySections {
+    fn push(&mut self, mut section: SummarySection) {
+        section.body = section.body.trim().to_string();
+        let heading = section.heading.to_lowercase();
+        let matches = |keywords: &[&str]| keywords.iter().any(|k| heading.contains(k));
+
+        if self.overview.is_none() && matches(OVERVIEW_KEYWORDS) {
+            self.overview = Some(section);
+        } else if matches(CONCLUSION_KEYWORDS) {
+            self.conclusion = Some(section);
+        } else {
+            self.files.push(section);
+        }
+    }
+}
+
+/// Removes a ```` ```markdown ```` / ```` ``` ```` fence wrapping the whole document.
+fn strip_code_fence(md: &str) -> &str {
+    let trimmed = md.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return md;
+    };
+    let Some(body_start) = rest.find('\n') else {
+        return md;
+    };
+    rest[body_start + 1..]
+        .strip_suffix("```")
+        .unwrap_or(&rest[body_start + 1..])
+}
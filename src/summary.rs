@@ -1,28 +1,62 @@
 use anyhow::{Context, Result};
-use serde_json::json;
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write;
 use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
 
-use crate::api::OpenRouterClient;
-use crate::file_processor::ProcessedFile;
+use crate::api::{ChatApi, ChatParams, ChatResponse, Usage};
+use crate::chunking;
+use crate::config::{Config, OutputFormat, SectionBudgets};
+use crate::file_processor::{self, ExtractionMetadata, ProcessedFile};
+use crate::key_points;
+use crate::keywords;
 use crate::logger::Logger;
+use crate::manifest;
+use crate::markdown;
+use crate::messages_template;
+use crate::output;
+use crate::sections::parse_summary;
+
+/// Header of the file tree optionally prepended to the combined text.
+const TREE_HEADER: &str = "=== Структура файлов ===";
 
 /// Combines file contents into a single document and computes word statistics.
 pub struct SummaryInput {
     pub combined_text: String,
     pub total_words: usize,
+    /// Word count with each file scaled by its compression weight; the basis
+    /// of [`SummaryInput::target_words`].
+    pub weighted_words: f64,
     pub file_count: usize,
 }
 
 impl SummaryInput {
     /// Builds the combined text from processed files.
     pub fn from_files(files: &[ProcessedFile]) -> Self {
+        Self::from_files_weighted(files, &HashMap::new())
+    }
+
+    /// Like [`SummaryInput::from_files`], but counts each file's words
+    /// `weights[ext]` times (1 for unlisted extensions) toward the target.
+    pub fn from_files_weighted(files: &[ProcessedFile], weights: &HashMap<String, f64>) -> Self {
         let estimated_len: usize = files.iter().map(|f| f.name.len() + f.content.len() + 20).sum();
         let mut combined = String::with_capacity(estimated_len);
         let mut total_words: usize = 0;
 
+        let mut weighted_words = 0.0;
+
         for file in files {
-            total_words += file.content.split_whitespace().count();
+            let words = file.content.split_whitespace().count();
+            let ext = file
+                .path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            total_words += words;
+            weighted_words += words as f64 * weights.get(&ext).copied().unwrap_or(1.0);
             // Using `write!` avoids extra allocations compared to `format!` + `push_str`.
             let _ = write!(combined, "=== File: {} ===\n{}\n\n", file.name, file.content);
         }
@@ -30,69 +64,1288 @@ impl SummaryInput {
         Self {
             combined_text: combined,
             total_words,
+            weighted_words,
             file_count: files.len(),
         }
     }
 
+    /// Builds the input of a single file's summary from one of the
+    /// [`SummaryInput::file_sections`], weighting its words by its extension.
+    pub fn from_section(name: &str, content: &str, weights: &HashMap<String, f64>) -> Self {
+        let total_words = content.split_whitespace().count();
+        let ext = Path::new(name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        Self {
+            combined_text: format!("=== File: {name} ===\n{content}\n\n"),
+            total_words,
+            weighted_words: total_words as f64 * weights.get(&ext).copied().unwrap_or(1.0),
+            file_count: 1,
+        }
+    }
+
+    /// Prepends an ASCII tree of the files' relative paths, so the model sees
+    /// how they are organized. The tree is not counted as source words.
+    pub fn prepend_tree(&mut self, files: &[ProcessedFile], root: &str) {
+        let tree = file_tree(files.iter().map(|f| f.path.as_path()), root);
+        self.combined_text = format!("{TREE_HEADER}\n{tree}\n{}", self.combined_text);
+    }
+
+    /// Returns the combined text without the file tree, if one was prepended.
+    pub fn source_text(&self) -> &str {
+        if !self.combined_text.starts_with(TREE_HEADER) {
+            return &self.combined_text;
+        }
+        match self.combined_text.find("\n=== File: ") {
+            Some(start) => &self.combined_text[start + 1..],
+            None => "",
+        }
+    }
+
+    /// Splits the combined text into `(file name, content)` pairs along its
+    /// `=== File: ... ===` headers.
+    pub fn file_sections(&self) -> Vec<(&str, &str)> {
+        let text = self.source_text();
+        let mut sections = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("=== File: ") {
+            let header = &rest[start..];
+            let Some(line_end) = header.find('\n') else {
+                break;
+            };
+            let name = header[..line_end]
+                .trim_start_matches("=== File: ")
+                .trim_end_matches(" ===");
+            let body = &header[line_end + 1..];
+            let end = body.find("\n=== File: ").map_or(body.len(), |i| i + 1);
+            sections.push((name, body[..end].trim()));
+            rest = &body[end..];
+        }
+        sections
+    }
+
+    /// Loads an existing combined text file (e.g. one edited by hand) as-is.
+    ///
+    /// The file count is taken from the `=== File: ... ===` headers (which are
+    /// excluded from the word count), falling back to 1 for free-form text.
+    pub fn from_combined_file(path: &str) -> Result<Self> {
+        let combined_text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read combined input: {path}"))?;
+        let is_header = |l: &str| l.starts_with("=== File: ") && l.ends_with(" ===");
+        let total_words = combined_text
+            .lines()
+            .filter(|l| !is_header(l))
+            .map(|l| l.split_whitespace().count())
+            .sum();
+        let file_count = combined_text
+            .lines()
+            .filter(|l| is_header(l))
+            .count()
+            .max(1);
+
+        Ok(Self {
+            combined_text,
+            total_words,
+            weighted_words: total_words as f64,
+            file_count,
+        })
+    }
+
+    /// Writes the combined text to `path` for inspection.
+    pub fn dump(&self, path: &str) -> Result<()> {
+        output::write_atomic(path, &self.combined_text, false)
+            .with_context(|| format!("Failed to write combined input to '{path}'"))
+    }
+
     /// Calculates the target word count based on the compression percent.
     pub fn target_words(&self, compress_pct: u8) -> usize {
-        let raw = (self.total_words as f64 * compress_pct as f64 / 100.0).ceil() as usize;
+        let raw = (self.weighted_words * compress_pct as f64 / 100.0).ceil() as usize;
         raw.max(50)
     }
 }
 
-/// Generates a summary via OpenRouter and writes it to `output_path`.
+/// Renders `paths` as an ASCII tree below `root`, directories first.
+fn file_tree<'a>(paths: impl Iterator<Item = &'a Path>, root: &str) -> String {
+    #[derive(Default)]
+    struct Node(BTreeMap<String, Node>);
+
+    fn render(node: &Node, prefix: &str, out: &mut String) {
+        let mut children: Vec<_> = node.0.iter().collect();
+        children.sort_by_key(|(name, child)| (child.0.is_empty(), name.as_str()));
+        for (i, (name, child)) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let branch = if last { "└── " } else { "├── " };
+            let slash = if child.0.is_empty() { "" } else { "/" };
+            let _ = writeln!(out, "{prefix}{branch}{name}{slash}");
+            let indent = if last { "    " } else { "│   " };
+            render(child, &format!("{prefix}{indent}"), out);
+        }
+    }
+
+    let mut tree = Node::default();
+    for path in paths {
+        let mut node = &mut tree;
+        for part in path.iter() {
+            node = node
+                .0
+                .entry(part.to_string_lossy().into_owned())
+                .or_default();
+        }
+    }
+
+    let mut out = format!("{}/\n", root.trim_end_matches('/'));
+    render(&tree, "", &mut out);
+    out
+}
+
+/// Returns the path of part `part` (1-based) of a split summary, named by
+/// `output_name_template.part`: by default `summary.md` becomes
+/// `summary.part1.md`.
+pub fn part_output_path(config: &Config, part: usize) -> String {
+    config
+        .output_name_template
+        .part_path(&config.output_path, part, &config.language().code())
+}
+
+/// Returns where extracted text is saved when no summary could be produced.
+pub fn partial_output_path(output_path: &str) -> String {
+    format!("{output_path}.partial")
+}
+
+/// Rough token count of `text`, erring high: about three characters per
+/// token, which also holds for Cyrillic text.
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(3)
+}
+
+/// Warns when the summary input likely exceeds the summary model's context
+/// window, in which case the request would be rejected or truncated. Input
+/// that will be summarized in chunks is not checked.
+fn warn_if_over_context(config: &Config, input: &SummaryInput, logger: &Logger) {
+    let chars = input.combined_text.chars().count();
+    if config.input_char_limit().is_some_and(|limit| chars > limit) {
+        return;
+    }
+    let limit = config.context_limit_for(&config.summary_model);
+    let tokens = estimate_tokens(&input.combined_text);
+    if tokens > limit {
+        logger.warn(&format!(
+            "Summary input is ~{tokens} tokens, more than the {limit}-token context of '{}'; \
+             the request may fail (set max_input_tokens to summarize it in chunks, or \
+             model_context_limits if the limit is wrong)",
+            config.summary_model
+        ));
+    }
+}
+
+/// Placeholder written instead of a summary when no text was extracted.
+const NO_CONTENT_PLACEHOLDER: &str = "# Нет содержимого\n\n\
+    Ни из одного файла не удалось извлечь текст, поэтому резюме не составлялось.\n";
+
+/// Writes the "no content extracted" placeholder to the configured output
+/// (an empty CSV with only the header for `output_format: csv`) without
+/// calling the model.
+pub fn save_empty_placeholder(config: &Config, logger: &Logger) -> Result<()> {
+    if config.output_format == OutputFormat::Csv {
+        let csv_path = key_points::csv_output_path(&config.output_path);
+        output::write_atomic(&csv_path, key_points::to_csv(&[]), config.check_disk_space)
+            .with_context(|| format!("Failed to write key points to '{csv_path}'"))?;
+        logger.info(&format!("Empty key point table written to '{csv_path}'"));
+        return Ok(());
+    }
+    save_summary(config, NO_CONTENT_PLACEHOLDER, logger)
+}
+
+/// Generates a summary via OpenRouter and writes it to `config.output_path`.
+///
+/// With `output_format: csv` the per-file key points are written as CSV
+/// instead; if the model's reply cannot be parsed, a Markdown summary is
+/// generated as a fallback.
 pub async fn generate_and_save(
-    api: &OpenRouterClient,
-    model: &str,
+    api: &dyn ChatApi,
+    config: &Config,
     input: &SummaryInput,
-    compress_pct: u8,
-    output_path: &str,
     logger: &Logger,
 ) -> Result<()> {
+    warn_if_over_context(config, input, logger);
+    if config.output_format == OutputFormat::Csv {
+        match generate_key_points_csv(api, config, input, logger).await {
+            Ok(()) => return Ok(()),
+            Err(e) => logger.warn(&format!(
+                "Could not produce CSV key points ({e:#}); writing a Markdown summary instead"
+            )),
+        }
+    }
+
+    let compress_pct = config.compress_percent();
     let target_words = input.target_words(compress_pct);
 
     logger.info(&format!(
         "Found {} file(s). Total words: {}. Target: ~{} words ({}%).",
         input.file_count, input.total_words, target_words, compress_pct
     ));
+    if config.sectioned {
+        return generate_sectioned_and_save(api, config, input, target_words, logger).await;
+    }
+    let chunk_summaries;
+    let prompt_input = match config.input_char_limit() {
+        Some(limit) if input.combined_text.chars().count() > limit => {
+            chunk_summaries =
+                summarize_chunks(api, config, input, limit, target_words, logger).await?;
+            &chunk_summaries
+        }
+        _ => input,
+    };
     logger.info("Sending to OpenRouter for summary...");
 
-    let system_prompt = format!(
+    let messages = match &config.messages_template_path {
+        Some(path) => messages_template::render(
+            &messages_template::load(path)?,
+            &prompt_input.combined_text,
+            prompt_input.total_words,
+            target_words,
+        ),
+        None => build_summary_messages(prompt_input, target_words, compress_pct, config),
+    };
+
+    let mut response = complete_summary(api, config, messages.clone(), logger)
+        .await
+        .context("Failed to get summary from OpenRouter")?;
+    response = correct_word_count(
+        api,
+        config,
+        messages.clone(),
+        response,
+        target_words,
+        logger,
+    )
+    .await;
+    if let Some(budgets) = &config.section_budgets {
+        let budget = SectionWordBudget::new(budgets, target_words, input.file_count);
+        response = rebalance_sections(api, config, messages, response, &budget, logger).await?;
+    }
+
+    save_summary(config, &finish_summary(config, input, response), logger)
+}
+
+/// Returns the directory of the `per_file_summaries` output: `summaries`
+/// next to the combined summary at `output_path`.
+pub fn per_file_summaries_dir(output_path: &str) -> PathBuf {
+    Path::new(output_path)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("summaries")
+}
+
+/// Returns `name` usable as a single file name: path separators and other
+/// characters that are unsafe on common file systems become `_`.
+fn safe_file_name(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match safe.trim_start_matches('.') {
+        "" => "_".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Returns a file name for each of `names` with `extension`, made safe by
+/// [`safe_file_name`]. Names that become equal (`a/b.txt` and `a_b.txt`)
+/// get a numeric suffix, ` (2)`, ` (3)` and so on, in the order given.
+fn unique_file_names(names: &[&str], extension: &str) -> Vec<String> {
+    let mut used = HashSet::new();
+    names
+        .iter()
+        .map(|name| {
+            let safe = safe_file_name(name);
+            let mut file_name = format!("{safe}.{extension}");
+            let mut occurrence = 1;
+            while !used.insert(file_name.clone()) {
+                occurrence += 1;
+                file_name = format!("{safe} ({occurrence}).{extension}");
+            }
+            file_name
+        })
+        .collect()
+}
+
+/// Summarizes every file of `input` on its own (`per_file_summaries`) and
+/// writes each summary to [`per_file_summaries_dir`]. Each file's target is
+/// `compress_percent` of its own words; up to `max_concurrency` requests run
+/// at once. A failed file is logged and skipped.
+pub async fn save_per_file_summaries(
+    api: &dyn ChatApi,
+    config: &Config,
+    input: &SummaryInput,
+    logger: &Logger,
+) -> Result<()> {
+    let dir = per_file_summaries_dir(&config.output_path);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+    let compress_pct = config.compress_percent();
+    let sections: Vec<(&str, &str)> = input
+        .file_sections()
+        .into_iter()
+        .filter(|(_, content)| !content.is_empty())
+        .collect();
+    logger.info(&format!(
+        "Summarizing {} file(s) separately (per_file_summaries)...",
+        sections.len()
+    ));
+
+    // Concurrent replies would interleave on stdout, so none is streamed.
+    let params = ChatParams {
+        stream: false,
+        ..summary_params(config)
+    };
+    let params = &params;
+    let names: Vec<&str> = sections.iter().map(|&(name, _)| name).collect();
+    let file_names = unique_file_names(&names, config.output_format.extension());
+    let results: Vec<(&str, String, Result<ChatResponse>)> =
+        stream::iter(sections.into_iter().zip(file_names))
+            .map(|((name, content), file_name)| async move {
+                let file_input =
+                    SummaryInput::from_section(name, content, &config.compression_weights);
+                let target_words = file_input.target_words(compress_pct);
+                let messages =
+                    build_summary_messages(&file_input, target_words, compress_pct, config);
+                let response = complete_summary_with(api, config, messages, params, logger).await;
+                (name, file_name, response)
+            })
+            .buffer_unordered(config.max_concurrency)
+            .collect()
+            .await;
+
+    let total = results.len();
+    let mut written = 0;
+    for (name, file_name, response) in results {
+        match response {
+            Ok(response) => {
+                let path = dir.join(file_name);
+                let summary = render_output(config, &response.content);
+                output::write_atomic(&path, summary, config.check_disk_space)
+                    .with_context(|| format!("Failed to write summary to '{}'", path.display()))?;
+                written += 1;
+            }
+            Err(e) => logger.warn(&format!("Could not summarize '{name}' on its own: {e:#}")),
+        }
+    }
+    logger.info(&format!(
+        "Per-file summaries written to '{}' ({written} of {total})",
+        dir.display()
+    ));
+    Ok(())
+}
+
+/// Summarizes the combined text chunk by chunk, each at most `max_chars`
+/// long and repeating the end of the previous one (`chunk_overlap`), and
+/// returns the chunk summaries as the input of the final summary. Each chunk
+/// gets a share of `target_words` proportional to its words. Word counts stay
+/// those of the original text, so the overlap is never counted twice.
+async fn summarize_chunks(
+    api: &dyn ChatApi,
+    config: &Config,
+    input: &SummaryInput,
+    max_chars: usize,
+    target_words: usize,
+    logger: &Logger,
+) -> Result<SummaryInput> {
+    let chunks = chunking::split(&input.combined_text, max_chars, config.chunk_overlap);
+    logger.info(&format!(
+        "Input exceeds the {max_chars}-character input limit (~{} tokens); \
+         summarizing it in {} chunks first",
+        estimate_tokens(&input.combined_text),
+        chunks.len()
+    ));
+
+    let mut combined_text = String::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let number = index + 1;
+        let share = target_words * chunk.words() / input.total_words.max(1);
+        let chunk_target = (share * CHUNK_TARGET_FACTOR).max(50);
+        let mut system_prompt = format!(
+            "Ты — эксперт по составлению резюме. Пользователь предоставит часть {number} \
+             из {total} большого документа, собранного из нескольких файлов. \
+             Составь подробное промежуточное резюме ЭТОЙ части в формате Markdown \
+             объёмом около {chunk_target} слов: сохрани имена файлов из заголовков \
+             `=== File: ... ===`, факты, числа и выводы. Текст между пометками \
+             [КОНТЕКСТ] и [/КОНТЕКСТ] повторяет конец предыдущей части — используй его \
+             только для понимания, не пересказывай.\n\n",
+            total = chunks.len(),
+        );
+        system_prompt.push_str(&prompt_closing(config));
+        let user = if chunk.overlap.is_empty() {
+            chunk.body.clone()
+        } else {
+            format!(
+                "[КОНТЕКСТ]\n{}\n[/КОНТЕКСТ]\n\n{}",
+                chunk.overlap, chunk.body
+            )
+        };
+        let messages = vec![
+            json!({"role": "system", "content": system_prompt}),
+            json!({"role": "user", "content": user}),
+        ];
+        logger.info(&format!("  Summarizing chunk {number}/{}...", chunks.len()));
+        let response = complete_summary(api, config, messages, logger)
+            .await
+            .with_context(|| format!("Failed to summarize chunk {number}"))?;
+        let _ = write!(
+            combined_text,
+            "=== Часть {number} из {} ===\n{}\n\n",
+            chunks.len(),
+            response.content.trim()
+        );
+    }
+
+    Ok(SummaryInput {
+        combined_text,
+        total_words: input.total_words,
+        weighted_words: input.weighted_words,
+        file_count: input.file_count,
+    })
+}
+
+/// Chunk summaries get this many times their share of the final target, so
+/// the final pass has material to condense.
+const CHUNK_TARGET_FACTOR: usize = 2;
+
+/// Asks the model for per-file key points and writes them as CSV next to
+/// `config.output_path`.
+async fn generate_key_points_csv(
+    api: &dyn ChatApi,
+    config: &Config,
+    input: &SummaryInput,
+    logger: &Logger,
+) -> Result<()> {
+    logger.info(&format!(
+        "Found {} file(s). Requesting key points as CSV...",
+        input.file_count
+    ));
+    let messages = key_points::build_key_point_messages(input, &config.language());
+    let reply = complete_summary(api, config, messages, logger)
+        .await
+        .context("Failed to get key points from OpenRouter")?;
+    let points = key_points::parse_key_points(&reply.content)?;
+
+    let csv_path = key_points::csv_output_path(&config.output_path);
+    output::write_atomic(
+        &csv_path,
+        key_points::to_csv(&points),
+        config.check_disk_space,
+    )
+    .with_context(|| format!("Failed to write key points to '{csv_path}'"))?;
+    logger.info(&format!(
+        "{} key point(s) successfully written to '{csv_path}'",
+        points.len()
+    ));
+    Ok(())
+}
+
+/// Revises the summary previously written to `config.output_path` according to
+/// `feedback`, keeping the previous version next to it with a `.prev` suffix.
+pub async fn revise_and_save(
+    api: &dyn ChatApi,
+    config: &Config,
+    input: &SummaryInput,
+    feedback: &str,
+    logger: &Logger,
+) -> Result<()> {
+    warn_if_over_context(config, input, logger);
+    let output_path = config.output_path.as_str();
+    let previous = fs::read_to_string(output_path)
+        .with_context(|| format!("Failed to read previous summary '{output_path}'"))?;
+
+    let compress_pct = config.compress_percent();
+    let target_words = input.target_words(compress_pct);
+    logger.info(&format!(
+        "Revising '{output_path}' with feedback (target: ~{target_words} words)..."
+    ));
+
+    let mut messages = build_summary_messages(input, target_words, compress_pct, config);
+    messages.push(json!({"role": "assistant", "content": previous}));
+    messages.push(json!({"role": "user", "content": format!(
+        "Исправь это резюме с учётом следующих замечаний. \
+         Сохрани формат и требования к объёму, выведи ТОЛЬКО исправленное Markdown-резюме.\n\n\
+         Замечания:\n{feedback}"
+    )}));
+
+    let response = complete_summary(api, config, messages, logger)
+        .await
+        .context("Failed to get revised summary from OpenRouter")?;
+
+    let backup_path = format!("{output_path}.prev");
+    output::write_atomic(&backup_path, &previous, config.check_disk_space)
+        .with_context(|| format!("Failed to back up previous summary to '{backup_path}'"))?;
+    logger.info(&format!("Previous summary kept as '{backup_path}'"));
+
+    save_summary(config, &finish_summary(config, input, response), logger)
+}
+
+/// Returns the generation parameters of summary requests.
+fn summary_params(config: &Config) -> ChatParams {
+    ChatParams {
+        max_tokens: config.summary_max_tokens,
+        temperature: config.summary_temperature,
+        top_p: config.summary_top_p,
+        seed: config.seed,
+        transforms: config.middle_out.then(|| vec!["middle-out".to_string()]),
+        tools: None,
+        stream: config.stream,
+    }
+}
+
+/// Requests a summary, asking the model to continue (up to
+/// `config.max_continuations` times) whenever it stops at the token limit.
+async fn complete_summary(
+    api: &dyn ChatApi,
+    config: &Config,
+    messages: Vec<Value>,
+    logger: &Logger,
+) -> Result<ChatResponse> {
+    complete_summary_with(api, config, messages, &summary_params(config), logger).await
+}
+
+/// Like [`complete_summary`], with explicit generation parameters.
+async fn complete_summary_with(
+    api: &dyn ChatApi,
+    config: &Config,
+    mut messages: Vec<Value>,
+    params: &ChatParams,
+    logger: &Logger,
+) -> Result<ChatResponse> {
+    let mut response = api
+        .chat_with_params(&config.summary_model, messages.clone(), params)
+        .await?;
+    let mut summary = response.content.clone();
+    let mut reasoning: Vec<String> = response.reasoning.clone().into_iter().collect();
+    let mut usage = response.usage;
+
+    for attempt in 1..=config.max_continuations {
+        if !response.is_truncated() {
+            break;
+        }
+        logger.warn(&format!(
+            "Summary was cut off at the token limit; requesting continuation {attempt}/{}...",
+            config.max_continuations
+        ));
+        messages.push(json!({"role": "assistant", "content": summary}));
+        messages.push(json!({"role": "user", "content":
+            "Продолжи резюме ровно с того места, где ты остановился. \
+             Не повторяй уже написанное и не добавляй комментариев."}));
+        response = api
+            .chat_with_params(&config.summary_model, messages.clone(), params)
+            .await?;
+        summary.push_str(&response.content);
+        reasoning.extend(response.reasoning.clone());
+        if let Some(more) = response.usage {
+            usage.get_or_insert_with(Usage::default).add(more);
+        }
+    }
+
+    if response.is_truncated() {
+        logger.warn("Summary is still truncated after the allowed continuations");
+    }
+    Ok(ChatResponse {
+        content: summary,
+        finish_reason: response.finish_reason,
+        reasoning: (!reasoning.is_empty()).then(|| reasoning.join("\n\n")),
+        tool_calls: Vec::new(),
+        usage,
+    })
+}
+
+/// A section whose length is off its budget by more than this fraction is
+/// reported in the verification pass.
+const SECTION_BUDGET_TOLERANCE: f64 = 0.5;
+
+/// Word budgets of the summary sections derived from `section_budgets`.
+struct SectionWordBudget {
+    overview: usize,
+    per_file: usize,
+    conclusion: usize,
+}
+
+impl SectionWordBudget {
+    fn new(budgets: &SectionBudgets, target_words: usize, file_count: usize) -> Self {
+        let share = |percent: u8| target_words * percent as usize / 100;
+        Self {
+            overview: share(budgets.overview),
+            per_file: share(budgets.files) / file_count.max(1),
+            conclusion: share(budgets.conclusion),
+        }
+    }
+
+    /// Describes (in Russian, for the model) every section of `summary` whose
+    /// word count is off its budget by more than [`SECTION_BUDGET_TOLERANCE`].
+    fn deviations(&self, summary: &str) -> Vec<String> {
+        let sections = parse_summary(summary);
+        let mut checks: Vec<(String, usize, usize)> = Vec::new();
+        let words = |body: &str| body.split_whitespace().count();
+        if let Some(overview) = &sections.overview {
+            checks.push((
+                "Общий обзор".to_string(),
+                words(&overview.body),
+                self.overview,
+            ));
+        }
+        for file in &sections.files {
+            checks.push((file.heading.clone(), words(&file.body), self.per_file));
+        }
+        if let Some(conclusion) = &sections.conclusion {
+            checks.push((
+                conclusion.heading.clone(),
+                words(&conclusion.body),
+                self.conclusion,
+            ));
+        }
+
+        checks
+            .into_iter()
+            .filter(|(_, actual, budget)| {
+                let budget = *budget as f64;
+                (*actual as f64 - budget).abs() > budget * SECTION_BUDGET_TOLERANCE
+            })
+            .map(|(name, actual, budget)| {
+                format!("«{name}»: {actual} слов вместо примерно {budget}")
+            })
+            .collect()
+    }
+}
+
+/// Verification pass for `section_budgets`: when sections of `summary` are
+/// far off their budgets, asks the model once to rebalance them. Returns the
+/// rebalanced summary, or `summary` unchanged when it is balanced.
+async fn rebalance_sections(
+    api: &dyn ChatApi,
+    config: &Config,
+    mut messages: Vec<Value>,
+    summary: ChatResponse,
+    budget: &SectionWordBudget,
+    logger: &Logger,
+) -> Result<ChatResponse> {
+    let deviations = budget.deviations(&summary.content);
+    if deviations.is_empty() {
+        return Ok(summary);
+    }
+    logger.warn(&format!(
+        "{} section(s) are off their word budget; requesting a rebalanced summary...",
+        deviations.len()
+    ));
+    messages.push(json!({"role": "assistant", "content": summary.content}));
+    messages.push(json!({"role": "user", "content": format!(
+        "Объём разделов не соответствует заданному распределению:\n- {}\n\n\
+         Перепиши резюме, сократив или дополнив эти разделы до заданного объёма. \
+         Выведи ТОЛЬКО исправленное Markdown-резюме.",
+        deviations.join("\n- ")
+    )}));
+    complete_summary(api, config, messages, logger)
+        .await
+        .context("Failed to get rebalanced summary from OpenRouter")
+}
+
+/// Returns whether `words` is off `target_words` by more than
+/// `word_count_tolerance_pct` percent (never when the tolerance is 0).
+fn word_count_off(config: &Config, words: usize, target_words: usize) -> bool {
+    let tolerance = config.word_count_tolerance_pct as f64 / 100.0;
+    config.word_count_tolerance_pct > 0
+        && (words as f64 - target_words as f64).abs() > target_words as f64 * tolerance
+}
+
+/// Length check for the summary: when its word count is far off
+/// `target_words`, asks the model once for a corrected summary. Returns
+/// whichever attempt is closer to the target.
+async fn correct_word_count(
+    api: &dyn ChatApi,
+    config: &Config,
+    mut messages: Vec<Value>,
+    summary: ChatResponse,
+    target_words: usize,
+    logger: &Logger,
+) -> ChatResponse {
+    let words = summary.content.split_whitespace().count();
+    if !word_count_off(config, words, target_words) {
+        return summary;
+    }
+    logger.warn(&format!(
+        "Summary has {words} words instead of ~{target_words}; requesting a corrected summary..."
+    ));
+    messages.push(json!({"role": "assistant", "content": summary.content}));
+    messages.push(json!({"role": "user", "content": format!(
+        "Твоё предыдущее резюме содержало {words} слов. Напиши резюме примерно из \
+         {target_words} слов. Выведи ТОЛЬКО исправленное Markdown-резюме."
+    )}));
+    let retry = match complete_summary(api, config, messages, logger).await {
+        Ok(retry) => retry,
+        Err(e) => {
+            logger.warn(&format!(
+                "Could not get a corrected summary ({e:#}); keeping the original"
+            ));
+            return summary;
+        }
+    };
+    let corrected = retry.content.split_whitespace().count();
+    logger.info(&format!(
+        "Summary word count: {words} originally, {corrected} after correction (target ~{target_words})"
+    ));
+    if !word_count_off(config, corrected, target_words) {
+        return retry;
+    }
+    logger.warn("Corrected summary is still off its word target; keeping the closer attempt");
+    if corrected.abs_diff(target_words) < words.abs_diff(target_words) {
+        retry
+    } else {
+        summary
+    }
+}
+
+/// Builds the system + user messages for summarizing `input`.
+fn build_summary_messages(
+    input: &SummaryInput,
+    target_words: usize,
+    compress_pct: u8,
+    config: &Config,
+) -> Vec<Value> {
+    let mut system_prompt = match &config.summary_prompt {
+        Some(template) => format!(
+            "{}\n\n",
+            template.render(&[
+                ("total_words", input.total_words.to_string()),
+                ("target_words", target_words.to_string()),
+                ("compress_pct", compress_pct.to_string()),
+                ("file_count", input.file_count.to_string()),
+                ("language", config.language().name().to_string()),
+            ])
+        ),
+        None => summary_prompt_opening(input.total_words, target_words, compress_pct),
+    };
+    if let Some(budgets) = &config.section_budgets {
+        let budget = SectionWordBudget::new(budgets, target_words, input.file_count);
+        let _ = write!(
+            system_prompt,
+            "РАСПРЕДЕЛЕНИЕ ОБЪЁМА: общий обзор — около {} слов, раздел каждого файла — \
+             около {} слов, заключение — около {} слов.\n\n",
+            budget.overview, budget.per_file, budget.conclusion
+        );
+    }
+    let file_instructions: Vec<String> = input
+        .file_sections()
+        .into_iter()
+        .filter_map(|(name, _)| {
+            let instruction = config.instructions.summary_for(name)?;
+            Some(format!("- {name}: {}", instruction.trim()))
+        })
+        .collect();
+    if !file_instructions.is_empty() {
+        let _ = write!(
+            system_prompt,
+            "УКАЗАНИЯ ДЛЯ ОТДЕЛЬНЫХ ФАЙЛОВ:\n{}\n\n",
+            file_instructions.join("\n")
+        );
+    }
+    system_prompt.push_str(&prompt_closing(config));
+
+    vec![
+        json!({"role": "system", "content": system_prompt}),
+        json!({"role": "user", "content": format!(
+            "Пожалуйста, составь резюме следующих файлов:\n\n{}", input.combined_text
+        )}),
+    ]
+}
+
+/// Returns the built-in opening of the summary system prompt: the task, the
+/// word target and the expected structure.
+fn summary_prompt_opening(total_words: usize, target_words: usize, compress_pct: u8) -> String {
+    format!(
         "Ты — эксперт по составлению резюме и аналитических сводок. \
          Пользователь предоставит содержимое нескольких файлов. \
          Твоя задача — создать подробное резюме ВСЕХ предоставленных файлов в формате Markdown.\n\n\
-         ВАЖНОЕ ОГРАНИЧЕНИЕ: Исходный текст содержит {total} слов. \
-         Твоё резюме ДОЛЖНО содержать примерно {target} слов (около {pct}% от оригинала). \
-         Считай слова внимательно. НЕ пиши значительно больше или меньше {target} слов.\n\n\
+         ВАЖНОЕ ОГРАНИЧЕНИЕ: Исходный текст содержит {total_words} слов. \
+         Твоё резюме ДОЛЖНО содержать примерно {target_words} слов (около {compress_pct}% от оригинала). \
+         Считай слова внимательно. НЕ пиши значительно больше или меньше {target_words} слов.\n\n\
          Резюме должно включать:\n\
          - Главный заголовок\n\
          - Раздел с общим обзором\n\
          - Раздел для каждого файла с его ключевыми тезисами\n\
-         - Заключение, объединяющее всё вместе\n\n\
-         ОБЯЗАТЕЛЬНО: Отвечай ТОЛЬКО на русском языке. \
+         - Заключение, объединяющее всё вместе\n\n"
+    )
+}
+
+/// Returns the instructions that end every summary system prompt: the
+/// audience, the document profile and the language and format rules.
+fn prompt_closing(config: &Config) -> String {
+    let mut closing = String::new();
+    let audience = config.audience.trim();
+    if !audience.is_empty() {
+        let _ = write!(
+            closing,
+            "ЦЕЛЕВАЯ АУДИТОРИЯ: {audience}. \
+             Подбирай тон, глубину и уровень терминологии под эту аудиторию.\n\n"
+        );
+    }
+    if let Some(profile) = config.document_profile() {
+        closing.push_str(&profile.prompt_section());
+    }
+    let _ = write!(
+        closing,
+        "ОБЯЗАТЕЛЬНО: Отвечай ТОЛЬКО {}. \
          Выводи ТОЛЬКО Markdown-резюме, без лишних комментариев.",
-        total = input.total_words,
-        target = target_words,
-        pct = compress_pct,
+        config.language().in_language()
     );
+    closing
+}
 
+/// Applies the configured post-processing to a generated summary: the fixed
+/// `summary_title`, the keyword section and, with `include_reasoning`, the
+/// model's reasoning.
+fn finish_summary(config: &Config, input: &SummaryInput, response: ChatResponse) -> String {
+    let mut summary = response.content;
+    if let Some(title) = &config.summary_title {
+        summary = replace_title(&summary, title);
+    }
+    if config.include_keywords {
+        let terms = keywords::top_terms(
+            input.source_text(),
+            config.keyword_count,
+            &config.keyword_languages,
+        );
+        summary = keywords::append_keywords(&summary, &terms);
+    }
+    if let Some(reasoning) = response.reasoning.filter(|_| config.include_reasoning) {
+        let _ = write!(
+            summary,
+            "\n\n<details>\n<summary>{REASONING_LABEL}</summary>\n\n{}\n\n</details>\n",
+            reasoning.trim()
+        );
+    }
+    summary
+}
+
+/// Label of the collapsible block holding the model's reasoning.
+const REASONING_LABEL: &str = "Рассуждения модели";
+
+/// Replaces the first `#` heading of `summary` with `title`, or prepends one
+/// if the model wrote no title.
+fn replace_title(summary: &str, title: &str) -> String {
+    let heading = format!("# {}", title.trim());
+    let mut replaced = false;
+    let lines: Vec<&str> = summary
+        .lines()
+        .map(|line| {
+            if !replaced && line.starts_with("# ") {
+                replaced = true;
+                heading.as_str()
+            } else {
+                line
+            }
+        })
+        .collect();
+    if replaced {
+        let mut joined = lines.join("\n");
+        if summary.ends_with('\n') {
+            joined.push('\n');
+        }
+        joined
+    } else {
+        format!("{heading}\n\n{summary}")
+    }
+}
+
+fn save_summary(config: &Config, summary: &str, logger: &Logger) -> Result<()> {
+    let output_path = config.output_path.as_str();
+    output::write_atomic(
+        output_path,
+        render_output(config, summary),
+        config.check_disk_space,
+    )
+    .with_context(|| format!("Failed to write summary to '{output_path}'"))?;
+
+    logger.info(&format!("Summary successfully written to '{output_path}'"));
+
+    if config.split_output_words > 0 {
+        save_parts(config, summary, logger)?;
+    }
+
+    Ok(())
+}
+
+/// Converts a Markdown summary to the configured `output_format`.
+fn render_output(config: &Config, summary: &str) -> String {
+    match config.output_format {
+        OutputFormat::Html => markdown::to_html_document(summary, &config.language().code()),
+        OutputFormat::Text => markdown::to_text(summary),
+        OutputFormat::Markdown | OutputFormat::Csv => summary.to_string(),
+    }
+}
+
+/// Writes `summary` split into parts of at most `split_output_words` words,
+/// each ending with links to its neighbours. A single section longer than the
+/// limit is kept whole rather than cut mid-way.
+fn save_parts(config: &Config, summary: &str, logger: &Logger) -> Result<()> {
+    let max_words = config.split_output_words;
+    let parts = split_at_headings(summary, max_words);
+    if parts.len() < 2 {
+        return Ok(());
+    }
+
+    // Parts live side by side, so links only need the file name.
+    let link = |part: usize| {
+        Path::new(&part_output_path(config, part))
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    for (index, part) in parts.iter().enumerate() {
+        let number = index + 1;
+        let mut nav = Vec::new();
+        if number > 1 {
+            nav.push(format!("[← Часть {}]({})", number - 1, link(number - 1)));
+        }
+        if number < parts.len() {
+            nav.push(format!("[Часть {} →]({})", number + 1, link(number + 1)));
+        }
+
+        let path = part_output_path(config, number);
+        let content = format!("{}\n\n---\n\n{}\n", part.trim_end(), nav.join(" | "));
+        output::write_atomic(
+            &path,
+            render_output(config, &content),
+            config.check_disk_space,
+        )
+        .with_context(|| format!("Failed to write summary part to '{path}'"))?;
+    }
+    logger.info(&format!(
+        "Summary split into {} part(s) of at most {max_words} words",
+        parts.len()
+    ));
+    Ok(())
+}
+
+/// Groups Markdown blocks (each starting at a heading) into chunks of at most
+/// `max_words` words. Headings inside fenced code blocks are not boundaries.
+fn split_at_headings(md: &str, max_words: usize) -> Vec<String> {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    for line in md.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if (!in_fence && line.starts_with('#')) || blocks.is_empty() {
+            blocks.push(String::new());
+        }
+        if let Some(block) = blocks.last_mut() {
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut words = 0;
+    for block in blocks {
+        let block_words = block.split_whitespace().count();
+        match parts.last_mut() {
+            Some(part) if words + block_words <= max_words => {
+                part.push_str(&block);
+                words += block_words;
+            }
+            _ => {
+                parts.push(block);
+                words = block_words;
+            }
+        }
+    }
+    parts
+}
+
+/// Line that opens the extracted text in a `single_pass` reply.
+const SINGLE_PASS_TEXT_MARKER: &str = "=== ТЕКСТ ДОКУМЕНТА ===";
+/// Line that separates the extracted text from the summary in a
+/// `single_pass` reply.
+const SINGLE_PASS_SUMMARY_MARKER: &str = "=== РЕЗЮМЕ ===";
+
+/// Returns `true` if `single_pass` can be used with the rest of the config:
+/// modes that need the extracted text before the summary request (CSV
+/// output, `sectioned`, a messages template, section budgets) cannot, nor can
+/// custom OCR or summary prompts, which the combined prompt would not honour.
+pub fn single_pass_supported(config: &Config) -> bool {
+    config.output_format != OutputFormat::Csv
+        && !config.sectioned
+        && config.messages_template_path.is_none()
+        && config.section_budgets.is_none()
+        && config.ocr_prompt.is_none()
+        && config.summary_prompt.is_none()
+}
+
+/// Extracts and summarizes one image or PDF (`document`, a message content
+/// part) with a single request and writes the summary. Returns the summary
+/// input built from the extracted text, or `None` if the reply did not follow
+/// the requested layout, in which case nothing is written and the caller
+/// should fall back to the two-stage pipeline.
+pub async fn single_pass_and_save(
+    api: &dyn ChatApi,
+    config: &Config,
+    file_name: &str,
+    document: Value,
+    logger: &Logger,
+) -> Result<Option<SummaryInput>> {
+    let compress_pct = config.compress_percent();
+    let mut system_prompt = format!(
+        "Ты — эксперт по распознаванию документов и составлению резюме. \
+         Пользователь предоставит один документ. Выполни за один ответ две задачи:\n\
+         1. Извлеки ВЕСЬ текст документа, сохраняя структуру и форматирование. \
+         Графики, диаграммы и таблицы опиши подробно.{}\n\
+         2. Составь резюме документа в формате Markdown объёмом около {compress_pct}% \
+         от извлечённого текста: главный заголовок, ключевые тезисы и заключение.\n\n",
+        file_processor::ocr_prompt_additions(config, config.instructions.ocr_for(file_name))
+    );
+    if let Some(instruction) = config.instructions.summary_for(file_name) {
+        let _ = write!(
+            system_prompt,
+            "УКАЗАНИЯ ДЛЯ РЕЗЮМЕ: {}\n\n",
+            instruction.trim()
+        );
+    }
+    system_prompt.push_str(&prompt_closing(config));
+    let _ = write!(
+        system_prompt,
+        "\n\nФОРМАТ ОТВЕТА: строка «{SINGLE_PASS_TEXT_MARKER}», затем извлечённый текст, \
+         затем строка «{SINGLE_PASS_SUMMARY_MARKER}», затем резюме. Больше ничего не выводи."
+    );
     let messages = vec![
         json!({"role": "system", "content": system_prompt}),
-        json!({"role": "user", "content": format!(
-            "Пожалуйста, составь резюме следующих файлов:\n\n{}", input.combined_text
-        )}),
+        json!({"role": "user", "content": [
+            {"type": "text", "text": format!("Документ: {file_name}")},
+            document
+        ]}),
     ];
 
-    let response = api
-        .chat(model, messages)
+    logger.info(&format!(
+        "Extracting and summarizing '{file_name}' in one request (single_pass)..."
+    ));
+    let started = std::time::Instant::now();
+    let mut response = complete_summary(api, config, messages, logger)
         .await
-        .context("Failed to get summary from OpenRouter")?;
+        .context("Failed to get single-pass summary from OpenRouter")?;
+    let Some((text, summary)) = split_single_pass_reply(&response.content) else {
+        return Ok(None);
+    };
 
-    fs::write(output_path, &response)
-        .with_context(|| format!("Failed to write summary to '{output_path}'"))?;
+    let file = ProcessedFile {
+        name: file_name.to_string(),
+        path: PathBuf::from(file_name),
+        content: text,
+        ocr_time: Some(started.elapsed()),
+        confidence: None,
+        fields: Vec::new(),
+        metadata: Some(ExtractionMetadata {
+            model: config.summary_model.clone(),
+            usage: response.usage.unwrap_or_default(),
+            cached: false,
+        }),
+    };
+    let files = [file];
+    let input = SummaryInput::from_files_weighted(&files, &config.compression_weights);
+    logger.info(&format!(
+        "Extracted {} words; summary is ~{} words",
+        input.total_words,
+        summary.split_whitespace().count()
+    ));
+    response.content = summary;
+    save_summary(config, &finish_summary(config, &input, response), logger)?;
+    manifest::save(config, &files, logger)?;
+    Ok(Some(input))
+}
 
-    logger.info(&format!("Summary successfully written to '{output_path}'"));
+/// Splits a `single_pass` reply into the extracted text and the summary.
+/// Returns `None` if the summary marker is missing or either part is empty.
+fn split_single_pass_reply(reply: &str) -> Option<(String, String)> {
+    let (text, summary) = reply.split_once(SINGLE_PASS_SUMMARY_MARKER)?;
+    let text = text.trim();
+    let text = text
+        .strip_prefix(SINGLE_PASS_TEXT_MARKER)
+        .unwrap_or(text)
+        .trim();
+    let summary = summary.trim();
+    if text.is_empty() || summary.is_empty() {
+        return None;
+    }
+    Some((text.to_string(), format!("{summary}\n")))
+}
+
+/// Returns the directory holding the sections already generated in
+/// `sectioned` mode (`summary.md.sections`).
+pub fn sections_checkpoint_dir(output_path: &str) -> String {
+    format!("{output_path}.sections")
+}
 
+/// Generates the summary in `sectioned` mode: the title and overview, then
+/// each file's section, then the conclusion, each by its own request. Every
+/// finished section is checkpointed to [`sections_checkpoint_dir`] and reused
+/// by a rerun on the same input, so a crash loses at most one section.
+async fn generate_sectioned_and_save(
+    api: &dyn ChatApi,
+    config: &Config,
+    input: &SummaryInput,
+    target_words: usize,
+    logger: &Logger,
+) -> Result<()> {
+    let files = input.file_sections();
+    let budgets = config.section_budgets.unwrap_or_default();
+    let budget = SectionWordBudget::new(&budgets, target_words, files.len());
+    let checkpoints = SectionCheckpoints::open(&config.output_path, input, logger)?;
+    let closing = prompt_closing(config);
+    let total = files.len() + 2;
+
+    logger.info(&format!("Generating the summary in {total} sections..."));
+    let overview_prompt = format!(
+        "Ты — эксперт по составлению резюме и аналитических сводок. \
+         Пользователь предоставит содержимое нескольких файлов. Напиши ТОЛЬКО начало \
+         резюме в формате Markdown: главный заголовок (`# ...`) и раздел `## Общий обзор` \
+         объёмом около {} слов, описывающий все файлы в целом. Разделы по отдельным \
+         файлам и заключение НЕ пиши.\n\n{closing}",
+        budget.overview
+    );
+    let overview = checkpoints
+        .section(
+            api,
+            config,
+            "overview",
+            &overview_prompt,
+            &input.combined_text,
+            logger,
+        )
+        .await?;
+
+    let mut sections = vec![overview.trim().to_string()];
+    for (index, (name, content)) in files.iter().enumerate() {
+        let words = content.split_whitespace().count() * config.compress_percent() as usize / 100;
+        let target = if config.section_budgets.is_some() {
+            budget.per_file
+        } else {
+            words.max(30)
+        };
+        let instruction = match config.instructions.summary_for(name) {
+            Some(instruction) => format!("УКАЗАНИЯ ДЛЯ ЭТОГО ФАЙЛА: {}\n\n", instruction.trim()),
+            None => String::new(),
+        };
+        let prompt = format!(
+            "Ты — эксперт по составлению резюме и аналитических сводок. \
+             Пользователь предоставит содержимое одного файла. Напиши раздел резюме об этом \
+             файле в формате Markdown: заголовок `## {name}` и ключевые тезисы файла, \
+             около {target} слов.\n\n{instruction}{closing}"
+        );
+        let section = checkpoints
+            .section(
+                api,
+                config,
+                &format!("file-{:03}", index + 1),
+                &prompt,
+                content,
+                logger,
+            )
+            .await?;
+        sections.push(section.trim().to_string());
+    }
+
+    let conclusion_prompt = format!(
+        "Ты — эксперт по составлению резюме и аналитических сводок. \
+         Пользователь предоставит уже написанные разделы резюме. Напиши ТОЛЬКО раздел \
+         `## Заключение` объёмом около {} слов, объединяющий их в общий вывод.\n\n{closing}",
+        budget.conclusion
+    );
+    let written = sections.join("\n\n");
+    let conclusion = checkpoints
+        .section(
+            api,
+            config,
+            "conclusion",
+            &conclusion_prompt,
+            &written,
+            logger,
+        )
+        .await?;
+    sections.push(conclusion.trim().to_string());
+
+    let response = ChatResponse {
+        content: format!("{}\n", sections.join("\n\n")),
+        finish_reason: None,
+        reasoning: None,
+        tool_calls: Vec::new(),
+        usage: None,
+    };
+    save_summary(config, &finish_summary(config, input, response), logger)?;
+    checkpoints.remove();
     Ok(())
 }
+
+/// Finished sections of a `sectioned` run, one file each, keyed by a
+/// fingerprint of the input so that a changed input starts over.
+struct SectionCheckpoints {
+    dir: PathBuf,
+}
+
+impl SectionCheckpoints {
+    fn open(output_path: &str, input: &SummaryInput, logger: &Logger) -> Result<Self> {
+        let dir = PathBuf::from(sections_checkpoint_dir(output_path));
+        let mut hasher = DefaultHasher::new();
+        input.combined_text.hash(&mut hasher);
+        let fingerprint = format!("{:016x}", hasher.finish());
+
+        let fingerprint_path = dir.join("input.hash");
+        match fs::read_to_string(&fingerprint_path) {
+            Ok(previous) if previous.trim() == fingerprint => logger.info(&format!(
+                "Resuming from sections checkpointed in '{}'",
+                dir.display()
+            )),
+            _ => {
+                let _ = fs::remove_dir_all(&dir);
+                fs::create_dir_all(&dir).with_context(|| {
+                    format!("Failed to create checkpoint directory '{}'", dir.display())
+                })?;
+                output::write_atomic(&fingerprint_path, &fingerprint, false)
+                    .with_context(|| format!("Failed to write '{}'", fingerprint_path.display()))?;
+            }
+        }
+        Ok(Self { dir })
+    }
+
+    /// Returns the checkpointed section `key`, or generates it from `system`
+    /// and `user` and checkpoints it.
+    async fn section(
+        &self,
+        api: &dyn ChatApi,
+        config: &Config,
+        key: &str,
+        system: &str,
+        user: &str,
+        logger: &Logger,
+    ) -> Result<String> {
+        let path = self.dir.join(format!("{key}.md"));
+        if let Ok(done) = fs::read_to_string(&path) {
+            return Ok(done);
+        }
+        logger.info(&format!("  Generating section '{key}'..."));
+        let messages = vec![
+            json!({"role": "system", "content": system}),
+            json!({"role": "user", "content": user}),
+        ];
+        let response = complete_summary(api, config, messages, logger)
+            .await
+            .with_context(|| format!("Failed to get section '{key}' from OpenRouter"))?;
+        output::write_atomic(&path, &response.content, config.check_disk_space)
+            .with_context(|| format!("Failed to checkpoint section to '{}'", path.display()))?;
+        Ok(response.content)
+    }
+
+    /// Deletes the checkpoints once the summary is written.
+    fn remove(self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,57 @@
+use files::secrets::{ApiKeySource, EnvSecret, FileSecret, SecretChain, SecretSource};
+
+const UNSET_VAR: &str = "FILES_TEST_API_KEY_NEVER_SET";
+
+fn unset_env() -> Box<dyn SecretSource> {
+    Box::new(EnvSecret {
+        var: UNSET_VAR.to_string(),
+    })
+}
+
+#[tokio::test]
+async fn key_file_is_read_and_trimmed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("key");
+    std::fs::write(&path, "  sk-or-123\n").unwrap();
+    let source = FileSecret {
+        path: path.to_string_lossy().into_owned(),
+    };
+
+    assert_eq!(source.fetch().await.unwrap(), "sk-or-123");
+}
+
+#[tokio::test]
+async fn chain_falls_back_past_missing_and_empty_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let empty = dir.path().join("empty");
+    let key = dir.path().join("key");
+    std::fs::write(&empty, "\n").unwrap();
+    std::fs::write(&key, "sk-or-456").unwrap();
+    let file = |path: &std::path::Path| -> Box<dyn SecretSource> {
+        Box::new(FileSecret {
+            path: path.to_string_lossy().into_owned(),
+        })
+    };
+    let chain = SecretChain::new(vec![unset_env(), file(&empty), file(&key)]);
+
+    assert_eq!(chain.fetch().await.unwrap(), "sk-or-456");
+}
+
+#[tokio::test]
+async fn chain_error_lists_every_source() {
+    let source = ApiKeySource::Env {
+        var: UNSET_VAR.to_string(),
+    }
+    .secret_source_with_file(Some("/nonexistent/openrouter.key"))
+    .unwrap();
+
+    assert_eq!(
+        source.describe(),
+        format!("environment variable {UNSET_VAR} or file '/nonexistent/openrouter.key'")
+    );
+    let error = format!("{:#}", source.fetch().await.unwrap_err());
+    assert!(error.starts_with(&format!(
+        "looked in environment variable {UNSET_VAR}: {UNSET_VAR} not set in .env"
+    )));
+    assert!(error.contains("; file '/nonexistent/openrouter.key': Failed to read"));
+}
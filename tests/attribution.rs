@@ -0,0 +1,16 @@
+use files::api::OpenRouterClient;
+
+#[test]
+fn attribution_headers_reject_control_characters() {
+    let client = || OpenRouterClient::new("key".to_string()).unwrap();
+
+    assert!(client()
+        .with_attribution(Some("https://example.org"), Some("Tribonian"))
+        .is_ok());
+    assert!(client().with_attribution(None, None).is_ok());
+    let error = client()
+        .with_attribution(None, Some("Tribonian\r\nX-Evil: 1"))
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("X-Title"), "{error}");
+}
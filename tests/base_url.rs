@@ -0,0 +1,41 @@
+use files::api::{ChatApi, ChatParams, OpenRouterClient};
+use files::config::Config;
+use serde_json::json;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn requests_go_to_the_configured_base_url() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/gateway/v1/chat/completions"))
+        .and(header("authorization", "Bearer test-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{"message": {"content": "Ответ шлюза"}, "finish_reason": "stop"}],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = OpenRouterClient::new("test-key".to_string())
+        .unwrap()
+        .with_retries(0)
+        .with_base_url(&format!("{}/gateway/v1/", server.uri()));
+
+    let response = client
+        .chat_with_params(
+            "some/model",
+            vec![json!({"role": "user", "content": "Привет"})],
+            &ChatParams::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.content, "Ответ шлюза");
+}
+
+#[test]
+fn base_url_must_be_http() {
+    let error = Config::parse("log_file: \"\"\nbase_url: localhost:8080").unwrap_err();
+
+    assert!(error.to_string().contains("base_url"), "{error:#}");
+}
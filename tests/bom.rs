@@ -0,0 +1,36 @@
+mod common;
+
+use files::file_processor::read_single_file;
+use files::summary::SummaryInput;
+
+/// Reads a BOM-prefixed text file with `yaml`, returning its content.
+async fn read_bom_file(yaml: &str) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("windows.txt");
+    std::fs::write(&path, "\u{feff}Слово один.\r\n").unwrap();
+
+    let outcome = read_single_file(
+        &path,
+        &common::config(yaml),
+        &common::offline_api(),
+        &common::logger(),
+    )
+    .await
+    .unwrap();
+    let input = SummaryInput::from_files(&outcome.files);
+    assert_eq!(input.total_words, 2);
+    outcome.files[0].content.clone()
+}
+
+#[tokio::test]
+async fn leading_bom_is_stripped_by_default() {
+    assert_eq!(read_bom_file("").await, "Слово один.\r\n");
+}
+
+#[tokio::test]
+async fn leading_bom_is_kept_when_configured() {
+    assert_eq!(
+        read_bom_file("strip_bom: false").await,
+        "\u{feff}Слово один.\r\n"
+    );
+}
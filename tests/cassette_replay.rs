@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use files::api::{ChatApi, ChatParams};
+use files::cassette::CassetteClient;
+use files::config::Config;
+use files::logger::{LogStream, Logger};
+use files::summary::{self, SummaryInput};
+use serde_json::json;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/cassettes")
+        .join(name)
+}
+
+fn config_with_output(output: &Path) -> Config {
+    Config::parse(&format!(
+        "files_directory: input\n\
+         compress_summary: 10\n\
+         ocr_model: google/gemini-2.0-flash-001\n\
+         summary_model: deepseek/deepseek-chat\n\
+         output_path: {}\n\
+         log_file: \"\"\n\
+         cache_dir: \"\"\n",
+        output.display()
+    ))
+    .unwrap()
+}
+
+fn sample_input(dir: &Path) -> SummaryInput {
+    let path = dir.join("combined.txt");
+    std::fs::write(
+        &path,
+        "=== File: gk.txt ===\nСтатья 11. Судебная защита гражданских прав.\n",
+    )
+    .unwrap();
+    SummaryInput::from_combined_file(path.to_str().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn replays_successful_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("summary.md");
+    let config = config_with_output(&output);
+    let api = CassetteClient::replay(fixture("summary_success.json")).unwrap();
+    let logger = Logger::new("", LogStream::Split);
+
+    summary::generate_and_save(&api, &config, &sample_input(dir.path()), &logger)
+        .await
+        .unwrap();
+
+    let written = std::fs::read_to_string(&output).unwrap();
+    assert!(written.starts_with("# Резюме"));
+    assert!(written.contains("## Заключение"));
+    assert_eq!(api.remaining(), 0);
+}
+
+#[tokio::test]
+async fn replays_truncated_summary_with_continuation() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("summary.md");
+    let config = config_with_output(&output);
+    let api = CassetteClient::replay(fixture("summary_truncated.json")).unwrap();
+    let logger = Logger::new("", LogStream::Split);
+
+    summary::generate_and_save(&api, &config, &sample_input(dir.path()), &logger)
+        .await
+        .unwrap();
+
+    let written = std::fs::read_to_string(&output).unwrap();
+    assert!(written.ends_with("Документ описывает порядок защиты гражданских прав."));
+    assert_eq!(api.remaining(), 0);
+}
+
+#[tokio::test]
+async fn replays_recorded_error() {
+    let api = CassetteClient::replay(fixture("ocr_rate_limited.json")).unwrap();
+
+    let err = api
+        .chat(
+            "google/gemini-2.0-flash-001",
+            vec![json!({"role": "user", "content": "x"})],
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("429"));
+}
+
+#[tokio::test]
+async fn fails_when_cassette_is_exhausted() {
+    let api = CassetteClient::replay(fixture("ocr_rate_limited.json")).unwrap();
+    let messages = vec![json!({"role": "user", "content": "x"})];
+
+    let _ = api
+        .chat("google/gemini-2.0-flash-001", messages.clone())
+        .await;
+    let err = api
+        .chat("google/gemini-2.0-flash-001", messages)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("No recorded interaction left"));
+}
+
+#[tokio::test]
+async fn recorded_cassette_replays_identically() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorded = dir.path().join("recorded.json");
+    let source: Arc<dyn ChatApi> =
+        Arc::new(CassetteClient::replay(fixture("summary_success.json")).unwrap());
+    let recorder = CassetteClient::record(source, &recorded);
+    let messages = vec![json!({"role": "user", "content": "Summarize"})];
+    let params = ChatParams::default();
+
+    let original = recorder
+        .chat_with_params("deepseek/deepseek-chat", messages.clone(), &params)
+        .await
+        .unwrap();
+    let replayed = CassetteClient::replay(&recorded)
+        .unwrap()
+        .chat_with_params("deepseek/deepseek-chat", messages, &params)
+        .await
+        .unwrap();
+
+    assert_eq!(original.content, replayed.content);
+    assert_eq!(original.finish_reason, replayed.finish_reason);
+}
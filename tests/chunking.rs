@@ -0,0 +1,61 @@
+use files::chunking::split;
+use files::config::Config;
+
+fn numbered_words(count: usize) -> String {
+    (1..=count)
+        .map(|n| format!("слово{n}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn adjacent_chunks_share_the_overlap() {
+    let text = numbered_words(200);
+
+    let chunks = split(&text, 300, 5);
+
+    assert!(chunks.len() > 2);
+    assert!(chunks[0].overlap.is_empty());
+    for pair in chunks.windows(2) {
+        let words: Vec<&str> = pair[0].body.split_whitespace().collect();
+        assert_eq!(pair[1].overlap, words[words.len() - 5..].join(" "));
+    }
+}
+
+#[test]
+fn chunks_fit_and_overlap_is_not_double_counted() {
+    let text = format!("{}\n\n{}\n", numbered_words(120), numbered_words(80));
+
+    let chunks = split(&text, 250, 10);
+
+    assert!(chunks.iter().all(|c| c.chars() <= 250));
+    let bodies: String = chunks.iter().map(|c| c.body.as_str()).collect();
+    assert_eq!(bodies, text);
+    let words: usize = chunks.iter().map(|c| c.words()).sum();
+    assert_eq!(words, 200);
+}
+
+#[test]
+fn short_text_is_a_single_chunk_without_overlap() {
+    let chunks = split("коротко", 100, 10);
+
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].body, "коротко");
+    assert!(chunks[0].overlap.is_empty());
+}
+
+#[test]
+fn stricter_of_the_char_and_token_limits_applies() {
+    let limit = |yaml: &str| Config::parse(yaml).unwrap().input_char_limit();
+
+    assert_eq!(limit("{}"), None);
+    assert_eq!(limit("max_input_tokens: 1000"), Some(3000));
+    assert_eq!(
+        limit("max_input_tokens: 1000\nmax_input_chars: 2000"),
+        Some(2000)
+    );
+    assert_eq!(
+        limit("max_input_tokens: 500\nmax_input_chars: 2000"),
+        Some(1500)
+    );
+}
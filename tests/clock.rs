@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use files::api::{parse_retry_after, retry_delay, OpenRouterClient, RetryBudget, MAX_RETRY_DELAY};
+use files::clock::{Clock, MockClock};
+
+#[tokio::test]
+async fn mock_sleep_advances_virtual_time_instantly() {
+    let clock = MockClock::new();
+    let virtual_start = clock.now();
+    let real_start = Instant::now();
+
+    clock.sleep(Duration::from_secs(3600)).await;
+
+    assert_eq!(clock.now() - virtual_start, Duration::from_secs(3600));
+    assert!(real_start.elapsed() < Duration::from_secs(1));
+    assert_eq!(clock.sleeps(), [Duration::from_secs(3600)]);
+}
+
+#[test]
+fn deadline_follows_the_injected_clock() {
+    let clock = Arc::new(MockClock::new());
+    let client = OpenRouterClient::new("test".to_string())
+        .unwrap()
+        .with_clock(clock.clone())
+        .with_deadline(Some(clock.now() + Duration::from_secs(60)));
+
+    assert!(!client.deadline_exceeded());
+    clock.advance(Duration::from_secs(59));
+    assert!(!client.deadline_exceeded());
+    clock.advance(Duration::from_secs(1));
+    assert!(client.deadline_exceeded());
+}
+
+#[test]
+fn retry_budget_runs_out() {
+    let budget = RetryBudget::new(2);
+
+    assert!(budget.try_take());
+    assert!(budget.try_take());
+    assert!(!budget.try_take());
+    assert_eq!(budget.remaining(), 0);
+}
+
+#[test]
+fn retry_backoff_doubles_up_to_the_cap() {
+    let delays: Vec<u64> = (1..=4).map(|n| retry_delay(n).as_secs()).collect();
+    assert_eq!(delays, [1, 2, 4, 8]);
+    assert_eq!(retry_delay(30), MAX_RETRY_DELAY);
+}
+
+#[test]
+fn retry_after_accepts_seconds_and_http_dates() {
+    let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+
+    assert_eq!(parse_retry_after("7", now), Some(Duration::from_secs(7)));
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2026 07:28:12 GMT", now),
+        Some(Duration::from_secs(12))
+    );
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2026 07:27:00 GMT", now),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon", now), None);
+}
@@ -0,0 +1,148 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use files::api::{ChatApi, ChatParams, ChatResponse, Usage};
+use files::cassette::CassetteClient;
+use files::config::Config;
+use files::logger::{LogStream, Logger};
+use serde_json::Value;
+
+/// Parses `yaml` with file logging and the OCR cache turned off, so tests
+/// leave nothing behind in the working directory.
+pub fn config(yaml: &str) -> Config {
+    Config::parse(&format!("log_file: \"\"\ncache_dir: \"\"\n{yaml}")).unwrap()
+}
+
+/// Like [`config`], reading files from `dir`.
+pub fn dir_config(dir: &Path, yaml: &str) -> Config {
+    config(&format!(
+        "files_directory: {:?}\n{yaml}",
+        dir.display().to_string()
+    ))
+}
+
+/// A console-only logger.
+pub fn logger() -> Logger {
+    Logger::new("", LogStream::Split)
+}
+
+/// A cassette with no interactions: any API call fails.
+pub fn offline_api() -> CassetteClient {
+    let cassette = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(cassette.path(), "[]").unwrap();
+    CassetteClient::replay(cassette.path()).unwrap()
+}
+
+/// A complete reply of `content` costing 120 tokens.
+pub fn reply(content: &str) -> ChatResponse {
+    ChatResponse {
+        content: content.to_string(),
+        finish_reason: Some("stop".to_string()),
+        reasoning: None,
+        tool_calls: Vec::new(),
+        usage: Some(Usage {
+            prompt_tokens: 100,
+            completion_tokens: 20,
+            total_tokens: 120,
+        }),
+    }
+}
+
+/// One request received by [`MockApi`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub model: String,
+    pub messages: Vec<Value>,
+    pub params: ChatParams,
+}
+
+type Answer = dyn Fn(&[Value]) -> Result<ChatResponse> + Send + Sync;
+type Delay = dyn Fn(&[Value]) -> Duration + Send + Sync;
+
+/// A [`ChatApi`] answering every request with a closure of its messages and
+/// keeping the requests it received.
+pub struct MockApi {
+    answer: Box<Answer>,
+    delay: Option<Box<Delay>>,
+    requests: Mutex<Vec<Request>>,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+impl MockApi {
+    pub fn new(answer: impl Fn(&[Value]) -> Result<ChatResponse> + Send + Sync + 'static) -> Self {
+        Self {
+            answer: Box::new(answer),
+            delay: None,
+            requests: Mutex::new(Vec::new()),
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Answers every request with `content`.
+    pub fn replying(content: &str) -> Self {
+        let content = content.to_string();
+        Self::new(move |_| Ok(reply(&content)))
+    }
+
+    /// Answers with `replies` in turn; a request beyond them fails the test.
+    pub fn scripted(replies: &[String]) -> Self {
+        let replies = Mutex::new(replies.iter().rev().cloned().collect::<Vec<_>>());
+        Self::new(move |_| {
+            Ok(reply(
+                &replies.lock().unwrap().pop().expect("unexpected request"),
+            ))
+        })
+    }
+
+    /// Waits for `delay(messages)` before answering each request.
+    pub fn with_delay(
+        mut self,
+        delay: impl Fn(&[Value]) -> Duration + Send + Sync + 'static,
+    ) -> Self {
+        self.delay = Some(Box::new(delay));
+        self
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns the most requests that were ever in flight at once.
+    pub fn peak_in_flight(&self) -> usize {
+        self.peak_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ChatApi for MockApi {
+    async fn chat_with_params(
+        &self,
+        model: &str,
+        messages: Vec<Value>,
+        params: &ChatParams,
+    ) -> Result<ChatResponse> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        if let Some(delay) = &self.delay {
+            tokio::time::sleep(delay(&messages)).await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        let answer = (self.answer)(&messages);
+        self.requests.lock().unwrap().push(Request {
+            model: model.to_string(),
+            messages,
+            params: params.clone(),
+        });
+        answer
+    }
+}
@@ -0,0 +1,81 @@
+use files::config::{Config, CONFIG_VERSION};
+
+fn parse(yaml: &str) -> Config {
+    Config::parse(&format!("log_file: \"\"\n{yaml}")).unwrap()
+}
+
+#[test]
+fn model_lists_become_fallback_models() {
+    let config = parse(
+        "ocr_model: [vision/a, vision/b]\n\
+         summary_model: [text/a, text/b, text/c]\n\
+         summary_fallback_models: [text/d]",
+    );
+
+    assert_eq!(config.config_version, CONFIG_VERSION);
+    assert_eq!(config.ocr_model, "vision/a");
+    assert_eq!(config.ocr_fallback_models, ["vision/b"]);
+    assert_eq!(config.summary_model, "text/a");
+    assert_eq!(
+        config.summary_fallback_models,
+        ["text/b", "text/c", "text/d"]
+    );
+    assert_eq!(config.load_warnings.len(), 2);
+    assert!(
+        config.load_warnings[1].contains("'summary_model' is deprecated"),
+        "{:?}",
+        config.load_warnings
+    );
+}
+
+#[test]
+fn current_layout_is_not_migrated() {
+    let config = parse("config_version: 1\nsummary_model: text/a");
+    assert!(config.load_warnings.is_empty());
+
+    let error =
+        Config::parse("log_file: \"\"\nconfig_version: 1\nsummary_model: [text/a]").unwrap_err();
+    assert!(format!("{error:#}").contains("summary_model"), "{error:#}");
+}
+
+#[test]
+fn newer_layouts_are_rejected() {
+    let error = Config::parse(&format!("config_version: {}", CONFIG_VERSION + 1)).unwrap_err();
+
+    assert!(
+        error.to_string().contains("update the program"),
+        "{error:#}"
+    );
+}
+
+#[test]
+fn unknown_nested_fields_are_ignored_with_a_warning() {
+    let config = parse(
+        "typo_field: 1\n\
+         section_budgets:\n  overveiw: 30\n\
+         profiles:\n  memo:\n    focsu: x\n\
+         model_pricing:\n  google/gemini-2.0-flash-001:\n    prompt: 0.1\n    complet: 0.4\n\
+         extraction_tools:\n  - name: dates\n    description: Dates\n    parameters: {}\n    strict: true",
+    );
+
+    let mut warnings = config.load_warnings.clone();
+    warnings.sort();
+    let fields: Vec<&str> = warnings
+        .iter()
+        .map(|w| w.split('\'').nth(1).unwrap())
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "extraction_tools[0].strict",
+            "model_pricing.google/gemini-2.0-flash-001.complet",
+            "profiles.memo.focsu",
+            "section_budgets.overveiw",
+            "typo_field",
+        ]
+    );
+    assert_eq!(
+        config.model_pricing["google/gemini-2.0-flash-001"].prompt,
+        0.1
+    );
+}
@@ -0,0 +1,20 @@
+use files::config::Config;
+use serde_yaml::{Mapping, Value};
+
+#[test]
+fn overrides_take_precedence_over_the_file_and_defaults() {
+    let mut overrides = Mapping::new();
+    overrides.insert(Value::from("summary_model"), Value::from("cli/model"));
+    overrides.insert(Value::from("compress_summary"), Value::from(25u8));
+
+    let config = Config::parse_with_overrides(
+        "summary_model: yaml/model\nfiles_directory: ./docs\n",
+        &overrides,
+    )
+    .unwrap();
+
+    assert_eq!(config.summary_model, "cli/model");
+    assert_eq!(config.compress_percent(), 25);
+    assert_eq!(config.files_directory, "./docs");
+    assert_eq!(config.ocr_model, Config::parse("{}").unwrap().ocr_model);
+}
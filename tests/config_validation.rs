@@ -0,0 +1,33 @@
+use files::config::Config;
+
+fn error(yaml: &str) -> String {
+    format!("{:#}", Config::parse(yaml).unwrap_err())
+}
+
+#[test]
+fn out_of_range_compression_is_rejected() {
+    assert_eq!(
+        error("compress_summary: 0"),
+        "compress_summary must be between 1 and 100, not 0"
+    );
+    assert!(error("compress_summary: 150").contains("compress_summary"));
+    assert_eq!(
+        Config::parse("compress_summary: 100")
+            .unwrap()
+            .compress_percent(),
+        100
+    );
+}
+
+#[test]
+fn empty_model_names_are_rejected() {
+    assert_eq!(
+        error("summary_model: \"\""),
+        "summary_model must not be empty"
+    );
+    assert_eq!(error("ocr_model: \"  \""), "ocr_model must not be empty");
+    assert_eq!(
+        error("ocr_fallback_models: [\"openai/gpt-4o\", \"\"]"),
+        "ocr_fallback_models must not contain an empty model name"
+    );
+}
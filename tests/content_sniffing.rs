@@ -0,0 +1,32 @@
+use std::fs;
+
+use files::config::Config;
+use files::file_processor::{plan_files, PlannedAction};
+
+#[test]
+fn unknown_extensions_are_classified_by_content() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("scan"), "%PDF-1.7\n").unwrap();
+    fs::write(
+        dir.path().join("photo.dat"),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A],
+    )
+    .unwrap();
+    fs::write(dir.path().join("data.dat"), "just bytes").unwrap();
+    // The extension wins when it is known.
+    fs::write(dir.path().join("notes.txt"), "%PDF is mentioned here").unwrap();
+    let config = Config::parse("cache_dir: \"\"").unwrap();
+
+    let plan = plan_files(dir.path(), &config).unwrap();
+
+    let actions: Vec<_> = plan.iter().map(|f| (f.name.as_str(), f.action)).collect();
+    assert_eq!(
+        actions,
+        [
+            ("data.dat", PlannedAction::Skip),
+            ("notes.txt", PlannedAction::Text),
+            ("photo.dat", PlannedAction::OcrImage),
+            ("scan", PlannedAction::OcrPdf),
+        ]
+    );
+}
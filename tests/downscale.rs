@@ -0,0 +1,59 @@
+use std::io::Cursor;
+
+use files::downscale::downscale;
+use image::{ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+
+fn encode_png(image: impl Into<image::DynamicImage>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image
+        .into()
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+fn noisy(width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| {
+        let v = (x.wrapping_mul(31) ^ y.wrapping_mul(17)) as u8;
+        Rgb([v, v.wrapping_add(80), v.wrapping_mul(3)])
+    })
+}
+
+#[test]
+fn large_photo_is_shrunk_to_jpeg() {
+    let original = encode_png(noisy(3000, 1000));
+
+    let image = downscale(&original, 1024).unwrap().unwrap();
+
+    assert_eq!((image.width, image.height), (1024, 341));
+    assert_eq!(image.mime, "image/jpeg");
+    assert!(image.bytes.len() < original.len());
+    assert_eq!(
+        image::guess_format(&image.bytes).unwrap(),
+        ImageFormat::Jpeg
+    );
+}
+
+#[test]
+fn transparency_is_kept_as_png() {
+    let original = encode_png(RgbaImage::from_fn(2000, 2000, |x, y| {
+        Rgba([(x % 256) as u8, (y % 256) as u8, 0, (x ^ y) as u8])
+    }));
+
+    let image = downscale(&original, 500).unwrap().unwrap();
+
+    assert_eq!((image.width, image.height), (500, 500));
+    assert_eq!(image.mime, "image/png");
+}
+
+#[test]
+fn image_that_fits_is_left_alone() {
+    let original = encode_png(noisy(800, 600));
+
+    assert!(downscale(&original, 1024).unwrap().is_none());
+}
+
+#[test]
+fn other_formats_are_left_alone() {
+    assert!(downscale(b"GIF89a not really", 16).unwrap().is_none());
+}
@@ -0,0 +1,54 @@
+use std::fs;
+
+use files::config::Config;
+use files::file_processor::{plan_files, PlannedAction};
+
+#[test]
+fn files_are_classified_without_calling_the_api() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "три слова тут").unwrap();
+    fs::write(dir.path().join("scan.pdf"), "%PDF-1.4").unwrap();
+    fs::write(dir.path().join("photo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+    fs::write(dir.path().join("blob.bin"), [0, 1, 2]).unwrap();
+    let config = Config::parse("cache_dir: \"\"").unwrap();
+
+    let plan = plan_files(dir.path(), &config).unwrap();
+
+    let planned: Vec<_> = plan
+        .iter()
+        .map(|f| (f.name.as_str(), f.action, f.words))
+        .collect();
+    assert_eq!(
+        planned,
+        [
+            ("blob.bin", PlannedAction::Skip, None),
+            ("notes.txt", PlannedAction::Text, Some(3)),
+            ("photo.png", PlannedAction::OcrImage, None),
+            ("scan.pdf", PlannedAction::OcrPdf, None),
+        ]
+    );
+    assert_eq!(plan[1].size, "три слова тут".len() as u64);
+}
+
+#[test]
+fn files_over_the_size_limit_are_reported_as_too_large() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("big.txt"), "слово ".repeat(100)).unwrap();
+    fs::write(dir.path().join("small.txt"), "два слова").unwrap();
+    let config = Config::parse("cache_dir: \"\"\nmax_file_size_bytes: 100").unwrap();
+
+    let plan = plan_files(dir.path(), &config).unwrap();
+
+    let planned: Vec<_> = plan
+        .iter()
+        .map(|f| (f.name.as_str(), f.action, f.words))
+        .collect();
+    assert_eq!(
+        planned,
+        [
+            ("big.txt", PlannedAction::TooLarge, None),
+            ("small.txt", PlannedAction::Text, Some(2)),
+        ]
+    );
+    assert_eq!(PlannedAction::TooLarge.to_string(), "skipped (too large)");
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::fs;
+
+use files::file_processor::read_all_files;
+use files::logger::{LogStream, Logger};
+
+/// Reads a directory holding `a/notes.txt` and `b/notes.txt` recursively with
+/// `duplicate_names: policy`, returning the file names and the log.
+async fn read_colliding(policy: &str) -> (Vec<String>, String) {
+    let dir = tempfile::tempdir().unwrap();
+    for sub in ["a", "b"] {
+        fs::create_dir(dir.path().join(sub)).unwrap();
+        fs::write(
+            dir.path().join(sub).join("notes.txt"),
+            format!("заметки {sub}"),
+        )
+        .unwrap();
+    }
+    let log = tempfile::tempdir().unwrap();
+    let log_path = log.path().join("app.log");
+    let logger = Logger::new(log_path.to_str().unwrap(), LogStream::Stderr);
+    let config = common::dir_config(
+        dir.path(),
+        &format!("recursive: true\nduplicate_names: {policy}"),
+    );
+
+    let outcome = read_all_files(&config, &common::offline_api(), &logger)
+        .await
+        .unwrap();
+
+    let names = outcome.files.into_iter().map(|f| f.name).collect();
+    (names, fs::read_to_string(log_path).unwrap())
+}
+
+#[tokio::test]
+async fn shared_base_names_keep_their_relative_paths() {
+    let (names, log) = read_colliding("path").await;
+
+    assert_eq!(names, ["a/notes.txt", "b/notes.txt"]);
+    assert!(
+        log.contains("Name collision: 2 files are named 'notes.txt'"),
+        "{log}"
+    );
+}
+
+#[tokio::test]
+async fn shared_base_names_can_be_numbered() {
+    let (names, log) = read_colliding("suffix").await;
+
+    assert_eq!(names, ["notes.txt", "notes (2).txt"]);
+    assert!(
+        log.contains("Name collision: 2 files are named 'notes.txt'"),
+        "{log}"
+    );
+}
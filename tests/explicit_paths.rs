@@ -0,0 +1,40 @@
+mod common;
+
+use std::fs;
+
+use files::file_processor::read_single_file;
+
+#[tokio::test]
+async fn directory_argument_is_skipped_unless_recursive() {
+    let dir = tempfile::tempdir().unwrap();
+    let docs = dir.path().join("docs");
+    fs::create_dir(&docs).unwrap();
+    fs::write(docs.join("a.txt"), "alpha").unwrap();
+    let config = common::config("");
+    let logger = common::logger();
+
+    let outcome = read_single_file(&docs, &config, &common::offline_api(), &logger)
+        .await
+        .unwrap();
+
+    assert!(outcome.files.is_empty());
+    assert!(outcome.failures.is_empty());
+}
+
+#[tokio::test]
+async fn directory_argument_is_read_when_recursive() {
+    let dir = tempfile::tempdir().unwrap();
+    let docs = dir.path().join("docs");
+    fs::create_dir_all(docs.join("nested")).unwrap();
+    fs::write(docs.join("a.txt"), "alpha").unwrap();
+    fs::write(docs.join("nested/b.txt"), "beta").unwrap();
+    let config = common::config("recursive: true");
+    let logger = common::logger();
+
+    let outcome = read_single_file(&docs, &config, &common::offline_api(), &logger)
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = outcome.files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["a.txt", "nested/b.txt"]);
+}
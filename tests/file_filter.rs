@@ -0,0 +1,54 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use files::file_filter::FileFilter;
+use files::file_processor::read_all_files;
+
+fn patterns(globs: &[&str]) -> Vec<String> {
+    globs.iter().map(|g| g.to_string()).collect()
+}
+
+#[test]
+fn exclude_wins_over_include() {
+    let filter = FileFilter::new(
+        &patterns(&["*.js", "docs/**"]),
+        &patterns(&["**/node_modules/**", "*.min.js"]),
+    )
+    .unwrap();
+
+    assert!(filter.matches(Path::new("src/app.js")));
+    assert!(filter.matches(Path::new("docs/guide.md")));
+    assert!(!filter.matches(Path::new("src/app.min.js")));
+    assert!(!filter.matches(Path::new("web/node_modules/lib/index.js")));
+    assert!(!filter.matches(Path::new("notes.txt")));
+}
+
+#[test]
+fn plain_names_match_at_any_depth() {
+    let filter = FileFilter::new(&[], &patterns(&["secret.txt"])).unwrap();
+
+    assert!(!filter.matches(Path::new("a/b/secret.txt")));
+    assert!(filter.matches(Path::new("a/b/public.txt")));
+}
+
+#[tokio::test]
+async fn recursive_scan_applies_the_filter() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("a/node_modules")).unwrap();
+    fs::write(dir.path().join("top.txt"), "верх").unwrap();
+    fs::write(dir.path().join("a/nested.txt"), "вложенный").unwrap();
+    fs::write(dir.path().join("a/node_modules/dep.txt"), "зависимость").unwrap();
+    let config = common::dir_config(
+        dir.path(),
+        "recursive: true\nexclude: [\"**/node_modules/**\"]",
+    );
+    let logger = common::logger();
+    let api = common::offline_api();
+
+    let outcome = read_all_files(&config, &api, &logger).await.unwrap();
+
+    let names: Vec<&str> = outcome.files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["a/nested.txt", "top.txt"]);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::io::Read;
+use std::path::Path;
+
+use common::MockApi;
+use files::file_processor::read_single_file;
+use files::gif::Gif;
+
+/// Two 4x4 frames: all red, then (interlaced) green, blue, green, blue rows.
+const FIXTURE: &str = "tests/fixtures/animated.gif";
+
+const RED: [u8; 3] = [255, 0, 0];
+const GREEN: [u8; 3] = [0, 255, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+
+/// Returns the RGB rows of an 8-bit truecolour PNG without filtering.
+fn png_rows(png: &[u8]) -> Vec<Vec<[u8; 3]>> {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let mut pos = 8;
+    let mut idat = Vec::new();
+    while pos < png.len() {
+        let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+        if &png[pos + 4..pos + 8] == b"IDAT" {
+            idat.extend_from_slice(&png[pos + 8..pos + 8 + len]);
+        }
+        pos += len + 12;
+    }
+    let mut raw = Vec::new();
+    flate2::read::ZlibDecoder::new(idat.as_slice())
+        .read_to_end(&mut raw)
+        .unwrap();
+    raw.chunks(1 + 4 * 3)
+        .map(|row| row[1..].chunks(3).map(|p| [p[0], p[1], p[2]]).collect())
+        .collect()
+}
+
+#[test]
+fn renders_each_frame_of_an_animated_gif() {
+    let gif = Gif::parse(&std::fs::read(FIXTURE).unwrap()).unwrap();
+    assert_eq!(gif.frame_count(), 2);
+
+    let pngs = gif.frames_to_png(&[0, 1]).unwrap();
+
+    assert_eq!(png_rows(&pngs[0]), vec![vec![RED; 4]; 4]);
+    let second: Vec<[u8; 3]> = png_rows(&pngs[1]).iter().map(|row| row[0]).collect();
+    assert_eq!(second, [GREEN, BLUE, GREEN, BLUE]);
+}
+
+#[tokio::test]
+async fn animated_gif_frames_are_sent_as_png() {
+    let config = common::config("gif_frames: 2");
+    let api = MockApi::replying("Текст кадра");
+
+    let outcome = read_single_file(Path::new(FIXTURE), &config, &api, &common::logger())
+        .await
+        .unwrap();
+
+    let content = &outcome.files[0].content;
+    assert!(content.contains("--- Кадр 1 из 2 ---"));
+    assert!(content.contains("--- Кадр 2 из 2 ---"));
+    let urls: Vec<String> = api
+        .requests()
+        .iter()
+        .map(|r| {
+            r.messages[0]["content"][1]["image_url"]["url"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(urls.len(), 2);
+    assert!(urls
+        .iter()
+        .all(|url| url.starts_with("data:image/png;base64,")));
+    let metadata = outcome.files[0].metadata.as_ref().unwrap();
+    assert_eq!(metadata.model, config.ocr_model);
+    assert_eq!(metadata.usage.total_tokens, 240);
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use std::path::Path;
+
+use files::file_processor::read_single_file;
+
+#[tokio::test]
+async fn html_keeps_visible_text_alt_text_and_link_text() {
+    let outcome = read_single_file(
+        Path::new("tests/fixtures/page.html"),
+        &common::config(""),
+        &common::offline_api(),
+        &common::logger(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        outcome.files[0].content,
+        "Отчёт за квартал\n\
+         Итоги\n\
+         Выручка выросла. Подробнее в полном отчёте.\n\
+         [Изображение: График выручки]\n\
+         Рисунок 1. Выручка по месяцам"
+    );
+}
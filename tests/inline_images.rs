@@ -0,0 +1,54 @@
+mod common;
+
+use std::fs;
+
+use common::MockApi;
+use files::file_processor::read_all_files;
+use files::logger::{LogStream, Logger};
+
+#[tokio::test]
+async fn images_outside_the_files_directory_are_not_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let docs = dir.path().join("docs");
+    fs::create_dir(&docs).unwrap();
+    let png = image::GrayImage::from_pixel(8, 8, image::Luma([0]));
+    png.save(docs.join("inside.png")).unwrap();
+    png.save(dir.path().join("secret.png")).unwrap();
+    let absolute = dir.path().join("secret.png").canonicalize().unwrap();
+    fs::write(
+        docs.join("notes.md"),
+        format!(
+            "![a](inside.png)\n![b](../secret.png)\n![c]({})\n",
+            absolute.display()
+        ),
+    )
+    .unwrap();
+    let log_path = dir.path().join("app.log");
+    let logger = Logger::new(log_path.to_str().unwrap(), LogStream::Stderr);
+    let api = MockApi::replying("Текст картинки");
+
+    let outcome = read_all_files(
+        &common::dir_config(&docs, "inline_referenced_images: true"),
+        &api,
+        &logger,
+    )
+    .await
+    .unwrap();
+
+    let notes = outcome.files.iter().find(|f| f.name == "notes.md").unwrap();
+    assert!(notes.content.contains("--- Изображение: inside.png ---"));
+    assert_eq!(notes.content.matches("--- Изображение:").count(), 1);
+    // inside.png is read twice: as a file of its own and from notes.md.
+    assert_eq!(api.requests().len(), 2);
+    let log = fs::read_to_string(log_path).unwrap();
+    assert!(
+        log.contains(
+            "Image '../secret.png' referenced from 'notes.md' is outside the files directory"
+        ),
+        "{log}"
+    );
+    assert!(
+        log.contains(&format!("Image '{}' referenced", absolute.display())),
+        "{log}"
+    );
+}
@@ -0,0 +1,18 @@
+use files::language::OutputLanguage;
+
+#[test]
+fn names_and_codes_are_equivalent() {
+    let by_name = OutputLanguage::parse("spanish");
+    assert_eq!(by_name, OutputLanguage::parse("ES"));
+    assert_eq!(by_name.name(), "Spanish");
+    assert_eq!(by_name.code(), "es");
+    assert_eq!(by_name.in_language(), "на испанском языке");
+    assert_eq!(OutputLanguage::parse("hi").in_language(), "на хинди");
+}
+
+#[test]
+fn unknown_languages_are_used_as_given() {
+    let language = OutputLanguage::parse(" Esperanto ");
+    assert_eq!(language.code(), "esperanto");
+    assert_eq!(language.in_language(), "на языке «Esperanto»");
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::path::Path;
+
+use files::file_processor::read_single_file;
+
+/// Larger than the size above which text files are streamed.
+const LINES: usize = 600_000;
+
+/// Writes a text file of `LINES` CRLF-terminated lines, over 8 MiB.
+fn write_large_file(path: &Path) -> String {
+    let text: String = (0..LINES).map(|n| format!("line {n:08}\r\n")).collect();
+    assert!(text.len() > 8 * 1024 * 1024);
+    std::fs::write(path, &text).unwrap();
+    text
+}
+
+async fn read(path: &Path, yaml: &str) -> String {
+    let outcome = read_single_file(
+        path,
+        &common::config(yaml),
+        &common::offline_api(),
+        &common::logger(),
+    )
+    .await
+    .unwrap();
+    outcome.files[0].content.clone()
+}
+
+#[tokio::test]
+async fn untruncated_large_file_is_read_byte_for_byte() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    let text = write_large_file(&path);
+
+    assert_eq!(read(&path, "").await, text);
+    assert_eq!(read(&path, "max_lines_per_file: 1000000").await, text);
+}
+
+#[tokio::test]
+async fn truncated_large_file_matches_a_small_file_truncation() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    write_large_file(&path);
+    let small = dir.path().join("small.txt");
+    std::fs::write(&small, "a\r\nb\r\nc\r\nd\r\ne\r\n").unwrap();
+
+    assert_eq!(
+        read(&path, "max_lines_per_file: 3").await,
+        format!(
+            "line 00000000\nline 00000001\n[... {} lines omitted ...]\nline {:08}",
+            LINES - 3,
+            LINES - 1
+        )
+    );
+    assert_eq!(
+        read(&small, "max_lines_per_file: 3").await,
+        "a\nb\n[... 2 lines omitted ...]\ne"
+    );
+}
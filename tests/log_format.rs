@@ -0,0 +1,36 @@
+use files::logger::{LogFormat, LogLevel, LogStream, Logger};
+use serde_json::Value;
+
+#[test]
+fn json_lines_carry_timestamp_level_and_message() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    let logger = Logger::with_format(path.to_str().unwrap(), LogStream::Stderr, LogFormat::Json);
+
+    logger.warn("Пропущен файл \"a.txt\"");
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    let line: Value = serde_json::from_str(content.trim_end()).unwrap();
+    assert_eq!(line["level"], "WARN");
+    assert_eq!(line["message"], "Пропущен файл \"a.txt\"");
+    assert!(chrono::DateTime::parse_from_rfc3339(line["timestamp"].as_str().unwrap()).is_ok());
+}
+
+#[test]
+fn messages_below_the_minimum_level_are_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    let logger =
+        Logger::new(path.to_str().unwrap(), LogStream::Stderr).with_min_level(LogLevel::Warn);
+
+    logger.info("Read (text): a.txt");
+    logger.warn("Skipping 'b.bin'");
+    logger.error("Failed");
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    let levels: Vec<_> = content
+        .lines()
+        .map(|l| l.split("] [").nth(1).unwrap().split(']').next().unwrap())
+        .collect();
+    assert_eq!(levels, ["WARN", "ERROR"]);
+}
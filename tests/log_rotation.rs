@@ -0,0 +1,21 @@
+mod common;
+
+use files::logger::{LogStream, Logger};
+
+#[test]
+fn rotation_can_be_configured_through_the_alternative_names() {
+    let config = common::config("max_log_size_bytes: 100\nmax_log_backups: 1");
+    assert_eq!((config.log_max_bytes, config.log_max_files), (100, 1));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    let logger = Logger::new(path.to_str().unwrap(), LogStream::Stderr)
+        .with_rotation(config.log_max_bytes, config.log_max_files);
+    for n in 0..10 {
+        logger.info(&format!("Сообщение номер {n}"));
+    }
+
+    assert!(std::fs::metadata(&path).unwrap().len() <= 100);
+    assert!(dir.path().join("app.log.1").exists());
+    assert!(!dir.path().join("app.log.2").exists());
+}
@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use chrono::{TimeZone, Utc};
+use files::config::Config;
+use files::file_processor::{ExtractionMetadata, ProcessedFile};
+use files::manifest::{manifest_path, Manifest};
+
+fn file(name: &str, content: &str, metadata: Option<ExtractionMetadata>) -> ProcessedFile {
+    ProcessedFile {
+        name: name.to_string(),
+        path: PathBuf::from(name),
+        content: content.to_string(),
+        ocr_time: None,
+        confidence: None,
+        fields: Vec::new(),
+        metadata,
+    }
+}
+
+#[test]
+fn manifest_lists_files_models_and_compression() {
+    let config = Config::parse(
+        "log_file: \"\"\nocr_model: vision/model\nsummary_model: text/model\ncompress_summary: 15",
+    )
+    .unwrap();
+    let files = [
+        file("notes.txt", "три слова здесь", None),
+        file(
+            "scan.pdf",
+            "Договор № 5",
+            Some(ExtractionMetadata {
+                cached: true,
+                ..ExtractionMetadata::new("vision/model")
+            }),
+        ),
+    ];
+    let generated_at = Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap();
+
+    let json = Manifest::new(&config, &files, generated_at)
+        .to_json()
+        .unwrap();
+
+    assert_eq!(
+        json,
+        r#"{
+  "generated_at": "2026-03-01T09:30:00Z",
+  "ocr_model": "vision/model",
+  "summary_model": "text/model",
+  "compress_percent": 15,
+  "files": [
+    {
+      "name": "notes.txt",
+      "words": 3,
+      "extraction": "text",
+      "cached": false
+    },
+    {
+      "name": "scan.pdf",
+      "words": 3,
+      "extraction": "ocr",
+      "model": "vision/model",
+      "cached": true
+    }
+  ]
+}
+"#
+    );
+}
+
+#[test]
+fn manifest_sits_next_to_the_summary() {
+    assert_eq!(manifest_path("out/summary.md"), "out/summary.meta.json");
+    assert_eq!(manifest_path("report"), "report.meta.json");
+}
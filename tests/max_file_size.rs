@@ -0,0 +1,47 @@
+mod common;
+
+use std::fs;
+
+use files::file_processor::read_all_files;
+
+async fn read_names(dir: &std::path::Path, limit: u64) -> (Vec<String>, usize) {
+    let config = common::dir_config(dir, &format!("max_file_size_bytes: {limit}"));
+    let logger = common::logger();
+    let api = common::offline_api();
+
+    let outcome = read_all_files(&config, &api, &logger).await.unwrap();
+    let names = outcome.files.into_iter().map(|f| f.name).collect();
+    (names, outcome.failures.len())
+}
+
+#[test]
+fn default_limit_is_fifty_megabytes() {
+    let config = common::config("");
+
+    assert_eq!(config.max_file_size_bytes, 50 * 1024 * 1024);
+}
+
+#[tokio::test]
+async fn oversized_files_are_skipped_before_reading() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("small.txt"), "коротко").unwrap();
+    fs::write(dir.path().join("large.txt"), "длинно ".repeat(20)).unwrap();
+    // Would need an OCR request (and fail on the empty cassette) if read.
+    fs::write(dir.path().join("scan.png"), vec![0u8; 200]).unwrap();
+    fs::write(dir.path().join("scan.pdf"), vec![0u8; 200]).unwrap();
+
+    let (names, failures) = read_names(dir.path(), 64).await;
+
+    assert_eq!(names, ["small.txt"]);
+    assert_eq!(failures, 0);
+}
+
+#[tokio::test]
+async fn zero_means_no_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("large.txt"), "длинно ".repeat(20)).unwrap();
+
+    let (names, _) = read_names(dir.path(), 0).await;
+
+    assert_eq!(names, ["large.txt"]);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::path::Path;
+
+use common::MockApi;
+use files::config::Config;
+use files::file_processor::read_single_file;
+use files::ocr_cache::OcrCache;
+
+#[test]
+fn key_depends_on_content_and_model() {
+    let key = OcrCache::key(b"image", "model-a", "ru");
+
+    assert_eq!(key.len(), 64);
+    assert_eq!(key, OcrCache::key(b"image", "model-a", "ru"));
+    assert_ne!(key, OcrCache::key(b"image", "model-b", "ru"));
+    assert_ne!(key, OcrCache::key(b"other", "model-a", "ru"));
+    assert_ne!(key, OcrCache::key(b"image", "model-a", "en"));
+}
+
+#[tokio::test]
+async fn unchanged_image_is_read_from_the_cache() {
+    let cache = tempfile::tempdir().unwrap();
+    let config = Config::parse(&format!(
+        "log_file: \"\"\ncache_dir: {:?}",
+        cache.path().display().to_string()
+    ))
+    .unwrap();
+    let logger = common::logger();
+    let api = MockApi::replying("Распознанный текст");
+    let image = Path::new("tests/fixtures/animated.gif");
+
+    let first = read_single_file(image, &config, &api, &logger)
+        .await
+        .unwrap();
+    let second = read_single_file(image, &config, &api, &logger)
+        .await
+        .unwrap();
+
+    assert_eq!(api.requests().len(), 1);
+    assert_eq!(first.files[0].content, second.files[0].content);
+    assert!(!first.files[0].metadata.as_ref().unwrap().cached);
+    assert!(second.files[0].metadata.as_ref().unwrap().cached);
+}
+
+#[tokio::test]
+async fn changed_extraction_settings_miss_the_cache() {
+    let cache = tempfile::tempdir().unwrap();
+    let image = Path::new("tests/fixtures/animated.gif");
+    let api = MockApi::replying("Распознанный текст");
+    let read = |extra: &str| {
+        let config = Config::parse(&format!(
+            "log_file: \"\"\ncache_dir: {:?}\n{extra}",
+            cache.path().display().to_string()
+        ))
+        .unwrap();
+        let api = &api;
+        async move {
+            read_single_file(image, &config, api, &common::logger())
+                .await
+                .unwrap();
+        }
+    };
+
+    read("").await;
+    let mut expected = api.requests().len();
+    for setting in [
+        "ocr_preserve_tables: true",
+        "gif_frames: 2",
+        "pdf_extract_images: true",
+        "pdf_image_fallback: true",
+        "split_pdf_pages: true",
+        "image_resize_threshold_kib: 1",
+        "max_image_dimension: 100",
+        "ocr_temperature: 0",
+    ] {
+        read(setting).await;
+        assert!(api.requests().len() > expected, "{setting} hit the cache");
+        expected = api.requests().len();
+        read(setting).await;
+        assert_eq!(api.requests().len(), expected, "{setting} missed twice");
+    }
+}
+
+#[tokio::test]
+async fn per_file_instructions_are_part_of_the_key() {
+    let cache = tempfile::tempdir().unwrap();
+    let instructions = cache.path().join("instructions.yaml");
+    std::fs::write(&instructions, "animated.gif:\n  ocr: Только заголовки\n").unwrap();
+    let image = Path::new("tests/fixtures/animated.gif");
+    let api = MockApi::replying("Распознанный текст");
+    let config = |extra: &str| {
+        Config::parse(&format!(
+            "log_file: \"\"\ncache_dir: {:?}\n{extra}",
+            cache.path().join("ocr").display().to_string()
+        ))
+        .unwrap()
+    };
+
+    for config in [
+        config(""),
+        config(&format!(
+            "instructions_path: {:?}",
+            instructions.display().to_string()
+        )),
+    ] {
+        read_single_file(image, &config, &api, &common::logger())
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(api.requests().len(), 2);
+}
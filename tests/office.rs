@@ -0,0 +1,81 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use files::office::extract;
+use zip::write::SimpleFileOptions;
+
+fn write_package(path: &Path, parts: &[(&str, &str)]) {
+    let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+    for (name, content) in parts {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn docx_paragraphs_become_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("letter.docx");
+    write_package(
+        &path,
+        &[
+            (
+                "word/document.xml",
+                r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+                  <w:body>
+                    <w:p><w:r><w:t>Договор </w:t></w:r><w:r><w:t>№ 5</w:t></w:r></w:p>
+                    <w:tbl><w:tr><w:tc><w:p><w:r><w:t>Сторона</w:t><w:tab/><w:t>А</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+                  </w:body>
+                </w:document>"#,
+            ),
+            ("word/media/image1.png", "png"),
+        ],
+    );
+
+    let office = extract(&path, "docx").unwrap();
+
+    assert_eq!(office.text, "Договор № 5\nСторона\tА");
+    assert_eq!(office.skipped, ["word/media/image1.png"]);
+}
+
+#[test]
+fn xlsx_cells_keep_their_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("budget.xlsx");
+    write_package(
+        &path,
+        &[
+            (
+                "xl/workbook.xml",
+                r#"<workbook xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+                  <sheets><sheet name="Бюджет" sheetId="1" r:id="rId1"/></sheets>
+                </workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Статья</t></si><si><t>Сумма</t></si></sst>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetData>
+                  <row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1" t="s"><v>1</v></c></row>
+                  <row r="2"><c r="A2" t="inlineStr"><is><t>Аренда</t></is></c><c r="C2"><v>1500</v></c></row>
+                </sheetData></worksheet>"#,
+            ),
+        ],
+    );
+
+    let office = extract(&path, "xlsx").unwrap();
+
+    assert_eq!(
+        office.text,
+        "--- Лист: Бюджет ---\nСтатья\t\tСумма\nАренда\t\t1500"
+    );
+    assert!(office.skipped.is_empty());
+}
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use files::api::{ApiStatusError, ChatApi, ChatParams, OpenRouterClient, Usage};
+use files::clock::MockClock;
+use serde_json::{json, Value};
+use wiremock::matchers::{header, headers, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const COMPLETIONS: &str = "/api/v1/chat/completions";
+
+fn completion(content: &str) -> Value {
+    json!({
+        "choices": [{"message": {"content": content}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15},
+    })
+}
+
+/// A client talking to `server`, with an instant clock for retry waits.
+fn client(server: &MockServer, clock: Arc<MockClock>) -> OpenRouterClient {
+    OpenRouterClient::new("test-key".to_string())
+        .unwrap()
+        .with_base_url(&format!("{}/api/v1", server.uri()))
+        .with_clock(clock)
+}
+
+async fn ask(client: &OpenRouterClient) -> anyhow::Result<files::api::ChatResponse> {
+    client
+        .chat_with_params(
+            "some/model",
+            vec![json!({"role": "user", "content": "Привет"})],
+            &ChatParams::default(),
+        )
+        .await
+}
+
+#[tokio::test]
+async fn successful_reply_is_extracted_with_its_usage() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .and(header("authorization", "Bearer test-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(completion("Здравствуйте")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server, Arc::new(MockClock::new()));
+
+    let response = ask(&client).await.unwrap();
+
+    assert_eq!(response.content, "Здравствуйте");
+    assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+    let expected = Usage {
+        prompt_tokens: 12,
+        completion_tokens: 3,
+        total_tokens: 15,
+    };
+    assert_eq!(response.usage, Some(expected));
+    assert_eq!(client.usage_by_model()["some/model"], expected);
+}
+
+#[tokio::test]
+async fn rate_limited_request_is_retried_after_the_requested_wait() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "7"))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .respond_with(ResponseTemplate::new(200).set_body_json(completion("Со второй попытки")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let clock = Arc::new(MockClock::new());
+    let client = client(&server, clock.clone());
+
+    let response = ask(&client).await.unwrap();
+
+    assert_eq!(response.content, "Со второй попытки");
+    assert_eq!(clock.sleeps(), [Duration::from_secs(7)]);
+}
+
+#[tokio::test]
+async fn bad_request_fails_without_retrying() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .respond_with(ResponseTemplate::new(400).set_body_string("invalid model"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let clock = Arc::new(MockClock::new());
+    let client = client(&server, clock.clone());
+
+    let error = ask(&client).await.unwrap_err();
+
+    let status = error.downcast_ref::<ApiStatusError>().unwrap();
+    assert_eq!(status.status.as_u16(), 400);
+    assert!(error.to_string().contains("invalid model"), "{error}");
+    assert!(clock.sleeps().is_empty());
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_parse_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .respond_with(ResponseTemplate::new(200).set_body_string("{\"choices\": [oops"))
+        .mount(&server)
+        .await;
+    let client = client(&server, Arc::new(MockClock::new()));
+
+    let error = ask(&client).await.unwrap_err();
+
+    assert!(
+        error.to_string().starts_with("Failed to parse"),
+        "{error:#}"
+    );
+}
+
+#[tokio::test]
+async fn compressed_reply_is_counted_on_the_wire_and_decompressed() {
+    use std::io::Write as _;
+
+    let server = MockServer::start().await;
+    let body = completion(&"сжатый ответ ".repeat(200)).to_string();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    let gzipped = encoder.finish().unwrap();
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .and(headers("accept-encoding", vec!["gzip", "br", "deflate"]))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-encoding", "gzip")
+                .insert_header("content-type", "application/json")
+                .set_body_bytes(gzipped.clone()),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server, Arc::new(MockClock::new()));
+
+    let response = ask(&client).await.unwrap();
+
+    assert!(response.content.starts_with("сжатый ответ"));
+    let traffic = client.response_traffic();
+    assert_eq!(traffic.responses, 1);
+    assert_eq!(traffic.wire_bytes, gzipped.len() as u64);
+    assert_eq!(traffic.decoded_bytes, body.len() as u64);
+    assert!(traffic.wire_bytes < traffic.decoded_bytes);
+}
+
+/// Answers requests for `some/model` with `primary` and those for
+/// `backup/model` with a completion, and returns what the client got.
+async fn ask_with_fallback(primary: ResponseTemplate) -> (MockServer, anyhow::Result<String>) {
+    use wiremock::matchers::body_partial_json;
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .and(body_partial_json(json!({"model": "some/model"})))
+        .respond_with(primary)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(COMPLETIONS))
+        .and(body_partial_json(json!({"model": "backup/model"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(completion("Запасная модель")))
+        .mount(&server)
+        .await;
+    let client = client(&server, Arc::new(MockClock::new()))
+        .with_fallbacks("some/model", &["backup/model".to_string()]);
+    let result = ask(&client).await.map(|response| response.content);
+    (server, result)
+}
+
+#[tokio::test]
+async fn unavailable_and_provider_failures_fall_back_to_the_next_model() {
+    let provider_error = json!({"error": {
+        "code": 500,
+        "message": "Upstream error",
+        "metadata": {"provider_name": "SomeProvider", "raw": "boom"},
+    }});
+    for primary in [
+        ResponseTemplate::new(429),
+        ResponseTemplate::new(503),
+        ResponseTemplate::new(529),
+        ResponseTemplate::new(404).set_body_json(json!({"error": {"message": "No such model"}})),
+        ResponseTemplate::new(500).set_body_json(provider_error),
+    ] {
+        let (_server, result) = ask_with_fallback(primary).await;
+
+        assert_eq!(result.unwrap(), "Запасная модель");
+    }
+}
+
+#[tokio::test]
+async fn request_errors_do_not_fall_back() {
+    for status in [400, 401, 402, 413, 500] {
+        let primary = ResponseTemplate::new(status)
+            .set_body_json(json!({"error": {"code": status, "message": "Rejected"}}));
+        let (server, result) = ask_with_fallback(primary).await;
+
+        let error = result.unwrap_err();
+        assert_eq!(
+            error
+                .downcast_ref::<ApiStatusError>()
+                .unwrap()
+                .status
+                .as_u16(),
+            status
+        );
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests
+            .iter()
+            .all(|r| !String::from_utf8_lossy(&r.body).contains("backup/model")));
+    }
+}
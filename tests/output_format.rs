@@ -0,0 +1,87 @@
+mod common;
+
+use common::config;
+use files::markdown::{to_html, to_html_document, to_text};
+
+const SUMMARY: &str = "# Итоги\n\
+\n\
+Договор **подписан** 5 мая, см. [реестр](https://example.com/r?a=1&b=2).\n\
+Поле `file_name` и snake_case_name не *выделяются* случайно.\n\
+\n\
+- Сторона А\n  - ООО <Ромашка>\n\
+- Сторона Б\n\
+\n\
+1. Оплата\n\
+2. Поставка\n\
+\n\
+| Статья | Сумма |\n\
+|:---|---:|\n\
+| Аренда | 1500 |\n\
+\n\
+---\n\
+\n\
+> Важно: срок 30 дней.\n";
+
+#[test]
+fn markdown_is_rendered_to_html() {
+    assert_eq!(
+        to_html(SUMMARY),
+        "<h1>Итоги</h1>\n\
+         <p>Договор <strong>подписан</strong> 5 мая, см. \
+         <a href=\"https://example.com/r?a=1&amp;b=2\">реестр</a>.\n\
+         Поле <code>file_name</code> и snake_case_name не <em>выделяются</em> случайно.</p>\n\
+         <ul>\n<li>Сторона А<ul>\n<li>ООО &lt;Ромашка&gt;</li>\n</ul>\n</li>\n<li>Сторона Б</li>\n</ul>\n\
+         <ol>\n<li>Оплата</li>\n<li>Поставка</li>\n</ol>\n\
+         <table>\n<thead>\n<tr><th>Статья</th><th>Сумма</th></tr>\n</thead>\n\
+         <tbody>\n<tr><td>Аренда</td><td>1500</td></tr>\n</tbody>\n</table>\n\
+         <hr>\n\
+         <blockquote><p>Важно: срок 30 дней.</p></blockquote>\n"
+    );
+}
+
+#[test]
+fn html_document_is_titled_by_the_first_heading() {
+    let page = to_html_document("Вступление\n\n## Раздел *один*\n", "ru");
+
+    assert!(page.starts_with("<!DOCTYPE html>\n<html lang=\"ru\">"));
+    assert!(page.contains("<meta charset=\"utf-8\">"));
+    assert!(page.contains("<title>Раздел один</title>"));
+    assert!(page.contains("<body>\n<p>Вступление</p>\n<h2>Раздел <em>один</em></h2>\n</body>"));
+}
+
+#[test]
+fn markdown_is_stripped_to_plain_text() {
+    assert_eq!(
+        to_text(SUMMARY),
+        "Итоги\n\n\
+         Договор подписан 5 мая, см. реестр (https://example.com/r?a=1&b=2). \
+         Поле file_name и snake_case_name не выделяются случайно.\n\n\
+         • Сторона А\n  • ООО <Ромашка>\n• Сторона Б\n\n\
+         1. Оплата\n2. Поставка\n\n\
+         Статья\tСумма\nАренда\t1500\n\n\
+         Важно: срок 30 дней.\n"
+    );
+}
+
+#[test]
+fn code_blocks_are_kept_verbatim() {
+    let md = "```rust\nlet x = **y**;\n```\n";
+
+    assert_eq!(to_html(md), "<pre><code>let x = **y**;</code></pre>\n");
+    assert_eq!(to_text(md), "let x = **y**;\n");
+}
+
+#[test]
+fn output_path_gets_the_extension_of_the_format() {
+    assert_eq!(config("").output_path, "summary.md");
+    assert_eq!(config("output_format: html").output_path, "summary.html");
+    assert_eq!(config("output_format: text").output_path, "summary.txt");
+    assert_eq!(
+        config("output_format: text\noutput_path: out/report").output_path,
+        "out/report.txt"
+    );
+    assert_eq!(
+        config("output_format: html\noutput_path: report.md").output_path,
+        "report.md"
+    );
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use std::fs;
+
+use files::file_processor::read_all_files;
+use files::logger::{LogStream, Logger};
+
+#[tokio::test]
+async fn previous_summary_inside_files_directory_is_not_read() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "Заметки").unwrap();
+    let output = dir.path().join("summary.md");
+    fs::write(&output, "# Прошлое резюме").unwrap();
+    fs::write(dir.path().join("summary.md.prev"), "# Позапрошлое").unwrap();
+    let log = tempfile::tempdir().unwrap();
+    let log_path = log.path().join("app.log");
+    let logger = Logger::new(log_path.to_str().unwrap(), LogStream::Stderr);
+    let config = common::dir_config(
+        dir.path(),
+        &format!("output_path: {:?}", output.display().to_string()),
+    );
+
+    let outcome = read_all_files(&config, &common::offline_api(), &logger)
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = outcome.files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["notes.txt"]);
+    let log = fs::read_to_string(log_path).unwrap();
+    for skipped in ["summary.md", "summary.md.prev"] {
+        assert!(
+            log.contains(&format!(
+                "Skipping '{skipped}': it is this tool's own output"
+            )),
+            "{log}"
+        );
+    }
+}
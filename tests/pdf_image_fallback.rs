@@ -0,0 +1,143 @@
+mod common;
+
+use std::fs;
+use std::io::{Cursor, Write};
+
+use common::MockApi;
+use files::file_processor::read_single_file;
+use files::logger::{LogStream, Logger};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// Builds a one-page PDF with no text whose page shows a 64x64 image with
+/// the given `/Filter` (`None` for raw samples).
+fn scanned_pdf(filter: Option<&str>) -> Vec<u8> {
+    let pixels = image::RgbImage::from_pixel(64, 64, image::Rgb([200, 200, 200]));
+    let data = match filter {
+        Some("DCTDecode") => {
+            let mut bytes = Cursor::new(Vec::new());
+            pixels
+                .write_to(&mut bytes, image::ImageFormat::Jpeg)
+                .unwrap();
+            bytes.into_inner()
+        }
+        Some("FlateDecode") => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&pixels.into_raw()).unwrap();
+            encoder.finish().unwrap()
+        }
+        // Not a real fax stream: it is never decoded.
+        Some(_) => vec![0; 16],
+        None => pixels.into_raw(),
+    };
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let mut image_dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 64,
+        "Height" => 64,
+        "ColorSpace" => "DeviceRGB",
+        "BitsPerComponent" => 8,
+    };
+    if let Some(filter) = filter {
+        image_dict.set("Filter", filter);
+    }
+    let image_id = doc.add_object(Stream::new(image_dict, data));
+    let content_id = doc.add_object(Stream::new(
+        dictionary! {},
+        b"q 64 0 0 64 0 0 cm /Im1 Do Q".to_vec(),
+    ));
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), 64.into(), 64.into()],
+        "Contents" => content_id,
+        "Resources" => dictionary! { "XObject" => dictionary! { "Im1" => image_id } },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).unwrap();
+    bytes
+}
+
+/// Answers PDF requests with almost nothing and requests with a JPEG or
+/// PNG image with text.
+fn scan_api() -> MockApi {
+    MockApi::new(|messages| {
+        let url = messages[0]["content"][1]["image_url"]["url"]
+            .as_str()
+            .unwrap_or_default();
+        let is_image =
+            url.starts_with("data:image/jpeg;base64,") || url.starts_with("data:image/png;base64,");
+        Ok(common::reply(if is_image {
+            "Текст со скана"
+        } else {
+            "-"
+        }))
+    })
+}
+
+/// Reads `pdf` with `pdf_image_fallback` on, returning its text and the log.
+async fn read_scan(pdf: Vec<u8>) -> (String, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("scan.pdf");
+    fs::write(&path, pdf).unwrap();
+    let log_path = dir.path().join("app.log");
+    let logger = Logger::new(log_path.to_str().unwrap(), LogStream::Stderr);
+
+    let outcome = read_single_file(
+        &path,
+        &common::config("pdf_image_fallback: true"),
+        &scan_api(),
+        &logger,
+    )
+    .await
+    .unwrap();
+    (
+        outcome.files[0].content.clone(),
+        fs::read_to_string(log_path).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn underextracted_pdf_falls_back_to_its_embedded_images() {
+    for filter in [Some("DCTDecode"), Some("FlateDecode"), None] {
+        let (content, log) = read_scan(scanned_pdf(filter)).await;
+
+        assert_eq!(content, "--- Страница 1 ---\nТекст со скана", "{filter:?}");
+        assert!(
+            log.contains("falling back to OCR of its embedded images"),
+            "{log}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn fax_encoded_images_are_skipped_and_logged() {
+    let (content, log) = read_scan(scanned_pdf(Some("CCITTFaxDecode"))).await;
+
+    assert_eq!(content, "-");
+    assert!(
+        log.contains("Skipping 1 embedded image(s) in 'scan.pdf': only JPEG and"),
+        "{log}"
+    );
+    assert!(log.contains("pages are not rasterized"), "{log}");
+}
+
+#[test]
+fn old_key_is_still_accepted() {
+    assert!(common::config("pdf_jpeg_fallback: true").pdf_image_fallback);
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use common::MockApi;
+use files::config::Config;
+use files::file_processor::{read_all_files, read_single_file, split_pdf_pages};
+use lopdf::{dictionary, Document, Object, Stream};
+
+/// Builds a PDF whose page `n` draws the text `Page n`.
+fn pdf_with_pages(count: u32) -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let kids: Vec<Object> = (1..=count)
+        .map(|n| {
+            let content = format!("BT /F1 12 Tf 72 720 Td (Page {n}) Tj ET");
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+            doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                "Contents" => content_id,
+            })
+            .into()
+        })
+        .collect();
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).unwrap();
+    bytes
+}
+
+/// Returns the text drawn by each page of `pdf`.
+fn page_texts(pdf: &[u8]) -> Vec<String> {
+    let doc = Document::load_mem(pdf).unwrap();
+    doc.get_pages()
+        .values()
+        .map(|&id| String::from_utf8_lossy(&doc.get_page_content(id)).into_owned())
+        .map(|content| content.split(['(', ')']).nth(1).unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn each_page_becomes_its_own_pdf() {
+    let pages = split_pdf_pages(&pdf_with_pages(3)).unwrap();
+
+    let texts: Vec<Vec<String>> = pages.iter().map(|pdf| page_texts(pdf)).collect();
+    assert_eq!(texts, [["Page 1"], ["Page 2"], ["Page 3"]]);
+}
+
+#[test]
+fn split_pages_keep_inherited_attributes_and_only_their_own_objects() {
+    let mut doc = Document::load_mem(&pdf_with_pages(3)).unwrap();
+    let page_ids: Vec<_> = doc.get_pages().into_values().collect();
+    for id in &page_ids {
+        doc.get_dictionary_mut(*id).unwrap().remove(b"MediaBox");
+    }
+    let pages_id = doc
+        .catalog()
+        .unwrap()
+        .get(b"Pages")
+        .unwrap()
+        .as_reference()
+        .unwrap();
+    doc.get_dictionary_mut(pages_id)
+        .unwrap()
+        .set("MediaBox", vec![0.into(), 0.into(), 300.into(), 400.into()]);
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).unwrap();
+
+    for (n, pdf) in (1..).zip(split_pdf_pages(&bytes).unwrap()) {
+        let page = Document::load_mem(&pdf).unwrap();
+        let catalog_id = page.trailer.get(b"Root").unwrap().as_reference().unwrap();
+        let pages_id = page
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let id = *page.get_pages().values().next().unwrap();
+        let content_id = page
+            .get_dictionary(id)
+            .unwrap()
+            .get(b"Contents")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let reachable = reachable_objects(&page);
+        for expected in [catalog_id, pages_id, id, content_id] {
+            assert!(reachable.contains(&expected), "{expected:?} unreachable");
+        }
+        let drawn: Vec<String> = reachable
+            .iter()
+            .filter_map(|&id| page.get_object(id).ok()?.as_stream().ok())
+            .map(|stream| String::from_utf8_lossy(&stream.content).into_owned())
+            .filter(|content| content.contains("(Page "))
+            .collect();
+        assert_eq!(drawn.len(), 1);
+        assert!(drawn[0].contains(&format!("(Page {n})")), "{drawn:?}");
+        let media_box = page.get_dictionary(id).unwrap().get(b"MediaBox").unwrap();
+        assert_eq!(media_box.as_array().unwrap()[3].as_i64().unwrap(), 400);
+    }
+}
+
+/// Returns the objects reachable from the trailer's `Root`.
+fn reachable_objects(doc: &Document) -> std::collections::BTreeSet<lopdf::ObjectId> {
+    fn visit(object: &Object, pending: &mut Vec<lopdf::ObjectId>) {
+        match object {
+            Object::Reference(id) => pending.push(*id),
+            Object::Array(items) => items.iter().for_each(|item| visit(item, pending)),
+            Object::Dictionary(dict) => dict.iter().for_each(|(_, value)| visit(value, pending)),
+            Object::Stream(stream) => stream
+                .dict
+                .iter()
+                .for_each(|(_, value)| visit(value, pending)),
+            _ => {}
+        }
+    }
+
+    let mut reachable = std::collections::BTreeSet::new();
+    let mut pending = Vec::new();
+    visit(doc.trailer.get(b"Root").unwrap(), &mut pending);
+    while let Some(id) = pending.pop() {
+        if reachable.insert(id) {
+            visit(doc.get_object(id).unwrap(), &mut pending);
+        }
+    }
+    reachable
+}
+
+/// Reads back the page text of every PDF it is sent, failing on `Page 2`.
+fn page_api() -> MockApi {
+    MockApi::new(|messages| {
+        let url = messages[0]["content"][1]["file"]["file_data"]
+            .as_str()
+            .unwrap();
+        let pdf = BASE64
+            .decode(url.strip_prefix("data:application/pdf;base64,").unwrap())
+            .unwrap();
+        let text = page_texts(&pdf).join(", ");
+        if text == "Page 2" {
+            anyhow::bail!("page too blurry");
+        }
+        Ok(common::reply(&format!("Текст: {text}")))
+    })
+}
+
+fn config(split: bool) -> Config {
+    common::config(&format!("max_concurrency: 2\nsplit_pdf_pages: {split}"))
+}
+
+#[tokio::test]
+async fn pages_are_read_in_order_and_failures_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("report.pdf");
+    std::fs::write(&path, pdf_with_pages(3)).unwrap();
+
+    let outcome = read_single_file(&path, &config(true), &page_api(), &common::logger())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        outcome.files[0].content,
+        "--- Страница 1 ---\nТекст: Page 1\n\n--- Страница 3 ---\nТекст: Page 3"
+    );
+    let metadata = outcome.files[0].metadata.as_ref().unwrap();
+    assert_eq!(metadata.usage.total_tokens, 240);
+}
+
+#[tokio::test]
+async fn whole_pdf_is_sent_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("report.pdf");
+    std::fs::write(&path, pdf_with_pages(3)).unwrap();
+
+    let outcome = read_single_file(&path, &config(false), &page_api(), &common::logger())
+        .await
+        .unwrap();
+
+    assert_eq!(outcome.files[0].content, "Текст: Page 1, Page 2, Page 3");
+}
+
+#[tokio::test]
+async fn pages_stay_within_the_concurrency_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a.pdf", "b.pdf", "c.pdf"] {
+        std::fs::write(dir.path().join(name), pdf_with_pages(3)).unwrap();
+    }
+    let config = common::dir_config(dir.path(), "max_concurrency: 2\nsplit_pdf_pages: true");
+    let api = MockApi::replying("Текст").with_delay(|_| std::time::Duration::from_millis(20));
+
+    read_all_files(&config, &api, &common::logger())
+        .await
+        .unwrap();
+
+    assert_eq!(api.requests().len(), 9);
+    assert!(
+        api.peak_in_flight() <= 2,
+        "{} requests in flight",
+        api.peak_in_flight()
+    );
+}
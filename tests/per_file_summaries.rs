@@ -0,0 +1,69 @@
+mod common;
+
+use common::MockApi;
+use files::config::Config;
+use files::summary::{per_file_summaries_dir, save_per_file_summaries, SummaryInput};
+
+/// Answers with the file header found in the user message.
+fn echo_api() -> MockApi {
+    MockApi::new(|messages| {
+        let user = messages[1]["content"].as_str().unwrap_or_default();
+        let header = user.lines().find(|l| l.starts_with("=== File: ")).unwrap();
+        Ok(common::reply(&format!("# {header}\n")))
+    })
+}
+
+#[tokio::test]
+async fn each_file_gets_its_own_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("summary.md");
+    let config = Config::parse(&format!(
+        "log_file: \"\"\nper_file_summaries: true\noutput_path: {:?}",
+        output.display().to_string()
+    ))
+    .unwrap();
+    let text = "=== File: a.txt ===\nпервый файл\n\n=== File: docs/b.txt ===\nвторой\n\n";
+    let input = SummaryInput {
+        combined_text: text.to_string(),
+        total_words: 3,
+        weighted_words: 3.0,
+        file_count: 2,
+    };
+
+    save_per_file_summaries(&echo_api(), &config, &input, &common::logger())
+        .await
+        .unwrap();
+
+    let summaries = per_file_summaries_dir(&config.output_path);
+    assert_eq!(summaries, dir.path().join("summaries"));
+    let read = |name: &str| std::fs::read_to_string(summaries.join(name)).unwrap();
+    assert_eq!(read("a.txt.md"), "# === File: a.txt ===\n");
+    assert_eq!(read("docs_b.txt.md"), "# === File: docs/b.txt ===\n");
+}
+
+#[tokio::test]
+async fn names_that_map_to_the_same_file_get_a_suffix() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("summary.md");
+    let config = Config::parse(&format!(
+        "log_file: \"\"\nper_file_summaries: true\noutput_path: {:?}",
+        output.display().to_string()
+    ))
+    .unwrap();
+    let text = "=== File: a/b.txt ===\nпервый\n\n=== File: a_b.txt ===\nвторой\n\n";
+    let input = SummaryInput {
+        combined_text: text.to_string(),
+        total_words: 2,
+        weighted_words: 2.0,
+        file_count: 2,
+    };
+
+    save_per_file_summaries(&echo_api(), &config, &input, &common::logger())
+        .await
+        .unwrap();
+
+    let summaries = per_file_summaries_dir(&config.output_path);
+    let read = |name: &str| std::fs::read_to_string(summaries.join(name)).unwrap();
+    assert_eq!(read("a_b.txt.md"), "# === File: a/b.txt ===\n");
+    assert_eq!(read("a_b.txt (2).md"), "# === File: a_b.txt ===\n");
+}
@@ -0,0 +1,32 @@
+use std::cell::Cell;
+
+use files::progress::{ProgressDisplay, ProgressEvent};
+
+#[test]
+fn console_lines_are_printed_while_the_bar_runs() {
+    let display = ProgressDisplay::default();
+    let printed = Cell::new(0);
+
+    display.suspend(|| printed.set(printed.get() + 1));
+    display.update(&ProgressEvent::FileStarted {
+        file: "a.png".to_string(),
+        index: 1,
+        total: 2,
+    });
+    display.suspend(|| printed.set(printed.get() + 1));
+    display.update(&ProgressEvent::FileFinished {
+        file: "a.png".to_string(),
+        ok: true,
+        secs: 0.1,
+    });
+    display.update(&ProgressEvent::FileSkipped {
+        file: "b.bin".to_string(),
+    });
+    display.update(&ProgressEvent::PhaseFinished {
+        phase: "extraction".to_string(),
+        secs: 0.2,
+    });
+    display.suspend(|| printed.set(printed.get() + 1));
+
+    assert_eq!(printed.get(), 3);
+}
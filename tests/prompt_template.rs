@@ -0,0 +1,43 @@
+use files::config::Config;
+use files::prompt_template::{PromptTemplate, SUMMARY_PLACEHOLDERS, SUMMARY_REQUIRED};
+
+#[test]
+fn templates_load_from_files_or_inline() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("summary.txt");
+    std::fs::write(&path, "Маркированный список, {target_words} слов.").unwrap();
+
+    let from_file = PromptTemplate::load(
+        "summary_prompt_template",
+        path.to_str().unwrap(),
+        SUMMARY_PLACEHOLDERS,
+        SUMMARY_REQUIRED,
+    )
+    .unwrap();
+    assert_eq!(
+        from_file.render(&[("target_words", "120".to_string())]),
+        "Маркированный список, 120 слов."
+    );
+
+    let inline =
+        Config::parse(r#"ocr_prompt_template: 'Bullets only for {filename}, JSON like {"a": 1}'"#)
+            .unwrap();
+    let template = inline.ocr_prompt.unwrap();
+    assert_eq!(
+        template.render(&[("filename", "scan.png".to_string())]),
+        "Bullets only for scan.png, JSON like {\"a\": 1}"
+    );
+}
+
+#[test]
+fn unknown_and_missing_placeholders_fail_at_load() {
+    let error =
+        Config::parse("summary_prompt_template: 'About {target_words} words, {foo}'").unwrap_err();
+    assert!(
+        error.to_string().contains("unknown placeholder {foo}"),
+        "{error}"
+    );
+
+    let error = Config::parse("summary_prompt_template: 'Short bullets'").unwrap_err();
+    assert!(error.to_string().contains("{target_words}"), "{error}");
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use files::api::{ChatApi, ChatParams, OpenRouterClient, RateLimiter};
+use files::clock::{Clock, MockClock};
+use serde_json::json;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn zero_means_no_limit() {
+    assert!(RateLimiter::per_minute(0).is_none());
+}
+
+#[test]
+fn simultaneous_callers_get_evenly_spaced_slots() {
+    let limiter = RateLimiter::per_minute(30).unwrap();
+    let now = Instant::now();
+
+    let waits: Vec<u64> = (0..3).map(|_| limiter.reserve(now).as_secs()).collect();
+
+    assert_eq!(waits, [0, 2, 4]);
+}
+
+#[test]
+fn idle_time_does_not_build_up_a_burst() {
+    let limiter = RateLimiter::per_minute(60).unwrap();
+    let start = Instant::now();
+    limiter.reserve(start);
+
+    let later = start + Duration::from_secs(600);
+
+    assert_eq!(limiter.reserve(later), Duration::ZERO);
+    assert_eq!(limiter.reserve(later), Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn retries_wait_for_the_limiter_on_top_of_the_backoff() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{"message": {"content": "ok"}, "finish_reason": "stop"}],
+        })))
+        .mount(&server)
+        .await;
+    let clock = Arc::new(MockClock::new());
+    let client = OpenRouterClient::new("test-key".to_string())
+        .unwrap()
+        .with_base_url(&server.uri())
+        .with_clock(clock.clone())
+        .with_requests_per_minute(20);
+
+    for _ in 0..2 {
+        client
+            .chat_with_params(
+                "some/model",
+                vec![json!({"role": "user", "content": "Привет"})],
+                &ChatParams::default(),
+            )
+            .await
+            .unwrap();
+    }
+
+    // The 429's zero Retry-After, then 3s slots for the retry and the
+    // second request.
+    assert_eq!(
+        clock.sleeps(),
+        [
+            Duration::ZERO,
+            Duration::from_secs(3),
+            Duration::from_secs(3)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn simultaneous_requests_reserve_spaced_starts() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{"message": {"content": "ok"}, "finish_reason": "stop"}],
+        })))
+        .mount(&server)
+        .await;
+    let clock = Arc::new(MockClock::new());
+    let client = OpenRouterClient::new("test-key".to_string())
+        .unwrap()
+        .with_base_url(&server.uri())
+        .with_clock(clock.clone())
+        .with_min_request_interval(Duration::from_secs(2));
+    let params = ChatParams::default();
+    let start = clock.now();
+
+    let replies = futures::future::join_all((0..3).map(|_| {
+        client.chat_with_params(
+            "some/model",
+            vec![json!({"role": "user", "content": "Привет"})],
+            &params,
+        )
+    }))
+    .await;
+
+    assert!(replies.iter().all(Result::is_ok));
+    // None has finished when the others start, yet each waits for its slot.
+    assert_eq!(
+        clock.sleeps(),
+        [Duration::from_secs(2), Duration::from_secs(2)]
+    );
+    assert_eq!(clock.now() - start, Duration::from_secs(4));
+}
+
+#[tokio::test]
+async fn interval_is_measured_from_the_end_of_a_long_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({
+                    "choices": [{"message": {"content": "ok"}, "finish_reason": "stop"}],
+                }))
+                .set_delay(Duration::from_millis(300)),
+        )
+        .mount(&server)
+        .await;
+    let client = OpenRouterClient::new("test-key".to_string())
+        .unwrap()
+        .with_base_url(&server.uri())
+        .with_min_request_interval(Duration::from_millis(200));
+    let started = Instant::now();
+
+    for _ in 0..2 {
+        client
+            .chat_with_params(
+                "some/model",
+                vec![json!({"role": "user", "content": "Привет"})],
+                &ChatParams::default(),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Two 300 ms requests with a 200 ms pause between them; spacing the
+    // starts alone would need only 600 ms.
+    assert!(started.elapsed() >= Duration::from_millis(800));
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use std::fs;
+
+use files::file_processor::read_all_files;
+
+#[tokio::test]
+async fn unsupported_files_are_counted_apart_from_failures() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "заметки").unwrap();
+    fs::write(dir.path().join("model.stl"), [0u8, 1, 2, 3]).unwrap();
+    fs::write(dir.path().join("archive.7z"), [0u8, 1, 2, 3]).unwrap();
+    // Needs an OCR request, which the empty cassette fails.
+    fs::write(dir.path().join("scan.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    let config = common::dir_config(dir.path(), "");
+    let api = common::offline_api();
+
+    let outcome = read_all_files(&config, &api, &common::logger())
+        .await
+        .unwrap();
+
+    assert_eq!(outcome.candidates, 4);
+    assert_eq!(outcome.unsupported, 2);
+    assert_eq!(outcome.counts(), "1 processed, 2 unsupported, 1 failed");
+}
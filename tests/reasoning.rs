@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::Path;
+
+use files::api::ChatResponse;
+use files::cassette::CassetteClient;
+use files::config::Config;
+use files::logger::{LogStream, Logger};
+use files::summary::{self, SummaryInput};
+use serde_json::json;
+
+#[test]
+fn reads_plain_response() {
+    let response = ChatResponse::from_json(&json!({
+        "choices": [{
+            "message": {"role": "assistant", "content": "# Резюме"},
+            "finish_reason": "stop"
+        }]
+    }))
+    .unwrap();
+
+    assert_eq!(response.content, "# Резюме");
+    assert_eq!(response.reasoning, None);
+}
+
+#[test]
+fn reads_response_with_reasoning() {
+    let response = ChatResponse::from_json(&json!({
+        "choices": [{
+            "message": {
+                "role": "assistant",
+                "content": "# Резюме",
+                "reasoning": "Сначала выделю главное."
+            },
+            "finish_reason": "stop"
+        }]
+    }))
+    .unwrap();
+
+    assert_eq!(response.content, "# Резюме");
+    assert_eq!(
+        response.reasoning.as_deref(),
+        Some("Сначала выделю главное.")
+    );
+}
+
+/// Summarizes a one-file input against a cassette whose answer carries
+/// reasoning and returns the written summary.
+async fn summarize_with_reasoning(dir: &Path, include_reasoning: bool) -> String {
+    let cassette = dir.join("cassette.json");
+    fs::write(
+        &cassette,
+        json!([{
+            "request": {"model": "deepseek/deepseek-chat", "messages": []},
+            "response": {
+                "content": "# Резюме\n\n## Заключение\nВсё.",
+                "finish_reason": "stop",
+                "reasoning": "Сначала выделю главное."
+            }
+        }])
+        .to_string(),
+    )
+    .unwrap();
+    let combined = dir.join("combined.txt");
+    fs::write(&combined, "=== File: a.txt ===\nТекст.\n").unwrap();
+    let output = dir.join("summary.md");
+    let config = Config::parse(&format!(
+        "output_path: {}\nlog_file: \"\"\ninclude_reasoning: {include_reasoning}\n",
+        output.display()
+    ))
+    .unwrap();
+    let api = CassetteClient::replay(&cassette).unwrap();
+    let input = SummaryInput::from_combined_file(combined.to_str().unwrap()).unwrap();
+
+    summary::generate_and_save(&api, &config, &input, &Logger::new("", LogStream::Split))
+        .await
+        .unwrap();
+    fs::read_to_string(output).unwrap()
+}
+
+#[tokio::test]
+async fn drops_reasoning_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let written = summarize_with_reasoning(dir.path(), false).await;
+    assert!(!written.contains("Сначала выделю главное."));
+}
+
+#[tokio::test]
+async fn appends_reasoning_when_enabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let written = summarize_with_reasoning(dir.path(), true).await;
+    assert!(written.starts_with("# Резюме"));
+    assert!(written.contains("<details>"));
+    assert!(written.contains("Сначала выделю главное."));
+}
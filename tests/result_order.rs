@@ -0,0 +1,65 @@
+mod common;
+
+use std::time::Duration;
+
+use chrono::Utc;
+use common::MockApi;
+use files::file_processor::read_all_files;
+use files::manifest::Manifest;
+use files::summary::SummaryInput;
+
+/// Names of the PDFs to OCR, in the order their extractions finish.
+const COMPLETION_ORDER: [&str; 4] = ["d.pdf", "b.pdf", "c.pdf", "a.pdf"];
+
+/// Answers with the name of the PDF it was sent, delaying each reply so that
+/// extractions finish in [`COMPLETION_ORDER`] rather than by name.
+fn delaying_api() -> MockApi {
+    let filename = |messages: &[serde_json::Value]| {
+        messages[0]["content"][1]["file"]["filename"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    MockApi::new(move |messages| Ok(common::reply(&format!("Текст {}", filename(messages)))))
+        .with_delay(move |messages| {
+            let name = filename(messages);
+            let rank = COMPLETION_ORDER.iter().position(|n| *n == name).unwrap();
+            Duration::from_millis(20 * rank as u64)
+        })
+}
+
+#[tokio::test]
+async fn results_are_sorted_by_name_whatever_the_completion_order() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in COMPLETION_ORDER {
+        std::fs::write(dir.path().join(name), b"%PDF-1.4").unwrap();
+    }
+    std::fs::write(dir.path().join("bb.txt"), "Локальный текст").unwrap();
+    let config = common::dir_config(dir.path(), "max_concurrency: 5");
+
+    let api = delaying_api();
+
+    let outcome = read_all_files(&config, &api, &common::logger())
+        .await
+        .unwrap();
+
+    let finished: Vec<String> = api
+        .requests()
+        .iter()
+        .map(|r| r.messages[0]["content"][1]["file"]["filename"].to_string())
+        .collect();
+    assert_eq!(finished, COMPLETION_ORDER.map(|n| format!("{n:?}")));
+
+    let expected = ["a.pdf", "b.pdf", "bb.txt", "c.pdf", "d.pdf"];
+    let manifest = Manifest::new(&config, &outcome.files, Utc::now());
+    let names: Vec<&str> = manifest.files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, expected);
+
+    let combined = SummaryInput::from_files(&outcome.files).combined_text;
+    let headers: Vec<&str> = combined
+        .lines()
+        .filter_map(|line| line.strip_prefix("=== File: "))
+        .filter_map(|line| line.strip_suffix(" ==="))
+        .collect();
+    assert_eq!(headers, expected);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::path::Path;
+
+use common::MockApi;
+use files::api::OpenRouterClient;
+use files::config::Config;
+use files::file_processor::read_single_file;
+use files::summary::{generate_and_save, SummaryInput};
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn ocr_requests_use_the_ocr_sampling_fields() {
+    let config = common::config("ocr_temperature: 0\nocr_top_p: 0.9\nocr_max_tokens: 4000");
+    let api = MockApi::replying("Распознанный текст");
+
+    read_single_file(
+        Path::new("tests/fixtures/animated.gif"),
+        &config,
+        &api,
+        &common::logger(),
+    )
+    .await
+    .unwrap();
+
+    let requests = api.requests();
+    assert!(!requests.is_empty());
+    for p in requests.iter().map(|r| &r.params) {
+        assert_eq!(
+            (p.temperature, p.top_p, p.max_tokens),
+            (Some(0.0), Some(0.9), Some(4000))
+        );
+    }
+}
+
+#[tokio::test]
+async fn summary_requests_carry_seed_and_temperature() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({"seed": 42, "temperature": 0.0})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{"message": {"content": "Краткое резюме."}, "finish_reason": "stop"}],
+        })))
+        .expect(1..)
+        .mount(&server)
+        .await;
+    let api = OpenRouterClient::new("test-key".to_string())
+        .unwrap()
+        .with_retries(0)
+        .with_base_url(&server.uri());
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("summary.md");
+    let config = common::config(&format!(
+        "seed: 42\nsummary_temperature: 0\noutput_path: {:?}",
+        output.display().to_string()
+    ));
+    let input = SummaryInput {
+        combined_text: "=== File: a.txt ===\nДва слова\n".to_string(),
+        total_words: 2,
+        weighted_words: 2.0,
+        file_count: 1,
+    };
+
+    generate_and_save(&api, &config, &input, &common::logger())
+        .await
+        .unwrap();
+
+    assert!(std::fs::read_to_string(output)
+        .unwrap()
+        .contains("Краткое резюме."));
+    let requests = server.received_requests().await.unwrap();
+    for request in requests {
+        let body: serde_json::Value = request.body_json().unwrap();
+        assert_eq!(
+            (&body["seed"], &body["temperature"]),
+            (&json!(42), &json!(0.0))
+        );
+    }
+}
+
+#[test]
+fn out_of_range_sampling_values_are_rejected() {
+    assert!(Config::parse("ocr_top_p: 1.5").is_err());
+    assert!(Config::parse("summary_temperature: 3").is_err());
+    assert!(Config::parse("summary_top_p: 1").is_ok());
+}
@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// The config.yaml shipped with the repository, which sets `files_directory`.
+const DEFAULT_CONFIG: &str = include_str!("../config.yaml");
+
+/// Runs the binary with `args` in `dir`, which holds the default config.yaml
+/// and a `report.txt` to summarize.
+fn run_in(dir: &Path, args: &[&str]) -> Output {
+    std::fs::write(dir.join("config.yaml"), DEFAULT_CONFIG).unwrap();
+    std::fs::write(dir.join("report.txt"), "Квартальный отчёт.").unwrap();
+    Command::new(env!("CARGO_BIN_EXE_files"))
+        .args(args)
+        .current_dir(dir)
+        .env_remove("OPENROUTER_API_KEY")
+        .output()
+        .unwrap()
+}
+
+fn assert_plans_report(output: &Output) {
+    let log = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(output.status.success(), "{log}");
+    assert!(log.contains("Dry run: planning 'report.txt'"), "{log}");
+}
+
+#[test]
+fn file_argument_overrides_the_configured_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    assert_plans_report(&run_in(dir.path(), &["report.txt", "--dry-run"]));
+}
+
+#[test]
+fn input_file_overrides_the_configured_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    assert_plans_report(&run_in(
+        dir.path(),
+        &["--input-file", "report.txt", "--dry-run"],
+    ));
+}
+
+#[test]
+fn file_argument_conflicts_with_files_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    for args in [
+        ["report.txt", "--files-dir", "input"],
+        ["--input-file=report.txt", "--files-dir", "input"],
+    ] {
+        let output = run_in(dir.path(), &args);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+    }
+}
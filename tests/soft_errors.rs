@@ -0,0 +1,56 @@
+use files::api::{is_model_error, is_retryable, ChatResponse, SoftApiError};
+use serde_json::json;
+
+#[test]
+fn empty_reply_with_overloaded_note_is_retryable() {
+    let error = ChatResponse::from_json(&json!({
+        "choices": [{
+            "message": {"role": "assistant", "content": ""},
+            "finish_reason": "error",
+            "error": {"code": 502, "message": "Model is overloaded, please try again"}
+        }]
+    }))
+    .unwrap_err();
+
+    assert!(is_retryable(&error));
+    let soft = error.downcast_ref::<SoftApiError>().unwrap();
+    assert_eq!(soft.message, "Model is overloaded, please try again");
+}
+
+#[test]
+fn top_level_error_without_choices_is_retryable() {
+    let error = ChatResponse::from_json(&json!({
+        "error": {"message": "overloaded"}
+    }))
+    .unwrap_err();
+
+    assert!(is_retryable(&error));
+    assert_eq!(
+        error.to_string(),
+        "OpenRouter returned no content: overloaded"
+    );
+}
+
+#[test]
+fn content_wins_over_an_error_note() {
+    let response = ChatResponse::from_json(&json!({
+        "choices": [{
+            "message": {"role": "assistant", "content": "Текст"},
+            "finish_reason": "stop",
+            "error": {"message": "overloaded"}
+        }]
+    }))
+    .unwrap();
+
+    assert_eq!(response.content, "Текст");
+}
+
+#[test]
+fn soft_errors_trigger_a_model_fallback() {
+    let error = ChatResponse::from_json(&json!({"error": {"message": "overloaded"}})).unwrap_err();
+
+    assert!(is_model_error(&error));
+    assert!(!is_model_error(&anyhow::anyhow!(
+        "Failed to parse OpenRouter response"
+    )));
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::config;
+use files::file_processor::{read_stdin, STDIN_NAME};
+use files::summary::SummaryInput;
+
+#[test]
+fn stdin_is_read_as_one_file() {
+    let text = "\u{feff}Первая строка.\nВторая строка.\n";
+
+    let outcome = read_stdin(text.as_bytes(), &config(""), &common::logger()).unwrap();
+
+    assert_eq!(outcome.candidates, 1);
+    let [file] = outcome.files.as_slice() else {
+        panic!("expected one file");
+    };
+    assert_eq!(file.name, STDIN_NAME);
+    assert_eq!(file.content, "Первая строка.\nВторая строка.\n");
+    let input = SummaryInput::from_files(&outcome.files);
+    assert_eq!((input.file_count, input.total_words), (1, 4));
+    assert!(input.combined_text.contains("=== File: stdin ==="));
+}
+
+#[test]
+fn stdin_honours_max_lines_per_file() {
+    let text = "1\n2\n3\n4\n5\n6\n";
+
+    let outcome = read_stdin(
+        text.as_bytes(),
+        &config("max_lines_per_file: 2"),
+        &common::logger(),
+    )
+    .unwrap();
+
+    assert_eq!(outcome.files[0].content, "1\n[... 4 lines omitted ...]\n6");
+}
+
+#[test]
+fn invalid_utf8_is_rejected_unless_repaired() {
+    let bytes: &[u8] = b"ok \xff";
+    let logger = common::logger();
+
+    assert!(read_stdin(bytes, &config("repair_invalid_utf8: false"), &logger).is_err());
+    let repaired = read_stdin(bytes, &config("repair_invalid_utf8: true"), &logger).unwrap();
+    assert_eq!(repaired.files[0].content, "ok \u{fffd}");
+}
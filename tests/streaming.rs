@@ -0,0 +1,125 @@
+use std::sync::{Arc, Mutex};
+
+use files::api::{
+    ChatApi, ChatParams, ChatResponse, OpenRouterClient, SoftApiError, StreamAccumulator,
+};
+use files::clock::MockClock;
+use serde_json::json;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Feeds `lines` and returns the reply text printed along the way and the
+/// reassembled response.
+fn replay(lines: &[&str]) -> (String, anyhow::Result<ChatResponse>) {
+    let mut stream = StreamAccumulator::default();
+    let mut printed = String::new();
+    for line in lines {
+        if let Some(text) = stream.feed_line(line).unwrap() {
+            printed.push_str(&text);
+        }
+    }
+    (printed, ChatResponse::from_json(&stream.into_json()))
+}
+
+#[test]
+fn streamed_reply_matches_the_plain_response() {
+    let (printed, streamed) = replay(&[
+        ": OPENROUTER PROCESSING",
+        "",
+        r#"data: {"choices":[{"delta":{"role":"assistant","content":""}}]}"#,
+        r#"data: {"choices":[{"delta":{"reasoning":"План"}}]}"#,
+        r##"data: {"choices":[{"delta":{"content":"# Итоги\n\nДоговор "}}]}"##,
+        "\r",
+        r#"data: {"choices":[{"delta":{"content":"подписан."},"finish_reason":"stop"}]}"#,
+        r#"data: {"choices":[],"usage":{"prompt_tokens":50,"completion_tokens":7,"total_tokens":57}}"#,
+        "data: [DONE]",
+    ]);
+    let plain = ChatResponse::from_json(&json!({
+        "choices": [{
+            "message": {"content": "# Итоги\n\nДоговор подписан.", "reasoning": "План"},
+            "finish_reason": "stop",
+        }],
+        "usage": {"prompt_tokens": 50, "completion_tokens": 7, "total_tokens": 57},
+    }))
+    .unwrap();
+
+    let streamed = streamed.unwrap();
+    assert_eq!(printed, "# Итоги\n\nДоговор подписан.");
+    assert_eq!(streamed.content, plain.content);
+    assert_eq!(streamed.reasoning, plain.reasoning);
+    assert_eq!(streamed.finish_reason, plain.finish_reason);
+    assert_eq!(streamed.usage, plain.usage);
+}
+
+#[test]
+fn error_chunk_becomes_a_soft_error() {
+    let (_, streamed) = replay(&[
+        r#"data: {"error":{"message":"Model is overloaded"},"choices":[{"delta":{"content":""},"finish_reason":"error"}]}"#,
+        "data: [DONE]",
+    ]);
+
+    let error = streamed.unwrap_err();
+    assert!(error.is::<SoftApiError>());
+    assert!(error.to_string().contains("Model is overloaded"));
+}
+
+#[test]
+fn done_marker_ends_the_stream() {
+    let mut stream = StreamAccumulator::default();
+    stream.feed_line("data: [DONE]").unwrap();
+
+    assert!(stream.is_done());
+}
+
+/// A server-sent event stream of `chunks`, ending with `[DONE]`.
+fn event_stream(chunks: &[serde_json::Value]) -> ResponseTemplate {
+    let mut body: String = chunks.iter().map(|c| format!("data: {c}\n\n")).collect();
+    body.push_str("data: [DONE]\n\n");
+    ResponseTemplate::new(200).set_body_raw(body, "text/event-stream")
+}
+
+#[tokio::test]
+async fn retried_stream_marks_the_discarded_text() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(event_stream(&[
+            json!({"choices": [{"delta": {"content": "Недописанный "}}]}),
+            json!({"error": {"message": "Model is overloaded"}, "choices": [{"delta": {}, "finish_reason": "error"}]}),
+        ]))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(event_stream(&[
+            json!({"choices": [{"delta": {"content": "Итог."}, "finish_reason": "stop"}]}),
+        ]))
+        .mount(&server)
+        .await;
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let client = OpenRouterClient::new("test-key".to_string())
+        .unwrap()
+        .with_base_url(&server.uri())
+        .with_clock(Arc::new(MockClock::new()))
+        .with_retries(1)
+        .with_stream_output(output.clone());
+
+    let params = ChatParams {
+        stream: true,
+        ..ChatParams::default()
+    };
+    let response = client
+        .chat_with_params(
+            "some/model",
+            vec![json!({"role": "user", "content": "Привет"})],
+            &params,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.content, "Итог.");
+    let printed = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    assert_eq!(
+        printed,
+        "Недописанный \n--- reply interrupted; the text above is discarded ---\nИтог.\n"
+    );
+}
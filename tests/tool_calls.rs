@@ -0,0 +1,78 @@
+use std::fs;
+
+use files::api::ChatResponse;
+use files::cassette::CassetteClient;
+use files::config::Config;
+use files::file_processor::read_single_file;
+use files::logger::{LogStream, Logger};
+use serde_json::json;
+
+#[test]
+fn reads_tool_calls_without_content() {
+    let response = ChatResponse::from_json(&json!({
+        "choices": [{
+            "message": {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {
+                        "name": "record_invoice",
+                        "arguments": "{\"number\": \"42\", \"total\": 100.5}"
+                    }
+                }]
+            },
+            "finish_reason": "tool_calls"
+        }]
+    }))
+    .unwrap();
+
+    assert_eq!(response.content, "");
+    assert_eq!(response.tool_calls.len(), 1);
+    assert_eq!(response.tool_calls[0].name, "record_invoice");
+    assert_eq!(
+        response.tool_calls[0].arguments,
+        json!({"number": "42", "total": 100.5})
+    );
+}
+
+#[tokio::test]
+async fn extraction_tools_fill_file_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("invoice.txt");
+    fs::write(&path, "Счёт № 42 на сумму 100,50").unwrap();
+    let cassette = dir.path().join("cassette.json");
+    fs::write(
+        &cassette,
+        json!([{
+            "request": {"model": "ocr", "messages": []},
+            "response": {
+                "content": "",
+                "finish_reason": "tool_calls",
+                "tool_calls": [{"name": "record_invoice", "arguments": {"number": "42"}}]
+            }
+        }])
+        .to_string(),
+    )
+    .unwrap();
+    let config = Config::parse(
+        "log_file: \"\"\n\
+         ocr_model: ocr\n\
+         extraction_tools:\n\
+         \x20 - name: record_invoice\n\
+         \x20   parameters: {type: object, properties: {number: {type: string}}}\n",
+    )
+    .unwrap();
+    let logger = Logger::new("", LogStream::Split);
+    let api = CassetteClient::replay(&cassette).unwrap();
+
+    let outcome = read_single_file(&path, &config, &api, &logger)
+        .await
+        .unwrap();
+
+    let fields = &outcome.files[0].fields;
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].arguments, json!({"number": "42"}));
+    assert_eq!(api.remaining(), 0);
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use files::api::{ModelPricing, Usage};
+use files::profile::usage_report;
+
+fn usage(prompt_tokens: u64, completion_tokens: u64) -> Usage {
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    }
+}
+
+#[test]
+fn usage_is_totaled_and_priced_per_model() {
+    let usage = HashMap::from([
+        ("vision".to_string(), usage(1000, 200)),
+        ("text".to_string(), usage(3000, 500)),
+    ]);
+    let pricing = HashMap::from([(
+        "text".to_string(),
+        ModelPricing {
+            prompt: 1.0,
+            completion: 2.0,
+        },
+    )]);
+
+    assert_eq!(
+        usage_report(&usage, &pricing),
+        [
+            "Token usage: 4700 tokens (4000 prompt, 700 completion), ~$0.0040",
+            "  text: 3500 tokens (3000 prompt, 500 completion), ~$0.0040",
+            "  vision: 1200 tokens (1000 prompt, 200 completion)",
+        ]
+    );
+    assert!(usage_report(&HashMap::new(), &pricing).is_empty());
+}
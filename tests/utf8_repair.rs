@@ -0,0 +1,61 @@
+mod common;
+
+use std::fs;
+
+use files::file_processor::read_single_file;
+use files::sanitize::{repair_json_escapes, repair_utf8};
+
+/// "ok", a truncated two-byte sequence, "mid", a stray continuation byte, "end".
+const BROKEN: &[u8] = b"ok \xD0 mid \x80 end";
+
+#[test]
+fn invalid_sequences_become_replacement_characters() {
+    let (text, repairs) = repair_utf8(BROKEN);
+    assert_eq!(text, "ok \u{FFFD} mid \u{FFFD} end");
+    assert_eq!(repairs, 2);
+
+    let (text, repairs) = repair_utf8("валидный".as_bytes());
+    assert_eq!(text, "валидный");
+    assert_eq!(repairs, 0);
+}
+
+#[test]
+fn lone_surrogate_escapes_are_replaced() {
+    let (json, repairs) = repair_json_escapes(r#"{"a":"x\ud83d y","b":"😀","c":"\\ud83d"}"#);
+    assert_eq!(repairs, 1);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["a"], "x\u{FFFD} y");
+    assert_eq!(value["b"], "\u{1F600}");
+    assert_eq!(value["c"], r"\ud83d");
+}
+
+#[tokio::test]
+async fn text_file_with_broken_bytes_is_repaired() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.txt");
+    fs::write(&path, BROKEN).unwrap();
+    let config = common::config("");
+    let logger = common::logger();
+
+    let outcome = read_single_file(&path, &config, &common::offline_api(), &logger)
+        .await
+        .unwrap();
+
+    assert_eq!(outcome.files[0].content, "ok \u{FFFD} mid \u{FFFD} end");
+}
+
+#[tokio::test]
+async fn broken_bytes_fail_the_file_when_repair_is_off() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.txt");
+    fs::write(&path, BROKEN).unwrap();
+    let config = common::config("repair_invalid_utf8: false");
+    let logger = common::logger();
+
+    let outcome = read_single_file(&path, &config, &common::offline_api(), &logger)
+        .await
+        .unwrap();
+
+    assert!(outcome.files.is_empty());
+    assert_eq!(outcome.failures.len(), 1);
+}
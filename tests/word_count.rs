@@ -0,0 +1,66 @@
+mod common;
+
+use common::MockApi;
+use files::config::Config;
+use files::summary::{generate_and_save, SummaryInput};
+use serde_json::Value;
+
+fn words(word: &str, count: usize) -> String {
+    vec![word; count].join(" ")
+}
+
+/// Summarizes a 1000-word input at 10% (a target of 100 words) and returns
+/// the saved summary and the messages of every request.
+async fn summarize(replies: &[String]) -> (String, Vec<Vec<Value>>) {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("summary.md");
+    let config = Config::parse(&format!(
+        "log_file: \"\"\ncompress_summary: 10\noutput_path: {:?}",
+        output.display().to_string()
+    ))
+    .unwrap();
+    let input = SummaryInput {
+        combined_text: format!("=== File: a.txt ===\n{}\n", words("текст", 1000)),
+        total_words: 1000,
+        weighted_words: 1000.0,
+        file_count: 1,
+    };
+    let api = MockApi::scripted(replies);
+
+    generate_and_save(&api, &config, &input, &common::logger())
+        .await
+        .unwrap();
+
+    let summary = std::fs::read_to_string(output).unwrap();
+    let requests = api.requests().into_iter().map(|r| r.messages).collect();
+    (summary, requests)
+}
+
+#[tokio::test]
+async fn summary_within_tolerance_is_kept() {
+    let (summary, requests) = summarize(&[words("итог", 120)]).await;
+
+    assert_eq!(requests.len(), 1);
+    assert_eq!(summary.split_whitespace().count(), 120);
+}
+
+#[tokio::test]
+async fn overlong_summary_is_requested_again() {
+    let (summary, requests) = summarize(&[words("длинно", 300), words("итог", 100)]).await;
+
+    assert_eq!(requests.len(), 2);
+    let correction = requests[1].last().unwrap()["content"].as_str().unwrap();
+    assert!(correction.contains("300 слов"), "{correction}");
+    assert!(correction.contains("примерно из 100 слов"), "{correction}");
+    assert_eq!(summary.split_whitespace().count(), 100);
+    assert!(summary.starts_with("итог"));
+}
+
+#[tokio::test]
+async fn closer_attempt_wins_when_both_miss() {
+    let (summary, requests) = summarize(&[words("коротко", 10), words("длинно", 300)]).await;
+
+    assert_eq!(requests.len(), 2);
+    assert_eq!(summary.split_whitespace().count(), 10);
+    assert!(summary.starts_with("коротко"));
+}